@@ -0,0 +1,113 @@
+// Precompresses the frontend dist and records build info
+//
+// Brotli and gzip variants of compressible files are written to $OUT_DIR/dist
+// as <path>.br and <path>.gz, and embedded next to the originals, so they are
+// served without compressing on every request.
+//
+// Versions of notable dependencies are passed to the crate as
+// BUILD_DEP_VERSIONS. The git SHA and build time are not read here, as this
+// would rerun on every commit, recompressing the dist and relinking.
+//
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// reported by /api/version when in Cargo.lock
+const REPORTED_DEPS: &[&str] = &["axum", "cozo", "tokio", "yew"];
+
+// already compressed formats are not worth it
+const COMPRESSIBLE: &[&str] = &[
+    "wasm", "js", "css", "html", "svg", "json", "ico", "txt", "map", "xml",
+];
+
+fn main() {
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let dist = manifest_dir.join("../frontend/dist");
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("dist");
+
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // stale variants of removed files are not to be embedded
+    let _ = std::fs::remove_dir_all(&out);
+    std::fs::create_dir_all(&out).unwrap();
+
+    // the dist is read at runtime otherwise, and frontend changes need no rebuild
+    if std::env::var_os("CARGO_FEATURE_EMBED_FRONTEND").is_some() {
+        println!("cargo:rerun-if-changed={}", dist.display());
+        compress_dir(&dist, &dist, &out);
+    }
+
+    build_info(&manifest_dir);
+}
+
+fn build_info(manifest_dir: &Path) {
+    let lock = manifest_dir.join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+    let lock = std::fs::read_to_string(lock).unwrap_or_default();
+    let lines = lock.lines().collect::<Vec<_>>();
+    let mut versions = vec![];
+    // a package's version follows its name
+    for pair in lines.windows(2) {
+        let name = pair[0]
+            .strip_prefix("name = \"")
+            .and_then(|v| v.strip_suffix('"'));
+        let version = pair[1]
+            .strip_prefix("version = \"")
+            .and_then(|v| v.strip_suffix('"'));
+        if let (Some(name), Some(version)) = (name, version) {
+            if REPORTED_DEPS.contains(&name) {
+                versions.push(format!("{name}={version}"));
+            }
+        }
+    }
+
+    println!("cargo:rustc-env=BUILD_DEP_VERSIONS={}", versions.join(","));
+}
+
+fn compress_dir(root: &Path, dir: &Path, out: &Path) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            compress_dir(root, &path, out);
+            continue;
+        }
+
+        let compressible = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(false, |ext| COMPRESSIBLE.contains(&ext));
+        if !compressible {
+            continue;
+        }
+
+        let contents = std::fs::read(&path).unwrap();
+        let target = out.join(path.strip_prefix(root).unwrap());
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+
+        for (extension, compressed) in [("br", brotli(&contents)), ("gz", gzip(&contents))] {
+            // tiny files can grow
+            if compressed.len() < contents.len() {
+                let mut variant = target.clone().into_os_string();
+                variant.push(".");
+                variant.push(extension);
+                std::fs::write(variant, compressed).unwrap();
+            }
+        }
+    }
+}
+
+fn brotli(contents: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        writer.write_all(contents).unwrap();
+    }
+    compressed
+}
+
+fn gzip(contents: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(contents).unwrap();
+    encoder.finish().unwrap()
+}
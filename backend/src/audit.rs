@@ -0,0 +1,80 @@
+// Audit log of admin actions
+//
+
+use crate::{conf::Conf, db, startup};
+
+#[derive(Debug, Clone, Copy)]
+pub enum AuditAction {
+    Login,
+    Logout,
+    PasswordChange,
+    ArticleCreate,
+    ArticleUpdate,
+    ArticleDelete,
+    SessionRevoke,
+    SessionRevokeOthers,
+    PasskeyRegister,
+    FeatureFlagSet,
+    MediaUpload,
+}
+
+impl AsRef<str> for AuditAction {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Login => "login",
+            Self::Logout => "logout",
+            Self::PasswordChange => "password_change",
+            Self::ArticleCreate => "article_create",
+            Self::ArticleUpdate => "article_update",
+            Self::ArticleDelete => "article_delete",
+            Self::SessionRevoke => "session_revoke",
+            Self::SessionRevokeOthers => "session_revoke_others",
+            Self::PasskeyRegister => "passkey_register",
+            Self::FeatureFlagSet => "feature_flag_set",
+            Self::MediaUpload => "media_upload",
+        }
+    }
+}
+
+pub struct AuditRecord<'a> {
+    pub actor: &'a str,
+    pub action: AuditAction,
+    pub target_id: &'a str,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl AuditRecord<'_> {
+    /// Writes the record to the audit_log relation
+    ///
+    /// The action has already happened by the time it's recorded,
+    /// so a failed write is logged instead of failing the request
+    #[tracing::instrument(name = "Record admin action", skip_all)]
+    pub fn write(self, db: &cozo::DbInstance, conf: &Conf, h: &hyper::HeaderMap) {
+        let ip = startup::ip_address(h);
+
+        let entry = interfacing::AuditLogEntry {
+            actor: self.actor.into(),
+            action: self.action.as_ref().into(),
+            target_id: self.target_id.into(),
+            timestamp: interfacing::EndpointHit::formatted_now(),
+            hashed_ip: startup::hash_ip(ip, conf),
+            before: self.before,
+            after: self.after,
+        };
+
+        let _result = db::q::put_audit_log_entry(db, entry).map_err(|e| tracing::error!("{e:?}"));
+    }
+}
+
+// markdown is left out, it's too long to be read from a log
+pub fn article_summary(article: &interfacing::Article) -> String {
+    format!(
+        "title: {:?}, public_id: {:?}, draft: {}, tags: {:?}, markdown: {} bytes",
+        article.title,
+        article.public_id,
+        article.draft,
+        article.tags,
+        article.markdown.len()
+    )
+}
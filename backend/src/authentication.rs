@@ -4,7 +4,7 @@ use crate::{
     trace::spawn_blocking_with_tracing,
 };
 use anyhow::Context;
-use axum_sessions::extractors::ReadableSession;
+use axum_sessions::async_session::Session;
 use secrecy::{ExposeSecret, SecretString};
 
 #[derive(Clone)]
@@ -47,7 +47,7 @@ pub async fn validate_credentials(
     Ok(())
 }
 
-pub fn reject_anonymous_users(session: &ReadableSession) -> ApiResult<String> {
+pub fn reject_anonymous_users(session: &Session) -> ApiResult<String> {
     let username: Option<String> = session.get("username");
 
     match username {
@@ -0,0 +1,439 @@
+// Command line interface of the backend executable
+//
+// Commands other than `serve` work against the configured database directly,
+// so the server is better stopped for the ones that write.
+//
+
+use crate::{
+    conf::{Conf, DbConf, DbStorageEngine},
+    db,
+};
+use anyhow::Context;
+use std::io::{BufRead, IsTerminal};
+use std::path::PathBuf;
+
+#[derive(clap::Parser, Debug)]
+#[command(about = "Personal site backend")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Serve the site, the default
+    Serve,
+    /// Generate a new session signing key and retire the oldest ones
+    RotateSessionKey {
+        /// How many keys to keep, including the new one
+        #[arg(long, default_value_t = 2)]
+        keep: usize,
+    },
+    /// Manage users
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Export and import articles as JSON
+    #[command(subcommand)]
+    Articles(ArticlesCommand),
+    /// Back up, restore and migrate the database
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// Manage stored sessions
+    #[command(subcommand)]
+    Sessions(SessionsCommand),
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum UserCommand {
+    /// Create a user, the password is read from stdin
+    Create { username: String },
+    /// Set a new password, read from stdin, and end sessions of the user
+    ResetPassword { username: String },
+    /// List usernames
+    List,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum ArticlesCommand {
+    /// Write all articles to a file, or to stdout
+    Export { path: Option<PathBuf> },
+    /// Read articles from a file, or from stdin
+    ///
+    /// Articles with known ids or public ids are updated, others are created
+    Import { path: Option<PathBuf> },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum DbCommand {
+    /// Write all relations to a JSON file
+    Backup { path: PathBuf },
+    /// Put rows of a backup into the database
+    Restore { path: PathBuf },
+    /// Create missing relations and upgrade legacy ones
+    Migrate,
+    /// Copy every relation into an empty database, possibly of another engine
+    ///
+    /// To move to the copy, point db.storage_engine and db.path at it
+    Copy {
+        /// Memory, SQLite, Sled or RocksDB
+        #[arg(long)]
+        engine: DbStorageEngine,
+        #[arg(long)]
+        path: String,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum SessionsCommand {
+    /// Remove expired sessions
+    Purge {
+        /// Remove all sessions, logging everyone out
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective configuration with secrets redacted
+    Print,
+}
+
+/// Runs any command but `serve`
+pub fn run(conf: &Conf, command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Serve => unreachable!("served by the caller"),
+        Command::RotateSessionKey { keep } => rotate_session_key(conf, keep),
+        Command::User(command) => user(conf, command),
+        Command::Articles(command) => articles(conf, command),
+        Command::Db(command) => database(conf, command),
+        Command::Sessions(SessionsCommand::Purge { all }) => purge_sessions(conf, all),
+        Command::Config(ConfigCommand::Print) => {
+            println!("Env: {}", conf.env);
+            println!("{:#?}", conf.redacted());
+            Ok(())
+        }
+    }
+}
+
+pub fn rotate_session_key(conf: &Conf, keep: usize) -> anyhow::Result<()> {
+    let keys = crate::session_keys::SessionKeys::load(conf)?.rotate(keep);
+
+    match conf.session_secret {
+        // configuration is not ours to rewrite
+        Some(_) => {
+            println!("Set session_secret to:");
+            println!("{}", keys.to_hex_list().join(","));
+        }
+        None => {
+            keys.save()?;
+            println!("Session keys rotated, {} kept", keys.count());
+        }
+    }
+
+    Ok(())
+}
+
+// migrated the same way as on serve
+fn open_db(conf: &Conf) -> anyhow::Result<cozo::DbInstance> {
+    Ok(db::start_db(conf.db.db_instance()?))
+}
+
+fn user(conf: &Conf, command: UserCommand) -> anyhow::Result<()> {
+    run_user(&open_db(conf)?, command, read_password)
+}
+
+fn run_user(
+    db: &cozo::DbInstance,
+    command: UserCommand,
+    read_password: impl FnOnce() -> anyhow::Result<String>,
+) -> anyhow::Result<()> {
+    let read_password = || {
+        let password = read_password()?;
+        anyhow::ensure!(!password.is_empty(), "empty password");
+        Ok(password)
+    };
+
+    match command {
+        UserCommand::Create { username } => {
+            anyhow::ensure!(
+                db::q::find_user_by_username(db, &username)?.is_none(),
+                "user {username} already exists"
+            );
+
+            let pwd_hash = auth::hash_pwd(read_password()?.as_bytes())?;
+            db::q::put_user(db, &username, &pwd_hash)?;
+            println!("User {username} created");
+        }
+        UserCommand::ResetPassword { username } => {
+            db::q::find_user_by_username(db, &username)?
+                .with_context(|| format!("user {username} does not exist"))?;
+
+            let pwd_hash = auth::hash_pwd(read_password()?.as_bytes())?;
+            db::q::update_user_pwd_hash(db, &username, &pwd_hash)?;
+            db::q::rm_sessions_by_username(db, &username)?;
+            println!("Password of {username} reset");
+        }
+        UserCommand::List => {
+            for username in db::q::find_usernames(db)? {
+                println!("{username}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// prompted for without echo on a terminal, read as a line when piped
+fn read_password() -> anyhow::Result<String> {
+    let stdin = std::io::stdin();
+
+    let password = if stdin.is_terminal() {
+        rpassword::prompt_password("Password: ")?
+    } else {
+        let mut password = String::new();
+        stdin.lock().read_line(&mut password)?;
+        password.trim_end_matches(['\r', '\n']).to_owned()
+    };
+
+    Ok(password)
+}
+
+fn articles(conf: &Conf, command: ArticlesCommand) -> anyhow::Result<()> {
+    let db = &open_db(conf)?;
+
+    match command {
+        ArticlesCommand::Export { path } => {
+            let articles = db::q::find_articles(db)?;
+            let json = serde_json::to_string_pretty(&articles)?;
+
+            match path {
+                Some(path) => {
+                    std::fs::write(&path, json)
+                        .with_context(|| format!("failed to write {}", path.display()))?;
+                    eprintln!("{} articles exported", articles.len());
+                }
+                None => println!("{json}"),
+            }
+        }
+        ArticlesCommand::Import { path } => {
+            let json = match path {
+                Some(path) => std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            let articles: Vec<interfacing::ArticleWithId> =
+                serde_json::from_str(&json).context("malformed articles")?;
+
+            let (mut created, mut updated) = (0, 0);
+            for article in articles {
+                let existing = match db::q::find_article_by_id(db, &article.id)? {
+                    Some(existing) => Some(existing),
+                    None => db::q::find_article_by_public_id(db, &article.body().public_id)?,
+                };
+
+                let article = match existing {
+                    Some(existing) => {
+                        let article = interfacing::ArticleWithId {
+                            id: existing.id,
+                            body: article.body,
+                        };
+                        db::q::update_article(db, article.clone())?;
+                        updated += 1;
+                        article
+                    }
+                    None => {
+                        db::q::put_article(db, article.body.clone())?;
+                        created += 1;
+                        interfacing::ArticleWithId {
+                            id: db::q::find_article_by_public_id(db, &article.body().public_id)?
+                                .context("imported article not found")?
+                                .id,
+                            body: article.body,
+                        }
+                    }
+                };
+                // tags are kept in article_meta
+                let before = db::q::find_article_meta_by_id(db, &article.id)?;
+                db::q::put_article_meta(db, db::q::ArticleMeta::saved(&article, before))?;
+            }
+
+            eprintln!("{created} articles created, {updated} updated");
+        }
+    }
+
+    Ok(())
+}
+
+fn database(conf: &Conf, command: DbCommand) -> anyhow::Result<()> {
+    match command {
+        DbCommand::Backup { path } => {
+            let db = &open_db(conf)?;
+            let relations = db::q::find_relation_names(db)?;
+
+            let backup = db
+                .export_relations(relations.iter().map(String::as_str))
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+            std::fs::write(&path, serde_json::to_string(&backup)?)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!("{} relations backed up", backup.len());
+        }
+        DbCommand::Restore { path } => {
+            let db = &open_db(conf)?;
+
+            let backup = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let backup: std::collections::BTreeMap<String, cozo::NamedRows> =
+                serde_json::from_str(&backup).context("malformed backup")?;
+
+            let count = backup.len();
+            db.import_relations(backup)
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+            println!("{count} relations restored");
+        }
+        DbCommand::Copy { engine, path } => {
+            let from = &open_db(conf)?;
+            let to = &DbConf {
+                storage_engine: engine,
+                path,
+            }
+            .db_instance()?;
+
+            anyhow::ensure!(
+                db::q::find_relation_names(to)?.is_empty(),
+                "target database is not empty"
+            );
+
+            let count = db::copy_db(from, to)?;
+            println!("{count} relations copied");
+        }
+        DbCommand::Migrate => {
+            open_db(conf)?;
+            println!("Database migrated");
+        }
+    }
+
+    Ok(())
+}
+
+fn purge_sessions(conf: &Conf, all: bool) -> anyhow::Result<()> {
+    let db = &open_db(conf)?;
+
+    if all {
+        db::q::rm_all_sessions(db)?;
+        println!("All sessions removed");
+    } else {
+        db::q::rm_expired_sessions(db, &interfacing::EndpointHit::formatted_now())?;
+        println!("Expired sessions removed");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // in memory, migrated as on serve
+    fn temporary_db() -> cozo::DbInstance {
+        db::start_db(cozo::DbInstance::default())
+    }
+
+    fn password(password: &str) -> impl FnOnce() -> anyhow::Result<String> + '_ {
+        move || Ok(password.to_owned())
+    }
+
+    fn verifies(db: &cozo::DbInstance, username: &str, password: &str) -> bool {
+        let user = db::q::find_user_by_username(db, username)
+            .unwrap()
+            .expect("user to exist");
+        auth::verify_password_hash(user.pwd_hash, password.as_bytes()).is_ok()
+    }
+
+    #[test]
+    fn user_created() {
+        let db = &temporary_db();
+        let create = || UserCommand::Create {
+            username: "editor".into(),
+        };
+
+        run_user(db, create(), password("first")).unwrap();
+        assert!(verifies(db, "editor", "first"));
+
+        let error = run_user(db, create(), password("second")).unwrap_err();
+        assert_eq!(error.to_string(), "user editor already exists");
+        assert!(verifies(db, "editor", "first"));
+
+        let error = run_user(
+            db,
+            UserCommand::Create {
+                username: "other".into(),
+            },
+            password(""),
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "empty password");
+    }
+
+    #[test]
+    fn password_reset_ends_sessions() {
+        let db = &temporary_db();
+        run_user(
+            db,
+            UserCommand::Create {
+                username: "editor".into(),
+            },
+            password("first"),
+        )
+        .unwrap();
+        db::q::put_session(
+            db,
+            db::q::StoredSession {
+                id: "id".into(),
+                value: "value".into(),
+                username: Some("editor".into()),
+                expires_at: None,
+                created_at: "2023-01-01T00:00:00Z".into(),
+                last_seen_at: "2023-01-01T00:00:00Z".into(),
+                user_agent_class: None,
+                hashed_ip: None,
+            },
+        )
+        .unwrap();
+
+        run_user(
+            db,
+            UserCommand::ResetPassword {
+                username: "editor".into(),
+            },
+            password("second"),
+        )
+        .unwrap();
+
+        assert!(verifies(db, "editor", "second"));
+        assert!(!verifies(db, "editor", "first"));
+        assert!(db::q::find_sessions_by_username(db, "editor")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn unknown_user_password_not_reset() {
+        let db = &temporary_db();
+
+        let command = UserCommand::ResetPassword {
+            username: "unknown".into(),
+        };
+        let error =
+            run_user(db, command, || panic!("password read for an unknown user")).unwrap_err();
+
+        assert_eq!(error.to_string(), "user unknown does not exist");
+        assert!(db::q::find_user_by_username(db, "unknown")
+            .unwrap()
+            .is_none());
+    }
+}
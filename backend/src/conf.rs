@@ -2,7 +2,10 @@
 //
 
 use serde::Deserialize;
-use serde_aux::field_attributes::deserialize_number_from_string as de_num;
+use serde_aux::field_attributes::{
+    deserialize_number_from_string as de_num, deserialize_option_number_from_string as de_opt_num,
+};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 static ENV_PREFIX: &str = "BE";
@@ -35,15 +38,76 @@ pub struct EnvConf {
     pub host: String,
     pub db: DbConf,
     pub log: Log,
+    pub session: SessionConf,
+    // served over plain HTTP when not configured
+    pub tls: Option<TlsConf>,
+    pub shutdown: ShutdownConf,
+    pub static_files: StaticFilesConf,
+    #[serde(default)]
+    pub frontend: FrontendConf,
+    pub media: MediaConf,
+    pub site: SiteConf,
+    pub webauthn: WebauthnConf,
+    // sign in through an identity provider, when configured
+    pub oidc: Option<OidcConf>,
+    #[serde(default)]
+    pub metrics: MetricsConf,
 
     pub features: EnvFeatures,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum DbStorageEngine {
     Memory,
     SQLite,
     Sled,
+    RocksDB,
+}
+
+impl DbStorageEngine {
+    fn cozo_engine(&self) -> &'static str {
+        match self {
+            Self::Memory => "mem",
+            Self::SQLite => "sqlite",
+            Self::Sled => "sled",
+            Self::RocksDB => "rocksdb",
+        }
+    }
+
+    // storages are optional features of cozo
+    fn compiled_in(&self) -> bool {
+        match self {
+            Self::Memory => true,
+            Self::SQLite => cfg!(feature = "storage-sqlite"),
+            Self::Sled => cfg!(feature = "storage-sled"),
+            Self::RocksDB => cfg!(feature = "storage-rocksdb"),
+        }
+    }
+
+    fn feature(&self) -> &'static str {
+        match self {
+            Self::Memory => "",
+            Self::SQLite => "storage-sqlite",
+            Self::Sled => "storage-sled",
+            Self::RocksDB => "storage-rocksdb",
+        }
+    }
+}
+
+impl std::str::FromStr for DbStorageEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "sqlite" => Ok(Self::SQLite),
+            "sled" => Ok(Self::Sled),
+            "rocksdb" => Ok(Self::RocksDB),
+            other => Err(format!(
+                "{other} is not a storage engine, use one of Memory, SQLite, Sled, RocksDB"
+            )),
+        }
+    }
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -53,22 +117,230 @@ pub struct DbConf {
 }
 
 impl DbConf {
-    pub fn db_instance(&self) -> cozo::DbInstance {
-        cozo::DbInstance::new(
-            match self.storage_engine {
-                DbStorageEngine::Memory => "mem",
-                DbStorageEngine::SQLite => "sqlite",
-                DbStorageEngine::Sled => "sled",
-            },
-            &self.path,
-            Default::default(),
-        )
-        .unwrap()
+    pub fn db_instance(&self) -> anyhow::Result<cozo::DbInstance> {
+        let engine = self.storage_engine;
+
+        anyhow::ensure!(
+            engine.compiled_in(),
+            "storage engine {engine:?} is not compiled in, build with `--features {}`",
+            engine.feature()
+        );
+
+        cozo::DbInstance::new(engine.cozo_engine(), &self.path, Default::default()).map_err(|e| {
+            anyhow::anyhow!(
+                "failed to open {engine:?} database at {:?}: {e:?}",
+                self.path
+            )
+        })
+    }
+}
+
+// named feature flags, overridable at runtime from the admin dashboard
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(try_from = "BTreeMap<String, FeatureFlagValue>")]
+pub struct EnvFeatures {
+    pub flags: BTreeMap<String, interfacing::FeatureFlag>,
+}
+
+// values set by environment variables come as strings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FeatureFlagValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl TryFrom<BTreeMap<String, FeatureFlagValue>> for EnvFeatures {
+    type Error = String;
+
+    fn try_from(value: BTreeMap<String, FeatureFlagValue>) -> Result<Self, Self::Error> {
+        let flags = value
+            .into_iter()
+            .map(|(name, value)| {
+                let flag = match value {
+                    FeatureFlagValue::Bool(enabled) => interfacing::FeatureFlag::Enabled(enabled),
+                    FeatureFlagValue::Int(percentage) => percentage_flag(&name, percentage)?,
+                    FeatureFlagValue::Float(percentage) => {
+                        return Err(format!(
+                            "feature {name} percentage {percentage} is not a whole number"
+                        ))
+                    }
+                    FeatureFlagValue::Str(s) => match (s.parse(), s.parse()) {
+                        (Ok(enabled), _) => interfacing::FeatureFlag::Enabled(enabled),
+                        (_, Ok(percentage)) => percentage_flag(&name, percentage)?,
+                        _ => {
+                            return Err(format!(
+                                "feature {name} is neither a bool nor a percentage"
+                            ))
+                        }
+                    },
+                };
+                Ok((name, flag))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { flags })
+    }
+}
+
+fn percentage_flag(name: &str, percentage: i64) -> Result<interfacing::FeatureFlag, String> {
+    match u8::try_from(percentage) {
+        Ok(percentage) if percentage <= 100 => Ok(interfacing::FeatureFlag::Percentage(percentage)),
+        _ => Err(format!(
+            "feature {name} percentage {percentage} is not within 0..=100"
+        )),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SessionConf {
+    // sessions expire after being unused for this long
+    #[serde(deserialize_with = "de_num")]
+    pub ttl_secs: u64,
+    // how often expired sessions are removed from the store
+    #[serde(deserialize_with = "de_num")]
+    pub purge_period_secs: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ShutdownConf {
+    // in-flight requests are dropped when not finished in time
+    #[serde(deserialize_with = "de_num")]
+    pub drain_timeout_secs: u64,
+}
+
+impl ShutdownConf {
+    pub fn drain_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.drain_timeout_secs)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct StaticFilesConf {
+    // served under /api/static, relative to the parent of the conf directory
+    pub root: std::path::PathBuf,
+    pub symlinks: SymlinkPolicy,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct FrontendConf {
+    // served from disk and reloaded on changes when set, otherwise embedded,
+    // relative to the parent of the conf directory
+    pub dist_path: Option<std::path::PathBuf>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MediaConf {
+    // uploads are stored in the media_blobs relation when not set,
+    // relative to the parent of the conf directory
+    pub dir: Option<std::path::PathBuf>,
+    // larger uploads are rejected
+    #[serde(deserialize_with = "de_num")]
+    pub max_size_bytes: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SiteConf {
+    // public address, links in feeds are absolute
+    pub base_url: String,
+    pub title: String,
+    pub description: String,
+    pub robots: RobotsConf,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RobotsConf {
+    // path prefixes crawlers are asked to stay out of
+    pub disallow: Vec<String>,
+}
+
+impl SiteConf {
+    /// Absolute link to a path of the site
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url.trim_end_matches('/'))
     }
 }
 
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct MetricsConf {
+    // scrapers send it as a bearer token
+    pub token: Option<secrecy::SecretString>,
+    // scrapers from these addresses need no token
+    #[serde(default)]
+    pub allowed_ips: Vec<std::net::IpAddr>,
+    // peers whose X-Forwarded-For tells the address of a scraper
+    #[serde(default)]
+    pub trusted_proxies: Vec<std::net::IpAddr>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SymlinkPolicy {
+    // symlinks are not served
+    Deny,
+    // symlinks are served when they resolve inside the root
+    WithinRoot,
+    // symlinks are served wherever they point
+    Follow,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TlsConf {
+    // PEM files, reloaded on SIGHUP
+    pub cert_path: String,
+    pub key_path: String,
+    // plain HTTP port redirecting to HTTPS
+    #[serde(default, deserialize_with = "de_opt_num")]
+    pub redirect_http_port: Option<u16>,
+}
+
 #[derive(Deserialize, Clone, Debug)]
-pub struct EnvFeatures {}
+pub struct WebauthnConf {
+    // domain passkeys are bound to, browsers don't accept IPs
+    pub rp_id: String,
+    pub rp_origin: String,
+    // when enabled, users with passkeys log in with password or single sign-on,
+    // then passkey, otherwise passkey replaces password
+    pub second_factor: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct OidcConf {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<secrecy::SecretString>,
+    // must point to the callback route, as registered with the provider
+    pub redirect_url: String,
+    #[serde(default = "OidcConf::default_scopes")]
+    pub scopes: Vec<String>,
+    // ID token claim mapped to usernames of the users relation
+    #[serde(default = "OidcConf::default_username_claim")]
+    pub username_claim: String,
+    // create users missing from the users relation, otherwise reject them
+    #[serde(default)]
+    pub provision_users: bool,
+}
+
+impl OidcConf {
+    pub fn default_scopes() -> Vec<String> {
+        vec!["openid".into(), "email".into(), "profile".into()]
+    }
+
+    pub fn default_username_claim() -> String {
+        "email".into()
+    }
+}
+
+impl SessionConf {
+    pub fn ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.ttl_secs)
+    }
+
+    pub fn purge_period(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.purge_period_secs)
+    }
+}
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Log {
@@ -104,12 +376,29 @@ impl EnvConf {
 
         let conf = conf_builder.unwrap();
 
-        match conf.try_deserialize() {
+        let mut conf: Self = match conf.try_deserialize() {
             Ok(conf) => conf,
             Err(e) => {
                 dbg!(&e);
                 Err(e).expect("correct config")
             }
+        };
+
+        // absolute roots are kept as is
+        if let Some(base) = conf_dir.parent() {
+            conf.static_files.root = base.join(&conf.static_files.root);
+            conf.frontend.dist_path = conf.frontend.dist_path.map(|path| base.join(path));
+            conf.media.dir = conf.media.dir.map(|dir| base.join(dir));
+        }
+
+        conf
+    }
+
+    /// For printing, secrets kept as SecretString are redacted by Debug already
+    pub fn redacted(&self) -> Self {
+        Self {
+            session_secret: self.session_secret.as_ref().map(|_| "[REDACTED]".into()),
+            ..self.clone()
         }
     }
 
@@ -123,8 +412,44 @@ impl EnvConf {
                 storage_engine: DbStorageEngine::Memory,
                 path: String::new(),
             },
-            features: EnvFeatures {},
+            features: EnvFeatures::default(),
             log: Log { pretty: false },
+            session: SessionConf {
+                ttl_secs: 24 * 60 * 60,
+                purge_period_secs: 60 * 60,
+            },
+            webauthn: WebauthnConf {
+                rp_id: "localhost".into(),
+                rp_origin: "http://localhost:8000".into(),
+                second_factor: false,
+            },
+            oidc: None,
+            metrics: MetricsConf::default(),
+            tls: None,
+            shutdown: ShutdownConf {
+                drain_timeout_secs: 5,
+            },
+            static_files: StaticFilesConf {
+                root: concat!(env!("CARGO_MANIFEST_DIR"), "/static").into(),
+                symlinks: SymlinkPolicy::WithinRoot,
+            },
+            frontend: FrontendConf {
+                // nothing else to serve without the embedded frontend
+                dist_path: (!cfg!(feature = "embed-frontend"))
+                    .then(|| concat!(env!("CARGO_MANIFEST_DIR"), "/../frontend/dist").into()),
+            },
+            media: MediaConf {
+                dir: None,
+                max_size_bytes: 10 * 1024 * 1024,
+            },
+            site: SiteConf {
+                base_url: "http://localhost:8000".into(),
+                title: "phantie.site".into(),
+                description: "Articles".into(),
+                robots: RobotsConf {
+                    disallow: vec!["/admin".into(), "/api/admin".into()],
+                },
+            },
         }
     }
 }
@@ -198,6 +523,83 @@ mod tests {
         assert!(!Env::derive().prod());
     }
 
+    #[test]
+    fn storage_engines() {
+        let memory = DbConf {
+            storage_engine: DbStorageEngine::Memory,
+            path: String::new(),
+        };
+        assert!(memory.db_instance().is_ok());
+
+        let engines = [
+            DbStorageEngine::SQLite,
+            DbStorageEngine::Sled,
+            DbStorageEngine::RocksDB,
+        ];
+        for engine in engines.into_iter().filter(|engine| !engine.compiled_in()) {
+            let conf = DbConf {
+                storage_engine: engine,
+                path: String::new(),
+            };
+            let e = conf.db_instance().unwrap_err();
+            assert!(e.to_string().contains(engine.feature()), "{e}");
+        }
+
+        assert_eq!("sqlite".parse(), Ok(DbStorageEngine::SQLite));
+        assert_eq!("RocksDB".parse(), Ok(DbStorageEngine::RocksDB));
+        assert!("postgres".parse::<DbStorageEngine>().is_err());
+    }
+
+    #[test]
+    fn redacted() {
+        let conf = EnvConf::test_default();
+        let secret = conf.session_secret.clone().unwrap();
+
+        let printed = format!("{:?}", conf.redacted());
+        assert!(!printed.contains(&secret));
+        assert!(printed.contains("[REDACTED]"));
+    }
+
+    #[test]
+    fn features() {
+        use interfacing::FeatureFlag;
+
+        let features: EnvFeatures = serde_json::from_str(
+            r#"{"snake_game": true, "users_online": 25, "from_env": "false", "from_env_pct": "10"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            features.flags,
+            [
+                ("from_env".to_string(), FeatureFlag::Enabled(false)),
+                ("from_env_pct".to_string(), FeatureFlag::Percentage(10)),
+                ("snake_game".to_string(), FeatureFlag::Enabled(true)),
+                ("users_online".to_string(), FeatureFlag::Percentage(25)),
+            ]
+            .into()
+        );
+
+        assert!(serde_json::from_str::<EnvFeatures>(r#"{"snake_game": "maybe"}"#).is_err());
+    }
+
+    #[test]
+    fn invalid_percentages_rejected() {
+        for invalid in ["101", "300", "-1", "2.5", r#""101""#, r#""-5""#] {
+            let error =
+                serde_json::from_str::<EnvFeatures>(&format!(r#"{{"snake_game": {invalid}}}"#))
+                    .unwrap_err()
+                    .to_string();
+            assert!(error.contains("feature snake_game"), "{invalid}: {error}");
+        }
+
+        let features: EnvFeatures = serde_json::from_str(r#"{"none": 0, "all": "100"}"#).unwrap();
+        assert_eq!(
+            features.flags["all"],
+            interfacing::FeatureFlag::Percentage(100)
+        );
+    }
+
     #[test]
     fn env() {
         #[derive(Debug)]
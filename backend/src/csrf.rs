@@ -0,0 +1,202 @@
+// CSRF defence for cookie authenticated mutations
+//
+// Two independent checks:
+//  - browsers must not report the request as cross-site
+//    (Sec-Fetch-Site, or Origin against Host when fetch metadata is missing)
+//  - logged in sessions must echo their token in the CSRF_TOKEN_HEADER
+//
+
+use crate::{
+    conf::Conf,
+    error::{ApiError, ApiResult},
+};
+use anyhow::Context;
+use axum::extract::Extension;
+use axum_sessions::extractors::{ReadableSession, WritableSession};
+use interfacing::CSRF_TOKEN_HEADER;
+
+static SESSION_KEY: &str = "csrf_token";
+
+/// Returns the token of a session, issuing one if the session has none yet
+pub fn session_token(session: &mut WritableSession) -> ApiResult<String> {
+    if let Some(token) = session.get::<String>(SESSION_KEY) {
+        return Ok(token);
+    }
+
+    let token = {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        let random_bytes: Vec<u8> = (0..32).map(|_| rng.gen::<u8>()).collect();
+        hex::encode(random_bytes)
+    };
+
+    session
+        .insert(SESSION_KEY, token.clone())
+        .context("Failed to register CSRF token in a session")?;
+
+    Ok(token)
+}
+
+/// Forgets the token, so that a new one is issued on the next request
+pub fn forget_session_token(session: &mut WritableSession) {
+    session.remove(SESSION_KEY);
+}
+
+// to be used with route_layer over mutating routes
+pub async fn reject_cross_site_requests<B>(
+    h: hyper::HeaderMap,
+    Extension(conf): Extension<Conf>,
+    session: ReadableSession,
+    request: hyper::http::Request<B>,
+    next: axum::middleware::Next<B>,
+) -> ApiResult<axum::response::Response> {
+    let logged_in = session.get::<String>("username").is_some();
+    let expected_token = session.get::<String>(SESSION_KEY);
+    // handlers may need a writable session, and it can't be acquired while this one lives
+    drop(session);
+
+    reject_cross_site_origin(&h, &conf)?;
+
+    // anonymous requests can't do any harm, handlers reject them on their own
+    if logged_in {
+        reject_invalid_token(&h, expected_token.as_deref())?;
+    }
+
+    Ok(next.run(request).await)
+}
+
+fn reject_cross_site_origin(h: &hyper::HeaderMap, conf: &Conf) -> ApiResult<()> {
+    fn rejection(reason: &str) -> ApiError {
+        ApiError::CsrfError(anyhow::anyhow!("{reason}"))
+    }
+
+    if let Some(site) = h.get("sec-fetch-site") {
+        return match site.as_bytes() {
+            b"same-origin" => Ok(()),
+            _ => Err(rejection("cross-site request")),
+        };
+    }
+
+    // when developing with frontend dev server, the Host header is rewritten by its proxy
+    if conf.env.local() {
+        return Ok(());
+    }
+
+    let origin = match h.get(hyper::header::ORIGIN) {
+        // not a browser or an old one, the token check still applies
+        None => return Ok(()),
+        Some(origin) => origin
+            .to_str()
+            .ok()
+            .and_then(|v| url::Url::parse(v).ok())
+            .ok_or_else(|| rejection("malformed Origin"))?,
+    };
+
+    let origin_host = match (origin.host_str(), origin.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_owned(),
+        (None, _) => return Err(rejection("Origin without host")),
+    };
+
+    match h.get(hyper::header::HOST).map(|v| v.to_str()) {
+        Some(Ok(host)) if host == origin_host => Ok(()),
+        _ => Err(rejection("Origin does not match Host")),
+    }
+}
+
+fn reject_invalid_token(h: &hyper::HeaderMap, expected_token: Option<&str>) -> ApiResult<()> {
+    let provided_token = h.get(CSRF_TOKEN_HEADER).map(|v| v.as_bytes());
+
+    match (expected_token, provided_token) {
+        (Some(expected), Some(provided)) if constant_time_eq(expected.as_bytes(), provided) => {
+            Ok(())
+        }
+        (None, _) => Err(ApiError::CsrfError(anyhow::anyhow!(
+            "session has no token issued"
+        ))),
+        _ => Err(ApiError::CsrfError(anyhow::anyhow!("invalid token"))),
+    }
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::{Env, EnvConf};
+    use claim::{assert_err, assert_ok};
+
+    fn conf(env: Env) -> Conf {
+        Conf::new(env, EnvConf::test_default())
+    }
+
+    fn headers(values: &[(&'static str, &'static str)]) -> hyper::HeaderMap {
+        values
+            .iter()
+            .map(|(k, v)| (k.parse().unwrap(), v.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn fetch_metadata() {
+        let conf = conf(Env::Prod);
+
+        assert_ok!(reject_cross_site_origin(
+            &headers(&[("sec-fetch-site", "same-origin")]),
+            &conf
+        ));
+        assert_err!(reject_cross_site_origin(
+            &headers(&[("sec-fetch-site", "cross-site")]),
+            &conf
+        ));
+        assert_err!(reject_cross_site_origin(
+            &headers(&[("sec-fetch-site", "same-site")]),
+            &conf
+        ));
+    }
+
+    #[test]
+    fn origin() {
+        let conf = conf(Env::Prod);
+
+        assert_ok!(reject_cross_site_origin(&headers(&[]), &conf));
+        assert_ok!(reject_cross_site_origin(
+            &headers(&[("origin", "https://phantie.site"), ("host", "phantie.site")]),
+            &conf
+        ));
+        assert_ok!(reject_cross_site_origin(
+            &headers(&[
+                ("origin", "http://127.0.0.1:8000"),
+                ("host", "127.0.0.1:8000")
+            ]),
+            &conf
+        ));
+        assert_err!(reject_cross_site_origin(
+            &headers(&[("origin", "https://evil.site"), ("host", "phantie.site")]),
+            &conf
+        ));
+        assert_err!(reject_cross_site_origin(
+            &headers(&[("origin", "null"), ("host", "phantie.site")]),
+            &conf
+        ));
+    }
+
+    #[test]
+    fn token() {
+        assert_ok!(reject_invalid_token(
+            &headers(&[(CSRF_TOKEN_HEADER, "token")]),
+            Some("token")
+        ));
+        assert_err!(reject_invalid_token(
+            &headers(&[(CSRF_TOKEN_HEADER, "other")]),
+            Some("token")
+        ));
+        assert_err!(reject_invalid_token(&headers(&[]), Some("token")));
+        assert_err!(reject_invalid_token(
+            &headers(&[(CSRF_TOKEN_HEADER, "token")]),
+            None
+        ));
+    }
+}
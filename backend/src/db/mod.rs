@@ -2,54 +2,56 @@ use cozo::*;
 pub mod q;
 
 pub fn start_db(db: DbInstance) -> DbInstance {
-    let db = &db;
+    migrate(&db);
+    create_default_admin(&db);
+    db
+}
+
+/// Creates missing relations and upgrades legacy ones
+pub fn migrate(db: &DbInstance) {
     {
         // Users:
         // Create missing tables: users
-        // Create missing user: admin
-
         if q::ensure_users_table(db).is_err() {
             let result = q::create_users_table(db);
             assert!(result.is_ok());
         }
+    }
 
-        struct UserData {
-            username: String,
-            default_pwd: String,
-        }
-
-        let admin_user_data = UserData {
-            username: "admin".into(),
-            default_pwd: "def".into(),
-        };
-
-        // if admin has not been created - create one with default password
-        if q::find_user_by_username(db, &admin_user_data.username)
-            .unwrap()
-            .is_none()
-        {
-            q::put_user(
-                db,
-                &admin_user_data.username,
-                &auth::hash_pwd(admin_user_data.default_pwd.as_bytes()).unwrap(),
-            )
-            .unwrap();
+    {
+        // Passkeys:
+        // Create missing tables: passkeys
+        if q::ensure_passkeys_table(db).is_err() {
+            let result = q::create_passkeys_table(db);
+            assert!(result.is_ok());
         }
     }
 
     {
         // Articles:
-        // Create missing tables: articles
+        // Create missing tables: articles, article_meta
         if q::ensure_articles_table(db).is_err() {
             let result = q::create_articles_table(db);
             assert!(result.is_ok());
         }
+        if q::ensure_article_meta_table(db).is_err() {
+            let result = q::create_article_meta_table(db);
+            assert!(result.is_ok());
+        }
     }
 
     {
         // Sessions:
         // Create missing tables: sessions
+        // Replace legacy table: sessions without metadata columns
         if q::ensure_sessions_table(db).is_err() {
+            // legacy sessions have no metadata to fill the columns with,
+            // so they are dropped and their owners have to log in again
+            if q::ensure_legacy_sessions_table(db).is_ok() {
+                tracing::info!("dropping legacy sessions table");
+                q::remove_sessions_table(db).unwrap();
+            }
+
             let result = q::create_sessions_table(db);
             assert!(result.is_ok());
         }
@@ -65,7 +67,77 @@ pub fn start_db(db: DbInstance) -> DbInstance {
         }
     }
 
-    db.clone()
+    {
+        // Audit log:
+        // Create missing tables: audit_log
+        if q::ensure_audit_log_table(db).is_err() {
+            let result = q::create_audit_log_table(db);
+            assert!(result.is_ok());
+        }
+    }
+
+    {
+        // Feature flags:
+        // Create missing tables: feature_flags
+        if q::ensure_feature_flags_table(db).is_err() {
+            let result = q::create_feature_flags_table(db);
+            assert!(result.is_ok());
+        }
+    }
+
+    {
+        // Media:
+        // Create missing tables: media, media_blobs
+        if q::ensure_media_table(db).is_err() {
+            let result = q::create_media_table(db);
+            assert!(result.is_ok());
+        }
+        if q::ensure_media_blobs_table(db).is_err() {
+            let result = q::create_media_blobs_table(db);
+            assert!(result.is_ok());
+        }
+    }
+}
+
+fn create_default_admin(db: &DbInstance) {
+    struct UserData {
+        username: String,
+        default_pwd: String,
+    }
+
+    let admin_user_data = UserData {
+        username: "admin".into(),
+        default_pwd: "def".into(),
+    };
+
+    // if admin has not been created - create one with default password
+    if q::find_user_by_username(db, &admin_user_data.username)
+        .unwrap()
+        .is_none()
+    {
+        q::put_user(
+            db,
+            &admin_user_data.username,
+            &auth::hash_pwd(admin_user_data.default_pwd.as_bytes()).unwrap(),
+        )
+        .unwrap();
+    }
+}
+
+/// Copies rows of every relation of `from` into `to`, returns the number of relations
+///
+/// `to` is migrated first, so that relations exist to put rows into
+pub fn copy_db(from: &DbInstance, to: &DbInstance) -> Result<usize> {
+    migrate(to);
+
+    let relations = q::find_relation_names(from)?;
+    let rows = from
+        .export_relations(relations.iter().map(String::as_str))
+        .map_err(Error::EngineError)?;
+    let count = rows.len();
+    to.import_relations(rows).map_err(Error::EngineError)?;
+
+    Ok(count)
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -136,6 +208,48 @@ mod tests {
 
         assert_eq!(&user.username, &user_data.username);
         assert_eq!(&user.pwd_hash, &user_data.pwd_hash);
+
+        assert_eq!(
+            q::find_usernames(db).expect("op to succeed"),
+            vec![user_data.username.clone()]
+        );
+    }
+
+    #[test]
+    fn copy_db_test() {
+        let from = &db();
+        super::migrate(from);
+        assert_ok!(q::put_user(from, "owner", "pwd_hash"));
+        assert_ok!(q::put_feature_flag_override(
+            from,
+            "snake_game",
+            interfacing::FeatureFlag::Enabled(false)
+        ));
+
+        let to = &db();
+        let count = super::copy_db(from, to).expect("op to succeed");
+        assert_eq!(count, q::find_relation_names(from).unwrap().len());
+
+        // no default admin in the copy
+        assert_eq!(q::find_usernames(to).unwrap(), vec!["owner".to_string()]);
+        assert_eq!(
+            q::find_feature_flag_overrides(to).unwrap(),
+            q::find_feature_flag_overrides(from).unwrap()
+        );
+    }
+
+    #[test]
+    fn relation_names_test() {
+        let db = &db();
+
+        assert!(q::find_relation_names(db)
+            .expect("op to succeed")
+            .is_empty());
+        super::start_db(db.clone());
+
+        let names = q::find_relation_names(db).expect("op to succeed");
+        assert!(names.contains(&"users".to_string()));
+        assert!(names.contains(&"articles".to_string()));
     }
 
     #[test]
@@ -145,6 +259,7 @@ mod tests {
         assert_err!(q::ensure_articles_table(db));
         assert_ok!(q::create_articles_table(db));
         assert_ok!(q::ensure_articles_table(db));
+        assert_ok!(q::create_article_meta_table(db));
 
         let assert_article_count =
             |count: usize| assert_eq!(q::find_articles(db).expect("op to succeed").len(), count);
@@ -170,6 +285,7 @@ mod tests {
                 markdown: "updated".into(),
                 title: "updated".into(),
                 draft: false,
+                tags: vec![],
             },
         };
 
@@ -181,10 +297,153 @@ mod tests {
 
         assert_eq!(&article, &updated_article_data);
 
+        let article = q::find_article_by_id(db, &updated_article_data.id)
+            .expect("op to succeed")
+            .expect("to find the article");
+        assert_eq!(&article, &updated_article_data);
+        assert_none!(q::find_article_by_id(db, "not-a-uuid").expect("op to succeed"));
+
         assert_ok!(q::rm_article(db, &updated_article_data.id));
         assert_article_count(0);
     }
 
+    #[test]
+    fn article_meta_test() {
+        let db = &db();
+
+        assert_err!(q::ensure_article_meta_table(db));
+        assert_ok!(q::create_article_meta_table(db));
+        assert_ok!(q::ensure_article_meta_table(db));
+        assert_ok!(q::create_articles_table(db));
+
+        assert_ok!(q::put_article(db, interfacing::Article::default()));
+        let article = q::find_article_by_public_id(db, "")
+            .expect("op to succeed")
+            .expect("to find the article");
+        // untagged without meta
+        assert!(article.body().tags.is_empty());
+        assert_none!(q::find_article_meta_by_id(db, &article.id).expect("op to succeed"));
+
+        let meta = q::ArticleMeta {
+            article_id: article.id.clone(),
+            tags: vec!["rust".into(), "web".into()],
+            published_at: None,
+            updated_at: "2023-01-01T00:00:00Z".into(),
+        };
+        assert_ok!(q::put_article_meta(db, meta.clone()));
+        assert_eq!(
+            q::find_article_meta_by_id(db, &article.id).expect("op to succeed"),
+            Some(meta.clone())
+        );
+
+        let published = q::ArticleMeta {
+            published_at: Some("2023-01-02T00:00:00Z".into()),
+            ..meta
+        };
+        assert_ok!(q::put_article_meta(db, published.clone()));
+        assert_eq!(
+            q::find_article_meta(db).expect("op to succeed"),
+            vec![published.clone()]
+        );
+
+        let articles = q::find_articles(db).expect("op to succeed");
+        assert_eq!(articles[0].body().tags, published.tags);
+        let article = q::find_article_by_id(db, &article.id)
+            .expect("op to succeed")
+            .expect("to find the article");
+        assert_eq!(article.body().tags, published.tags);
+
+        assert_ok!(q::rm_article_meta(db, &article.id));
+        assert!(q::find_article_meta(db).expect("op to succeed").is_empty());
+        assert_ok!(q::rm_article_meta(db, "not-a-uuid"));
+        assert_err!(q::put_article_meta(
+            db,
+            q::ArticleMeta {
+                article_id: "not-a-uuid".into(),
+                ..published
+            }
+        ));
+    }
+
+    #[test]
+    fn passkeys_test() {
+        let db = &db();
+
+        assert_err!(q::ensure_passkeys_table(db));
+        assert_ok!(q::create_passkeys_table(db));
+        assert_ok!(q::ensure_passkeys_table(db));
+        assert_ok!(q::create_users_table(db));
+
+        let passkey = q::StoredPasskey {
+            credential_id: "credential_id".into(),
+            username: "admin".into(),
+            name: "laptop".into(),
+            passkey: "{}".into(),
+            created_at: "2023-01-01T00:00:00Z".into(),
+        };
+
+        // tied to existing users
+        assert_ok!(q::put_passkey(db, passkey.clone()));
+        assert!(q::find_passkeys_by_username(db, "admin")
+            .expect("op to succeed")
+            .is_empty());
+
+        assert_ok!(q::put_user(db, "admin", "pwd_hash"));
+        assert_ok!(q::put_passkey(db, passkey.clone()));
+        assert_eq!(
+            q::find_passkeys_by_username(db, "admin").expect("op to succeed"),
+            vec![passkey]
+        );
+        assert!(q::find_passkeys_by_username(db, "other")
+            .expect("op to succeed")
+            .is_empty());
+    }
+
+    #[test]
+    fn feature_flags_test() {
+        use interfacing::FeatureFlag;
+        let db = &db();
+
+        assert_err!(q::ensure_feature_flags_table(db));
+        assert_ok!(q::create_feature_flags_table(db));
+        assert_ok!(q::ensure_feature_flags_table(db));
+
+        assert!(q::find_feature_flag_overrides(db)
+            .expect("op to succeed")
+            .is_empty());
+
+        assert_ok!(q::put_feature_flag_override(
+            db,
+            "snake_game",
+            FeatureFlag::Enabled(false)
+        ));
+        assert_ok!(q::put_feature_flag_override(
+            db,
+            "users_online",
+            FeatureFlag::Percentage(25)
+        ));
+        assert_eq!(
+            q::find_feature_flag_overrides(db).expect("op to succeed"),
+            [
+                ("snake_game".to_string(), FeatureFlag::Enabled(false)),
+                ("users_online".to_string(), FeatureFlag::Percentage(25)),
+            ]
+            .into()
+        );
+
+        // replaced
+        assert_ok!(q::put_feature_flag_override(
+            db,
+            "snake_game",
+            FeatureFlag::Percentage(50)
+        ));
+        assert_ok!(q::rm_feature_flag_override(db, "users_online"));
+        assert_eq!(
+            q::find_feature_flag_overrides(db).expect("op to succeed"),
+            [("snake_game".to_string(), FeatureFlag::Percentage(50))].into()
+        );
+    }
+
     #[test]
     fn sessions_test() {
         let db = &db();
@@ -193,23 +452,189 @@ mod tests {
         assert_ok!(q::create_sessions_table(db));
         assert_ok!(q::ensure_sessions_table(db));
 
-        #[derive(Default)]
-        struct SessionData {
-            id: String,
-            value: String,
-        }
-
-        let session_data = SessionData::default();
+        let session_data = q::StoredSession {
+            id: "id".into(),
+            value: "value".into(),
+            username: Some("admin".into()),
+            expires_at: Some("2023-01-02T00:00:00Z".into()),
+            created_at: "2023-01-01T00:00:00Z".into(),
+            last_seen_at: "2023-01-01T00:00:00Z".into(),
+            user_agent_class: Some("Firefox on Linux".into()),
+            hashed_ip: Some("hashed_ip".into()),
+        };
 
-        assert_ok!(q::put_session(db, &session_data.id, &session_data.value));
+        assert_ok!(q::put_session(db, session_data.clone()));
 
         let session = q::find_session_by_id(db, &session_data.id)
             .expect("op to succeed")
             .expect("to find the session");
         assert_eq!(&session, &session_data.value);
 
+        let sessions = q::find_sessions_by_username(db, "admin").expect("op to succeed");
+        assert_eq!(
+            sessions,
+            vec![interfacing::SessionInfo {
+                id: session_data.id.clone(),
+                current: false,
+                created_at: session_data.created_at.clone(),
+                last_seen_at: session_data.last_seen_at.clone(),
+                expires_at: session_data.expires_at.clone(),
+                user_agent_class: session_data.user_agent_class.clone(),
+                hashed_ip: session_data.hashed_ip.clone(),
+            }]
+        );
+
         assert_ok!(q::rm_session(db, &session_data.id));
         let session = q::find_session_by_id(db, &session_data.id).expect("op to succeed");
         assert_none!(session);
+
+        // anonymous, never expiring
+        let anonymous = q::StoredSession {
+            id: "anonymous".into(),
+            username: None,
+            expires_at: None,
+            user_agent_class: None,
+            hashed_ip: None,
+            ..session_data.clone()
+        };
+        let other = q::StoredSession {
+            id: "other".into(),
+            expires_at: Some("2023-01-03T00:00:00Z".into()),
+            ..session_data.clone()
+        };
+        assert_ok!(q::put_session(db, session_data.clone()));
+        assert_ok!(q::put_session(db, other.clone()));
+        assert_ok!(q::put_session(db, anonymous.clone()));
+
+        assert_ok!(q::rm_expired_sessions(db, "2023-01-02T12:00:00Z"));
+        assert_none!(q::find_session_by_id(db, &session_data.id).expect("op to succeed"));
+        assert_eq!(
+            q::find_sessions_by_username(db, "admin")
+                .expect("op to succeed")
+                .len(),
+            1
+        );
+
+        assert_ok!(q::put_session(db, session_data.clone()));
+        assert_ok!(q::rm_sessions_by_username_except(db, "admin", &other.id));
+        let sessions = q::find_sessions_by_username(db, "admin").expect("op to succeed");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(&sessions[0].id, &other.id);
+        assert!(q::find_session_by_id(db, &anonymous.id)
+            .expect("op to succeed")
+            .is_some());
+
+        assert_ok!(q::put_session(db, session_data.clone()));
+        assert_ok!(q::rm_sessions_by_username(db, "admin"));
+        assert!(q::find_sessions_by_username(db, "admin")
+            .expect("op to succeed")
+            .is_empty());
+        assert!(q::find_session_by_id(db, &anonymous.id)
+            .expect("op to succeed")
+            .is_some());
+
+        assert_ok!(q::put_session(db, other.clone()));
+        assert_ok!(q::rm_all_sessions(db));
+        assert_none!(q::find_session_by_id(db, &other.id).expect("op to succeed"));
+        assert_none!(q::find_session_by_id(db, &anonymous.id).expect("op to succeed"));
+    }
+
+    #[test]
+    fn legacy_sessions_table_replaced() {
+        let db = &db();
+
+        db.run_script(
+            "?[id, value] <- [] :create sessions { id: String => value: String }",
+            Default::default(),
+            cozo::ScriptMutability::Mutable,
+        )
+        .unwrap();
+        assert_ok!(q::ensure_legacy_sessions_table(db));
+        assert_err!(q::ensure_sessions_table(db));
+
+        super::start_db(db.clone());
+        assert_ok!(q::ensure_sessions_table(db));
+    }
+
+    #[test]
+    fn audit_log_test() {
+        let db = &db();
+
+        assert_err!(q::ensure_audit_log_table(db));
+        assert_ok!(q::create_audit_log_table(db));
+        assert_ok!(q::ensure_audit_log_table(db));
+
+        assert_eq!(
+            q::find_audit_log_entries(db).expect("op to succeed").len(),
+            0
+        );
+
+        let entry = interfacing::AuditLogEntry {
+            actor: "admin".into(),
+            action: "article_update".into(),
+            target_id: "id".into(),
+            timestamp: "2023-01-01T00:00:00Z".into(),
+            hashed_ip: "127.0.0.1".into(),
+            before: Some("before".into()),
+            after: None,
+        };
+
+        assert_ok!(q::put_audit_log_entry(db, entry.clone()));
+
+        let entries = q::find_audit_log_entries(db).expect("op to succeed");
+        assert_eq!(&entries, &[entry]);
+    }
+
+    #[test]
+    fn media_test() {
+        let db = &db();
+
+        assert_err!(q::ensure_media_table(db));
+        assert_ok!(q::create_media_table(db));
+        assert_ok!(q::ensure_media_table(db));
+        assert_err!(q::ensure_media_blobs_table(db));
+        assert_ok!(q::create_media_blobs_table(db));
+        assert_ok!(q::ensure_media_blobs_table(db));
+
+        assert!(q::find_media_files(db).expect("op to succeed").is_empty());
+        assert_none!(q::find_media_file_by_id(db, "missing.png").expect("op to succeed"));
+
+        let older = interfacing::MediaFile {
+            id: "0a1b.png".into(),
+            name: "diagram.png".into(),
+            mime: "image/png".into(),
+            size: 4,
+            uploaded_by: "admin".into(),
+            uploaded_at: "2023-01-01T00:00:00Z".into(),
+        };
+        let newer = interfacing::MediaFile {
+            id: "2c3d.pdf".into(),
+            name: "paper.pdf".into(),
+            mime: "application/pdf".into(),
+            uploaded_at: "2023-01-02T00:00:00Z".into(),
+            ..older.clone()
+        };
+
+        assert_ok!(q::put_media_file(db, older.clone()));
+        assert_ok!(q::put_media_file(db, newer.clone()));
+        assert_eq!(
+            q::find_media_files(db).expect("op to succeed"),
+            vec![newer, older.clone()]
+        );
+        assert_eq!(
+            q::find_media_file_by_id(db, &older.id).expect("op to succeed"),
+            Some(older.clone())
+        );
+
+        assert_none!(q::find_media_blob(db, &older.id).expect("op to succeed"));
+        assert_ok!(q::put_media_blob(
+            db,
+            &older.id,
+            vec![0x89, b'P', b'N', b'G']
+        ));
+        assert_eq!(
+            q::find_media_blob(db, &older.id).expect("op to succeed"),
+            Some(vec![0x89, b'P', b'N', b'G'])
+        );
     }
 }
@@ -12,14 +12,14 @@ mod imports {
 #[tracing::instrument(name = "Create users table", skip_all)]
 pub fn create_users_table(db: &DbInstance) -> OpResult {
     let script = include_str!("users/create_users_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "create_users_table", script, Default::default());
     op_result(result)
 }
 
 #[tracing::instrument(name = "Ensure users table", skip_all)]
 pub fn ensure_users_table(db: &DbInstance) -> OpResult {
     let script = include_str!("users/ensure_users_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "ensure_users_table", script, Default::default());
     op_result(result)
 }
 
@@ -29,9 +29,8 @@ pub fn find_user_by_username(db: &DbInstance, username: &str) -> Result<Option<U
     let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
         "username".into() => username.into()
     };
-    let result = db
-        .run_script(script, params, ScriptMutability::Mutable)
-        .map_err(Error::EngineError)?;
+    let result =
+        run_script(db, "find_user_by_username", script, params).map_err(Error::EngineError)?;
 
     let headers = result.headers.iter().map(String::as_str).collect_vec();
     let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
@@ -61,10 +60,36 @@ pub fn put_user(db: &DbInstance, username: &str, pwd_hash: &str) -> OpResult {
         "username".into() => username.into(),
         "pwd_hash".into() => pwd_hash.into()
     };
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "put_user", script, params);
     op_result(result)
 }
 
+#[tracing::instrument(name = "Find usernames", skip_all)]
+pub fn find_usernames(db: &DbInstance) -> Result<Vec<String>> {
+    let script = include_str!("users/find_usernames.cozo");
+    let result =
+        run_script(db, "find_usernames", script, Default::default()).map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match &headers[..] {
+        ["username"] => {}
+        _ => return Err(Error::ResultError(result)),
+    }
+
+    let mut res = vec![];
+    // all rows must comply to format, if any does not - return error
+    for row in rows {
+        match &row[..] {
+            [DataValue::Str(username)] => res.push(username.to_string()),
+            _ => return Err(Error::ResultError(result)),
+        }
+    }
+
+    Ok(res)
+}
+
 #[tracing::instrument(name = "Update user pwd_hash", skip_all)]
 pub fn update_user_pwd_hash(db: &DbInstance, username: &str, pwd_hash: &str) -> OpResult {
     let script = include_str!("users/update_pwd_hash.cozo");
@@ -72,21 +97,21 @@ pub fn update_user_pwd_hash(db: &DbInstance, username: &str, pwd_hash: &str) ->
         "username".into() => username.into(),
         "pwd_hash".into() => pwd_hash.into()
     };
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "update_user_pwd_hash", script, params);
     op_result(result)
 }
 
 #[tracing::instrument(name = "Create articles table", skip_all)]
 pub fn create_articles_table(db: &DbInstance) -> OpResult {
     let script = include_str!("articles/create_articles_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "create_articles_table", script, Default::default());
     op_result(result)
 }
 
 #[tracing::instrument(name = "Ensure articles table", skip_all)]
 pub fn ensure_articles_table(db: &DbInstance) -> OpResult {
     let script = include_str!("articles/ensure_articles_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "ensure_articles_table", script, Default::default());
     op_result(result)
 }
 
@@ -107,6 +132,7 @@ pub struct ArticleWithId {
     pub draft: bool,
 }
 
+/// Tags are not written, they are kept in article_meta
 #[tracing::instrument(name = "Put article", skip_all)]
 pub fn put_article(db: &DbInstance, article: interfacing::Article) -> OpResult {
     let script = include_str!("articles/put.cozo");
@@ -117,7 +143,7 @@ pub fn put_article(db: &DbInstance, article: interfacing::Article) -> OpResult {
         "draft".into() => article.draft.into(),
     };
 
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "put_article", script, params);
     op_result(result)
 }
 
@@ -130,31 +156,74 @@ pub fn find_article_by_public_id(
     let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
         "public_id".into() => public_id.into()
     };
-    let result = db
-        .run_script(script, params, ScriptMutability::Mutable)
-        .map_err(Error::EngineError)?;
+    let result =
+        run_script(db, "find_article_by_public_id", script, params).map_err(Error::EngineError)?;
 
     let headers = result.headers.iter().map(String::as_str).collect_vec();
     let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
 
     match (&headers[..], &rows[..]) {
         (
-            ["id", "public_id", "title", "markdown", "draft"],
-            [[DataValue::Uuid(UuidWrapper(id)), DataValue::Str(public_id), DataValue::Str(title), DataValue::Str(markdown), DataValue::Bool(draft)]],
-        ) => Ok(Some(interfacing::ArticleWithId {
-            id: id.to_string(),
-            body: interfacing::Article {
-                title: title.to_string(),
-                public_id: public_id.to_string(),
-                markdown: markdown.to_string(),
-                draft: *draft,
-            },
-        })),
-        (["id", "public_id", "title", "markdown", "draft"], []) => Ok(None),
+            ["id", "public_id", "title", "markdown", "draft", "tags"],
+            [[DataValue::Uuid(UuidWrapper(id)), DataValue::Str(public_id), DataValue::Str(title), DataValue::Str(markdown), DataValue::Bool(draft), DataValue::List(tags)]],
+        ) => match strings(tags) {
+            Some(tags) => Ok(Some(interfacing::ArticleWithId {
+                id: id.to_string(),
+                body: interfacing::Article {
+                    title: title.to_string(),
+                    public_id: public_id.to_string(),
+                    markdown: markdown.to_string(),
+                    draft: *draft,
+                    tags,
+                },
+            })),
+            None => Err(Error::ResultError(result)),
+        },
+        (["id", "public_id", "title", "markdown", "draft", "tags"], []) => Ok(None),
         _ => Err(Error::ResultError(result)),
     }
 }
 
+#[tracing::instrument(name = "Find article by id", skip_all)]
+pub fn find_article_by_id(db: &DbInstance, id: &str) -> Result<Option<interfacing::ArticleWithId>> {
+    let id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(None),
+    };
+
+    let script = include_str!("articles/find_by_id.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "id".into() => DataValue::Uuid(UuidWrapper(id))
+    };
+    let result =
+        run_script(db, "find_article_by_id", script, params).map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match (&headers[..], &rows[..]) {
+        (
+            ["id", "public_id", "title", "markdown", "draft", "tags"],
+            [[DataValue::Uuid(UuidWrapper(id)), DataValue::Str(public_id), DataValue::Str(title), DataValue::Str(markdown), DataValue::Bool(draft), DataValue::List(tags)]],
+        ) => match strings(tags) {
+            Some(tags) => Ok(Some(interfacing::ArticleWithId {
+                id: id.to_string(),
+                body: interfacing::Article {
+                    title: title.to_string(),
+                    public_id: public_id.to_string(),
+                    markdown: markdown.to_string(),
+                    draft: *draft,
+                    tags,
+                },
+            })),
+            None => Err(Error::ResultError(result)),
+        },
+        (["id", "public_id", "title", "markdown", "draft", "tags"], []) => Ok(None),
+        _ => Err(Error::ResultError(result)),
+    }
+}
+
+/// Tags are not written, they are kept in article_meta
 #[tracing::instrument(name = "Update article", skip_all)]
 pub fn update_article(db: &DbInstance, article: interfacing::ArticleWithId) -> OpResult {
     let script = include_str!("articles/update.cozo");
@@ -165,22 +234,21 @@ pub fn update_article(db: &DbInstance, article: interfacing::ArticleWithId) -> O
         "markdown".into() => article.body().markdown.clone().into(),
         "draft".into() => article.body().draft.into(),
     };
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "update_article", script, params);
     op_result(result)
 }
 
 #[tracing::instrument(name = "Find articles", skip_all)]
 pub fn find_articles(db: &DbInstance) -> Result<Vec<interfacing::ArticleWithId>> {
     let script = include_str!("articles/find.cozo");
-    let result = db
-        .run_script(script, Default::default(), ScriptMutability::Mutable)
-        .map_err(Error::EngineError)?;
+    let result =
+        run_script(db, "find_articles", script, Default::default()).map_err(Error::EngineError)?;
 
     let headers = result.headers.iter().map(String::as_str).collect_vec();
     let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
 
     match &headers[..] {
-        ["id", "public_id", "title", "markdown", "draft"] => {}
+        ["id", "public_id", "title", "markdown", "draft", "tags"] => {}
         _ => return Err(Error::ResultError(result)),
     }
 
@@ -188,8 +256,11 @@ pub fn find_articles(db: &DbInstance) -> Result<Vec<interfacing::ArticleWithId>>
     // all rows must comply to format, if any does not - return error
     for row in rows {
         match &row[..] {
-            [DataValue::Uuid(UuidWrapper(id)), DataValue::Str(public_id), DataValue::Str(title), DataValue::Str(markdown), DataValue::Bool(draft)] =>
+            [DataValue::Uuid(UuidWrapper(id)), DataValue::Str(public_id), DataValue::Str(title), DataValue::Str(markdown), DataValue::Bool(draft), DataValue::List(tags)] =>
             {
+                let Some(tags) = strings(tags) else {
+                    return Err(Error::ResultError(result));
+                };
                 res.push(interfacing::ArticleWithId {
                     id: id.to_string(),
                     body: interfacing::Article {
@@ -197,6 +268,7 @@ pub fn find_articles(db: &DbInstance) -> Result<Vec<interfacing::ArticleWithId>>
                         public_id: public_id.to_string(),
                         markdown: markdown.to_string(),
                         draft: *draft,
+                        tags,
                     },
                 });
             }
@@ -213,32 +285,294 @@ pub fn rm_article(db: &DbInstance, id: &str) -> OpResult {
     let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
         "id".into() => id.into(),
     };
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "rm_article", script, params);
     op_result(result)
 }
 
+// list of strings, as stored for tags
+fn strings(list: &[DataValue]) -> Option<Vec<String>> {
+    list.iter()
+        .map(|value| match value {
+            DataValue::Str(value) => Some(value.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tracing::instrument(name = "Create article_meta table", skip_all)]
+pub fn create_article_meta_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("article_meta/create_table.cozo");
+    let result = run_script(db, "create_article_meta_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Ensure article_meta table", skip_all)]
+pub fn ensure_article_meta_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("article_meta/ensure_table.cozo");
+    let result = run_script(db, "ensure_article_meta_table", script, Default::default());
+    op_result(result)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArticleMeta {
+    pub article_id: String,
+    pub tags: Vec<String>,
+    // RFC 3339, set when first saved as published
+    pub published_at: Option<String>,
+    pub updated_at: String,
+}
+
+impl ArticleMeta {
+    /// Meta of an article saved now, published_at is kept from the first save as published
+    pub fn saved(article: &interfacing::ArticleWithId, before: Option<ArticleMeta>) -> Self {
+        let now = interfacing::EndpointHit::formatted_now();
+        let published_at = before
+            .and_then(|before| before.published_at)
+            .or_else(|| (!article.body().draft).then(|| now.clone()));
+
+        Self {
+            article_id: article.id.clone(),
+            tags: article.body().tags.clone(),
+            published_at,
+            updated_at: now,
+        }
+    }
+}
+
+fn article_uuid(id: &str) -> Option<DataValue> {
+    uuid::Uuid::parse_str(id)
+        .ok()
+        .map(|id| DataValue::Uuid(UuidWrapper(id)))
+}
+
+#[tracing::instrument(name = "Put article_meta", skip_all)]
+pub fn put_article_meta(db: &DbInstance, meta: ArticleMeta) -> OpResult {
+    let Some(article_id) = article_uuid(&meta.article_id) else {
+        let report = miette::miette!("{} is not a uuid", meta.article_id);
+        return Err(Error::EngineError(report));
+    };
+
+    let script = include_str!("article_meta/put.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "article_id".into() => article_id,
+        "tags".into() => DataValue::List(meta.tags.into_iter().map(DataValue::from).collect()),
+        "published_at".into() => meta.published_at.map_or(DataValue::Null, DataValue::from),
+        "updated_at".into() => meta.updated_at.into(),
+    };
+    let result = run_script(db, "put_article_meta", script, params);
+    op_result(result)
+}
+
+fn article_meta_rows(result: NamedRows) -> Result<Vec<ArticleMeta>> {
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    match &headers[..] {
+        ["article_id", "tags", "published_at", "updated_at"] => {}
+        _ => return Err(Error::ResultError(result)),
+    }
+
+    let mut res = vec![];
+    // all rows must comply to format, if any does not - return error
+    for row in &result.rows {
+        match &row[..] {
+            [DataValue::Uuid(UuidWrapper(article_id)), DataValue::List(tags), published_at @ (DataValue::Str(_) | DataValue::Null), DataValue::Str(updated_at)] =>
+            {
+                let Some(tags) = strings(tags) else {
+                    return Err(Error::ResultError(result));
+                };
+                res.push(ArticleMeta {
+                    article_id: article_id.to_string(),
+                    tags,
+                    published_at: match published_at {
+                        DataValue::Str(published_at) => Some(published_at.to_string()),
+                        _ => None,
+                    },
+                    updated_at: updated_at.to_string(),
+                });
+            }
+            _ => return Err(Error::ResultError(result)),
+        }
+    }
+
+    Ok(res)
+}
+
+#[tracing::instrument(name = "Find article_meta", skip_all)]
+pub fn find_article_meta(db: &DbInstance) -> Result<Vec<ArticleMeta>> {
+    let script = include_str!("article_meta/find.cozo");
+    let result = run_script(db, "find_article_meta", script, Default::default())
+        .map_err(Error::EngineError)?;
+
+    article_meta_rows(result)
+}
+
+#[tracing::instrument(name = "Find article_meta by article id", skip_all)]
+pub fn find_article_meta_by_id(db: &DbInstance, article_id: &str) -> Result<Option<ArticleMeta>> {
+    let Some(article_id) = article_uuid(article_id) else {
+        return Ok(None);
+    };
+
+    let script = include_str!("article_meta/find_by_id.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "article_id".into() => article_id,
+    };
+    let result =
+        run_script(db, "find_article_meta_by_id", script, params).map_err(Error::EngineError)?;
+
+    Ok(article_meta_rows(result)?.pop())
+}
+
+#[tracing::instrument(name = "Remove article_meta", skip(db))]
+pub fn rm_article_meta(db: &DbInstance, article_id: &str) -> OpResult {
+    let Some(article_id) = article_uuid(article_id) else {
+        return Ok(());
+    };
+
+    let script = include_str!("article_meta/rm.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "article_id".into() => article_id,
+    };
+    let result = run_script(db, "rm_article_meta", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Create passkeys table", skip_all)]
+pub fn create_passkeys_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("passkeys/create_table.cozo");
+    let result = run_script(db, "create_passkeys_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Ensure passkeys table", skip_all)]
+pub fn ensure_passkeys_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("passkeys/ensure_table.cozo");
+    let result = run_script(db, "ensure_passkeys_table", script, Default::default());
+    op_result(result)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredPasskey {
+    pub credential_id: String,
+    pub username: String,
+    pub name: String,
+    // serialized webauthn_rs::prelude::Passkey
+    pub passkey: String,
+    pub created_at: String,
+}
+
+#[tracing::instrument(name = "Put passkey", skip_all, fields(username = %value.username))]
+pub fn put_passkey(db: &DbInstance, value: StoredPasskey) -> OpResult {
+    let script = include_str!("passkeys/put.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "credential_id".into() => value.credential_id.into(),
+        "username".into() => value.username.into(),
+        "name".into() => value.name.into(),
+        "passkey".into() => value.passkey.into(),
+        "created_at".into() => value.created_at.into(),
+    };
+    let result = run_script(db, "put_passkey", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Find passkeys by username", skip(db))]
+pub fn find_passkeys_by_username(db: &DbInstance, username: &str) -> Result<Vec<StoredPasskey>> {
+    let script = include_str!("passkeys/find_by_username.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "username".into() => username.into()
+    };
+    let result =
+        run_script(db, "find_passkeys_by_username", script, params).map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match &headers[..] {
+        ["credential_id", "username", "name", "passkey", "created_at"] => {}
+        _ => return Err(Error::ResultError(result)),
+    }
+
+    let mut res = vec![];
+    // all rows must comply to format, if any does not - return error
+    for row in rows {
+        match &row[..] {
+            [DataValue::Str(credential_id), DataValue::Str(username), DataValue::Str(name), DataValue::Str(passkey), DataValue::Str(created_at)] => {
+                res.push(StoredPasskey {
+                    credential_id: credential_id.to_string(),
+                    username: username.to_string(),
+                    name: name.to_string(),
+                    passkey: passkey.to_string(),
+                    created_at: created_at.to_string(),
+                })
+            }
+            _ => return Err(Error::ResultError(result)),
+        }
+    }
+
+    Ok(res)
+}
+
 #[tracing::instrument(name = "Create sessions table", skip_all)]
 pub fn create_sessions_table(db: &DbInstance) -> OpResult {
     let script = include_str!("sessions/create_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "create_sessions_table", script, Default::default());
     op_result(result)
 }
 
 #[tracing::instrument(name = "Ensure sessions table", skip_all)]
 pub fn ensure_sessions_table(db: &DbInstance) -> OpResult {
     let script = include_str!("sessions/ensure_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "ensure_sessions_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Ensure legacy sessions table", skip_all)]
+pub fn ensure_legacy_sessions_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("sessions/ensure_legacy_table.cozo");
+    let result = run_script(
+        db,
+        "ensure_legacy_sessions_table",
+        script,
+        Default::default(),
+    );
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Remove sessions table", skip_all)]
+pub fn remove_sessions_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("sessions/remove_table.cozo");
+    let result = run_script(db, "remove_sessions_table", script, Default::default());
     op_result(result)
 }
 
-#[tracing::instrument(name = "Put session", ret, skip(db))]
-pub fn put_session(db: &DbInstance, id: &str, value: &str) -> OpResult {
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredSession {
+    pub id: String,
+    pub value: String,
+    pub username: Option<String>,
+    pub expires_at: Option<String>,
+    pub created_at: String,
+    pub last_seen_at: String,
+    pub user_agent_class: Option<String>,
+    pub hashed_ip: Option<String>,
+}
+
+#[tracing::instrument(name = "Put session", skip_all, fields(id = %value.id))]
+pub fn put_session(db: &DbInstance, value: StoredSession) -> OpResult {
+    fn nullable(value: Option<String>) -> DataValue {
+        value.map(DataValue::from).unwrap_or(DataValue::Null)
+    }
+
     let script = include_str!("sessions/put.cozo");
     let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
-        "id".into() => id.into(),
-        "value".into() => value.into()
+        "id".into() => value.id.into(),
+        "value".into() => value.value.into(),
+        "username".into() => nullable(value.username),
+        "expires_at".into() => nullable(value.expires_at),
+        "created_at".into() => value.created_at.into(),
+        "last_seen_at".into() => value.last_seen_at.into(),
+        "user_agent_class".into() => nullable(value.user_agent_class),
+        "hashed_ip".into() => nullable(value.hashed_ip),
     };
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "put_session", script, params);
     op_result(result)
 }
 
@@ -248,9 +582,8 @@ pub fn find_session_by_id<'de>(db: &DbInstance, id: &str) -> Result<Option<Strin
     let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
         "id".into() => id.into()
     };
-    let result = db
-        .run_script(script, params, ScriptMutability::Mutable)
-        .map_err(Error::EngineError)?;
+    let result =
+        run_script(db, "find_session_by_id", script, params).map_err(Error::EngineError)?;
 
     let headers = result.headers.iter().map(String::as_str).collect_vec();
     let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
@@ -264,27 +597,141 @@ pub fn find_session_by_id<'de>(db: &DbInstance, id: &str) -> Result<Option<Strin
     }
 }
 
+/// Sets when the session was last seen, unless it was since `stale_before`
+#[tracing::instrument(name = "Touch session", skip(db))]
+pub fn touch_session(db: &DbInstance, id: &str, now: &str, stale_before: &str) -> OpResult {
+    let script = include_str!("sessions/touch.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "id".into() => id.into(),
+        "now".into() => now.into(),
+        "stale_before".into() => stale_before.into(),
+    };
+    let result = run_script(db, "touch_session", script, params);
+    op_result(result)
+}
+
 #[tracing::instrument(name = "Remove session", skip(db))]
 pub fn rm_session(db: &DbInstance, id: &str) -> OpResult {
     let script = include_str!("sessions/rm.cozo");
     let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
         "id".into() => id.into(),
     };
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "rm_session", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Find sessions by username", skip(db))]
+pub fn find_sessions_by_username(
+    db: &DbInstance,
+    username: &str,
+) -> Result<Vec<interfacing::SessionInfo>> {
+    let script = include_str!("sessions/find_by_username.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "username".into() => username.into()
+    };
+    let result =
+        run_script(db, "find_sessions_by_username", script, params).map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match &headers[..] {
+        ["id", "expires_at", "created_at", "last_seen_at", "user_agent_class", "hashed_ip"] => {}
+        _ => return Err(Error::ResultError(result)),
+    }
+
+    fn nullable(value: &DataValue) -> std::result::Result<Option<String>, ()> {
+        match value {
+            DataValue::Null => Ok(None),
+            DataValue::Str(value) => Ok(Some(value.to_string())),
+            _ => Err(()),
+        }
+    }
+
+    let mut res = vec![];
+    // all rows must comply to format, if any does not - return error
+    for row in rows {
+        match &row[..] {
+            [DataValue::Str(id), expires_at, DataValue::Str(created_at), DataValue::Str(last_seen_at), user_agent_class, hashed_ip] =>
+            {
+                let (Ok(expires_at), Ok(user_agent_class), Ok(hashed_ip)) = (
+                    nullable(expires_at),
+                    nullable(user_agent_class),
+                    nullable(hashed_ip),
+                ) else {
+                    return Err(Error::ResultError(result));
+                };
+
+                res.push(interfacing::SessionInfo {
+                    id: id.to_string(),
+                    current: false,
+                    created_at: created_at.to_string(),
+                    last_seen_at: last_seen_at.to_string(),
+                    expires_at,
+                    user_agent_class,
+                    hashed_ip,
+                })
+            }
+            _ => return Err(Error::ResultError(result)),
+        }
+    }
+
+    Ok(res)
+}
+
+#[tracing::instrument(name = "Remove sessions by username", skip(db))]
+pub fn rm_sessions_by_username(db: &DbInstance, username: &str) -> OpResult {
+    let script = include_str!("sessions/rm_by_username.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "username".into() => username.into(),
+    };
+    let result = run_script(db, "rm_sessions_by_username", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Remove sessions by username except one", skip(db))]
+pub fn rm_sessions_by_username_except(
+    db: &DbInstance,
+    username: &str,
+    except_id: &str,
+) -> OpResult {
+    let script = include_str!("sessions/rm_by_username_except.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "username".into() => username.into(),
+        "except_id".into() => except_id.into(),
+    };
+    let result = run_script(db, "rm_sessions_by_username_except", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Remove expired sessions", skip(db))]
+pub fn rm_expired_sessions(db: &DbInstance, now: &str) -> OpResult {
+    let script = include_str!("sessions/rm_expired.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "now".into() => now.into(),
+    };
+    let result = run_script(db, "rm_expired_sessions", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Remove all sessions", skip_all)]
+pub fn rm_all_sessions(db: &DbInstance) -> OpResult {
+    let script = include_str!("sessions/rm_all.cozo");
+    let result = run_script(db, "rm_all_sessions", script, Default::default());
     op_result(result)
 }
 
 #[tracing::instrument(name = "Create endpoint_hits table", skip_all)]
 pub fn create_endpoint_hits(db: &DbInstance) -> OpResult {
     let script = include_str!("endpoint_hits/create_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "create_endpoint_hits", script, Default::default());
     op_result(result)
 }
 
 #[tracing::instrument(name = "Ensure endpoint_hits table", skip_all)]
 pub fn ensure_endpoint_hits(db: &DbInstance) -> OpResult {
     let script = include_str!("endpoint_hits/ensure_table.cozo");
-    let result = db.run_script(script, Default::default(), ScriptMutability::Mutable);
+    let result = run_script(db, "ensure_endpoint_hits", script, Default::default());
     op_result(result)
 }
 
@@ -299,15 +746,14 @@ pub fn put_endpoint_hit(db: &DbInstance, value: interfacing::EndpointHit) -> OpR
         "timestamp".into() => value.timestamp.into(),
     };
 
-    let result = db.run_script(script, params, ScriptMutability::Mutable);
+    let result = run_script(db, "put_endpoint_hit", script, params);
     op_result(result)
 }
 
 #[tracing::instrument(name = "Find endpoint_hits", skip_all)]
 pub fn find_endpoint_hits(db: &DbInstance) -> Result<Vec<interfacing::EndpointHit>> {
     let script = include_str!("endpoint_hits/find.cozo");
-    let result = db
-        .run_script(script, Default::default(), ScriptMutability::Mutable)
+    let result = run_script(db, "find_endpoint_hits", script, Default::default())
         .map_err(Error::EngineError)?;
 
     let headers = result.headers.iter().map(String::as_str).collect_vec();
@@ -338,3 +784,345 @@ pub fn find_endpoint_hits(db: &DbInstance) -> Result<Vec<interfacing::EndpointHi
 
     Ok(res)
 }
+
+#[tracing::instrument(name = "Create audit_log table", skip_all)]
+pub fn create_audit_log_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("audit_log/create_table.cozo");
+    let result = run_script(db, "create_audit_log_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Ensure audit_log table", skip_all)]
+pub fn ensure_audit_log_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("audit_log/ensure_table.cozo");
+    let result = run_script(db, "ensure_audit_log_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Put audit_log entry", skip_all)]
+pub fn put_audit_log_entry(db: &DbInstance, value: interfacing::AuditLogEntry) -> OpResult {
+    fn nullable(value: Option<String>) -> DataValue {
+        value.map(DataValue::from).unwrap_or(DataValue::Null)
+    }
+
+    let script = include_str!("audit_log/put.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "actor".into() => value.actor.into(),
+        "action".into() => value.action.into(),
+        "target_id".into() => value.target_id.into(),
+        "timestamp".into() => value.timestamp.into(),
+        "hashed_ip".into() => value.hashed_ip.into(),
+        "before".into() => nullable(value.before),
+        "after".into() => nullable(value.after),
+    };
+
+    let result = run_script(db, "put_audit_log_entry", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Find audit_log entries", skip_all)]
+pub fn find_audit_log_entries(db: &DbInstance) -> Result<Vec<interfacing::AuditLogEntry>> {
+    let script = include_str!("audit_log/find.cozo");
+    let result = run_script(db, "find_audit_log_entries", script, Default::default())
+        .map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match &headers[..] {
+        ["actor", "action", "target_id", "timestamp", "hashed_ip", "before", "after"] => {}
+        _ => return Err(Error::ResultError(result)),
+    }
+
+    fn nullable(value: &DataValue) -> std::result::Result<Option<String>, ()> {
+        match value {
+            DataValue::Null => Ok(None),
+            DataValue::Str(value) => Ok(Some(value.to_string())),
+            _ => Err(()),
+        }
+    }
+
+    let mut res = vec![];
+    // all rows must comply to format, if any does not - return error
+    for row in rows {
+        match &row[..] {
+            [DataValue::Str(actor), DataValue::Str(action), DataValue::Str(target_id), DataValue::Str(timestamp), DataValue::Str(hashed_ip), before, after] =>
+            {
+                let (Ok(before), Ok(after)) = (nullable(before), nullable(after)) else {
+                    return Err(Error::ResultError(result));
+                };
+
+                res.push(interfacing::AuditLogEntry {
+                    actor: actor.to_string(),
+                    action: action.to_string(),
+                    target_id: target_id.to_string(),
+                    timestamp: timestamp.to_string(),
+                    hashed_ip: hashed_ip.to_string(),
+                    before,
+                    after,
+                });
+            }
+            _ => return Err(Error::ResultError(result)),
+        }
+    }
+
+    Ok(res)
+}
+
+#[tracing::instrument(name = "Create feature_flags table", skip_all)]
+pub fn create_feature_flags_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("feature_flags/create_table.cozo");
+    let result = run_script(db, "create_feature_flags_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Ensure feature_flags table", skip_all)]
+pub fn ensure_feature_flags_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("feature_flags/ensure_table.cozo");
+    let result = run_script(db, "ensure_feature_flags_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Put feature flag override", skip(db))]
+pub fn put_feature_flag_override(
+    db: &DbInstance,
+    name: &str,
+    value: interfacing::FeatureFlag,
+) -> OpResult {
+    let (enabled, percentage) = match value {
+        interfacing::FeatureFlag::Enabled(enabled) => (enabled.into(), DataValue::Null),
+        interfacing::FeatureFlag::Percentage(percentage) => {
+            (DataValue::Null, (percentage as i64).into())
+        }
+    };
+
+    let script = include_str!("feature_flags/put.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "name".into() => name.into(),
+        "enabled".into() => enabled,
+        "percentage".into() => percentage,
+        "updated_at".into() => interfacing::EndpointHit::formatted_now().into(),
+    };
+    let result = run_script(db, "put_feature_flag_override", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Remove feature flag override", skip(db))]
+pub fn rm_feature_flag_override(db: &DbInstance, name: &str) -> OpResult {
+    let script = include_str!("feature_flags/rm.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "name".into() => name.into(),
+    };
+    let result = run_script(db, "rm_feature_flag_override", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Find feature flag overrides", skip_all)]
+pub fn find_feature_flag_overrides(
+    db: &DbInstance,
+) -> Result<BTreeMap<String, interfacing::FeatureFlag>> {
+    let script = include_str!("feature_flags/find.cozo");
+    let result = run_script(
+        db,
+        "find_feature_flag_overrides",
+        script,
+        Default::default(),
+    )
+    .map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match &headers[..] {
+        ["name", "enabled", "percentage"] => {}
+        _ => return Err(Error::ResultError(result)),
+    }
+
+    let mut res = BTreeMap::new();
+    // all rows must comply to format, if any does not - return error
+    for row in rows {
+        match &row[..] {
+            [DataValue::Str(name), DataValue::Bool(enabled), DataValue::Null] => {
+                res.insert(
+                    name.to_string(),
+                    interfacing::FeatureFlag::Enabled(*enabled),
+                );
+            }
+            [DataValue::Str(name), DataValue::Null, DataValue::Num(Num::Int(percentage))] => {
+                let Ok(percentage) = u8::try_from(*percentage) else {
+                    return Err(Error::ResultError(result));
+                };
+                res.insert(
+                    name.to_string(),
+                    interfacing::FeatureFlag::Percentage(percentage),
+                );
+            }
+            _ => return Err(Error::ResultError(result)),
+        }
+    }
+
+    Ok(res)
+}
+
+#[tracing::instrument(name = "Create media table", skip_all)]
+pub fn create_media_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("media/create_table.cozo");
+    let result = run_script(db, "create_media_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Ensure media table", skip_all)]
+pub fn ensure_media_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("media/ensure_table.cozo");
+    let result = run_script(db, "ensure_media_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Create media_blobs table", skip_all)]
+pub fn create_media_blobs_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("media/create_blobs_table.cozo");
+    let result = run_script(db, "create_media_blobs_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Ensure media_blobs table", skip_all)]
+pub fn ensure_media_blobs_table(db: &DbInstance) -> OpResult {
+    let script = include_str!("media/ensure_blobs_table.cozo");
+    let result = run_script(db, "ensure_media_blobs_table", script, Default::default());
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Put media file", skip(db))]
+pub fn put_media_file(db: &DbInstance, value: interfacing::MediaFile) -> OpResult {
+    let script = include_str!("media/put.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "id".into() => value.id.into(),
+        "name".into() => value.name.into(),
+        "mime".into() => value.mime.into(),
+        "size".into() => (value.size as i64).into(),
+        "uploaded_by".into() => value.uploaded_by.into(),
+        "uploaded_at".into() => value.uploaded_at.into(),
+    };
+    let result = run_script(db, "put_media_file", script, params);
+    op_result(result)
+}
+
+fn media_file(row: &[DataValue]) -> std::result::Result<interfacing::MediaFile, ()> {
+    match row {
+        [DataValue::Str(id), DataValue::Str(name), DataValue::Str(mime), DataValue::Num(Num::Int(size)), DataValue::Str(uploaded_by), DataValue::Str(uploaded_at)] => {
+            Ok(interfacing::MediaFile {
+                id: id.to_string(),
+                name: name.to_string(),
+                mime: mime.to_string(),
+                size: u64::try_from(*size).map_err(|_| ())?,
+                uploaded_by: uploaded_by.to_string(),
+                uploaded_at: uploaded_at.to_string(),
+            })
+        }
+        _ => Err(()),
+    }
+}
+
+#[tracing::instrument(name = "Find media files", skip_all)]
+pub fn find_media_files(db: &DbInstance) -> Result<Vec<interfacing::MediaFile>> {
+    let script = include_str!("media/find.cozo");
+    let result = run_script(db, "find_media_files", script, Default::default())
+        .map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+
+    match &headers[..] {
+        ["id", "name", "mime", "size", "uploaded_by", "uploaded_at"] => {}
+        _ => return Err(Error::ResultError(result)),
+    }
+
+    // all rows must comply to format, if any does not - return error
+    match result.rows.iter().map(|row| media_file(row)).collect() {
+        Ok(res) => Ok(res),
+        Err(()) => Err(Error::ResultError(result)),
+    }
+}
+
+#[tracing::instrument(name = "Find media file by id", skip(db))]
+pub fn find_media_file_by_id(db: &DbInstance, id: &str) -> Result<Option<interfacing::MediaFile>> {
+    let script = include_str!("media/find_by_id.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "id".into() => id.into()
+    };
+    let result =
+        run_script(db, "find_media_file_by_id", script, params).map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match (&headers[..], &rows[..]) {
+        (["id", "name", "mime", "size", "uploaded_by", "uploaded_at"], [row]) => {
+            match media_file(row) {
+                Ok(file) => Ok(Some(file)),
+                Err(()) => Err(Error::ResultError(result)),
+            }
+        }
+        (["id", "name", "mime", "size", "uploaded_by", "uploaded_at"], []) => Ok(None),
+        _ => Err(Error::ResultError(result)),
+    }
+}
+
+#[tracing::instrument(name = "Put media blob", skip(db, data))]
+pub fn put_media_blob(db: &DbInstance, id: &str, data: Vec<u8>) -> OpResult {
+    let script = include_str!("media/put_blob.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "id".into() => id.into(),
+        "data".into() => DataValue::Bytes(data),
+    };
+    let result = run_script(db, "put_media_blob", script, params);
+    op_result(result)
+}
+
+#[tracing::instrument(name = "Find media blob", skip(db))]
+pub fn find_media_blob(db: &DbInstance, id: &str) -> Result<Option<Vec<u8>>> {
+    let script = include_str!("media/find_blob.cozo");
+    let params: BTreeMap<String, DataValue> = map_macro::btree_map! {
+        "id".into() => id.into()
+    };
+    let result = run_script(db, "find_media_blob", script, params).map_err(Error::EngineError)?;
+
+    let headers = result.headers.iter().map(String::as_str).collect_vec();
+    let rows = result.rows.iter().map(Vec::as_slice).collect_vec();
+
+    match (&headers[..], &rows[..]) {
+        (["data"], [[DataValue::Bytes(data)]]) => Ok(Some(data.clone())),
+        (["data"], []) => Ok(None),
+        _ => Err(Error::ResultError(result)),
+    }
+}
+
+/// Runs a trivial query, to tell whether the db responds
+#[tracing::instrument(name = "Ping db", skip_all)]
+pub fn ping(db: &DbInstance) -> Result<()> {
+    let script = include_str!("ping.cozo");
+    let result = run_script(db, "ping", script, Default::default()).map_err(Error::EngineError)?;
+
+    match &result.rows[..] {
+        [row] if row[..] == [DataValue::from(1i64)] => Ok(()),
+        _ => Err(Error::ResultError(result)),
+    }
+}
+
+/// Names of stored relations, indices excluded
+#[tracing::instrument(name = "Find relation names", skip_all)]
+pub fn find_relation_names(db: &DbInstance) -> Result<Vec<String>> {
+    let script = include_str!("relations.cozo");
+    let result = run_script(db, "find_relation_names", script, Default::default())
+        .map_err(Error::EngineError)?;
+
+    let mut res = vec![];
+    for row in &result.rows {
+        match row.first() {
+            Some(DataValue::Str(name)) if !name.contains(':') => res.push(name.to_string()),
+            Some(DataValue::Str(_)) => {}
+            _ => return Err(Error::ResultError(result)),
+        }
+    }
+
+    Ok(res)
+}
@@ -12,3 +12,14 @@ pub fn op_result(result: std::result::Result<NamedRows, miette::Report>) -> OpRe
         _ => Err(Error::ResultError(result)),
     }
 }
+
+/// Runs a script, its duration observed under the db::q function running it
+pub fn run_script(
+    db: &cozo::DbInstance,
+    query: &'static str,
+    script: &str,
+    params: std::collections::BTreeMap<String, cozo::DataValue>,
+) -> std::result::Result<cozo::NamedRows, miette::Report> {
+    let _timer = crate::metrics::db_query_timer(query);
+    db.run_script(script, params, cozo::ScriptMutability::Mutable)
+}
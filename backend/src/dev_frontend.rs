@@ -0,0 +1,163 @@
+// Development mode serving the frontend from disk
+//
+// When frontend.dist_path is set, the dist is read on every request instead of
+// being embedded, and is polled for changes. Served pages get a script that
+// reloads them once the dist changes, like after `trunk build --watch`.
+//
+// Without the embed-frontend feature the backend compiles without a built
+// frontend, and this mode is the only one available.
+//
+
+use crate::shutdown::Shutdown;
+use static_routes::*;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+
+/// Reloads the page once a message comes from the dev_reload route
+fn reload_script() -> String {
+    let path = routes().ws.dev_reload.get().complete().to_owned();
+    format!(
+        r#"<script>
+(() => {{
+    const protocol = location.protocol === "https:" ? "wss:" : "ws:";
+    const socket = new WebSocket(`${{protocol}}//${{location.host}}{path}`);
+    socket.onmessage = () => location.reload();
+}})();
+</script>"#
+    )
+}
+
+/// Notifies open pages of frontend changes
+#[derive(Clone)]
+pub struct DevReload {
+    tx: broadcast::Sender<()>,
+}
+
+impl Default for DevReload {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(1).0,
+        }
+    }
+}
+
+impl DevReload {
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.tx.subscribe()
+    }
+
+    fn notify(&self) {
+        // no open pages is fine
+        let _ = self.tx.send(());
+    }
+}
+
+/// index.html with the reload script injected
+pub fn with_reload_script(index: &str) -> String {
+    let script = reload_script();
+    match index.rfind("</body>").or_else(|| index.rfind("</html>")) {
+        Some(end) => format!("{}{script}\n{}", &index[..end], &index[end..]),
+        None => format!("{index}{script}"),
+    }
+}
+
+/// Polls the dist for changes until shutdown
+///
+/// Polling is coarse, but indifferent to how the dist gets rewritten.
+pub async fn watch(dist: PathBuf, reload: DevReload, shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(Duration::from_millis(500));
+    let mut last = fingerprint(&dist);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => (),
+            () = shutdown.signaled() => return,
+        }
+
+        let current = fingerprint(&dist);
+        if current != last {
+            tracing::info!("Frontend changed, reloading pages");
+            last = current;
+            reload.notify();
+        }
+    }
+}
+
+// file count and the latest modification, None while the dist is missing
+fn fingerprint(dir: &Path) -> Option<(usize, SystemTime)> {
+    let mut count = 0;
+    let mut latest = SystemTime::UNIX_EPOCH;
+
+    for entry in std::fs::read_dir(dir).ok()? {
+        let entry = entry.ok()?;
+        let metadata = entry.metadata().ok()?;
+
+        if metadata.is_dir() {
+            let (dir_count, dir_latest) = fingerprint(&entry.path())?;
+            count += dir_count;
+            latest = latest.max(dir_latest);
+        } else {
+            count += 1;
+            latest = latest.max(metadata.modified().ok()?);
+        }
+    }
+
+    Some((count, latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_script_injected() {
+        let index = "<html><head></head><body></body></html>";
+        let injected = with_reload_script(index);
+        assert!(injected.contains("/ws/dev_reload"));
+        assert!(injected.find("<script>").unwrap() < injected.find("</body>").unwrap());
+
+        // trunk output may lack a body
+        let injected = with_reload_script("<html><head></head></html>");
+        assert!(injected.find("<script>").unwrap() < injected.find("</html>").unwrap());
+    }
+
+    #[test]
+    fn fingerprint_changes() {
+        let dir = std::env::temp_dir().join(format!("dist-{}", uuid::Uuid::new_v4()));
+        assert_eq!(fingerprint(&dir), None);
+
+        std::fs::create_dir_all(dir.join("snippets")).unwrap();
+        std::fs::write(dir.join("index.html"), "").unwrap();
+        let initial = fingerprint(&dir);
+        assert_eq!(initial.map(|(count, _)| count), Some(1));
+
+        std::fs::write(dir.join("snippets/app.js"), "").unwrap();
+        assert_ne!(fingerprint(&dir), initial);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn notified_on_change() {
+        let dir = std::env::temp_dir().join(format!("dist-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let (reload, shutdown) = (DevReload::default(), Shutdown::default());
+        let mut reloads = reload.subscribe();
+        let watcher = tokio::spawn(watch(dir.clone(), reload, shutdown.clone()));
+
+        // let the watcher take the initial fingerprint
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        std::fs::write(dir.join("index.html"), "").unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), reloads.recv())
+            .await
+            .expect("to be notified")
+            .unwrap();
+
+        shutdown.trigger();
+        watcher.await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
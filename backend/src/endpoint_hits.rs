@@ -0,0 +1,131 @@
+// Endpoint hit writes
+//
+// The middleware queues hits and a background task writes them, so responses
+// don't wait on the db. The queue is bounded, hits are dropped and counted
+// while it's full. Hits queued by the time the server stops are written
+// before the db is dropped.
+//
+
+use crate::db;
+use crate::metrics::metrics;
+use tokio::sync::{mpsc, oneshot};
+
+// enough to ride out a slow db, not to grow without limit
+const QUEUE_CAPACITY: usize = 10_000;
+
+#[derive(Clone)]
+pub struct EndpointHitQueue {
+    sender: mpsc::Sender<interfacing::EndpointHit>,
+}
+
+pub struct EndpointHitWriter {
+    stop: oneshot::Sender<()>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl EndpointHitQueue {
+    pub fn start(db: cozo::DbInstance) -> (Self, EndpointHitWriter) {
+        Self::with_capacity(db, QUEUE_CAPACITY)
+    }
+
+    fn with_capacity(db: cozo::DbInstance, capacity: usize) -> (Self, EndpointHitWriter) {
+        let (sender, hits) = mpsc::channel(capacity);
+        let (stop, stopped) = oneshot::channel();
+        let handle = tokio::spawn(write(db, hits, stopped));
+
+        (Self { sender }, EndpointHitWriter { stop, handle })
+    }
+
+    pub fn push(&self, hit: interfacing::EndpointHit) {
+        let depth = &metrics().endpoint_hit_queue;
+        depth.inc();
+        match self.sender.try_send(hit) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                depth.dec();
+                metrics().endpoint_hits_dropped.inc();
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                depth.dec();
+                tracing::error!("endpoint hit dropped, the writer has stopped");
+            }
+        }
+    }
+}
+
+impl EndpointHitWriter {
+    /// Writes the queued hits and stops, later ones are dropped
+    pub async fn finish(self) {
+        let _ = self.stop.send(());
+        if let Err(e) = self.handle.await {
+            tracing::error!("endpoint hit writer failed: {e:?}");
+        }
+    }
+}
+
+async fn write(
+    db: cozo::DbInstance,
+    mut hits: mpsc::Receiver<interfacing::EndpointHit>,
+    mut stopped: oneshot::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            Some(hit) = hits.recv() => put(&db, hit),
+            _ = &mut stopped => break,
+        }
+    }
+
+    hits.close();
+    while let Some(hit) = hits.recv().await {
+        put(&db, hit);
+    }
+}
+
+fn put(db: &cozo::DbInstance, hit: interfacing::EndpointHit) {
+    let _result = db::q::put_endpoint_hit(db, hit).map_err(|e| tracing::error!("{e:?}"));
+    metrics().endpoint_hit_queue.dec();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn queued_hits_written_on_finish() {
+        let db = cozo::DbInstance::default();
+        db::migrate(&db);
+
+        let (queue, writer) = EndpointHitQueue::start(db.clone());
+        for endpoint in ["/", "/articles"] {
+            queue.push(interfacing::EndpointHit {
+                hashed_ip: "127.0.0.1".into(),
+                endpoint: endpoint.into(),
+                method: "GET".into(),
+                status: 200,
+                timestamp: interfacing::EndpointHit::formatted_now(),
+            });
+        }
+        writer.finish().await;
+
+        assert_eq!(db::q::find_endpoint_hits(&db).unwrap().len(), 2);
+        // dropped rather than left waiting
+        queue.push(interfacing::EndpointHit::default());
+    }
+
+    #[tokio::test]
+    async fn hits_dropped_while_full() {
+        let db = cozo::DbInstance::default();
+        db::migrate(&db);
+        let dropped = || metrics().endpoint_hits_dropped.get();
+        let dropped_before = dropped();
+
+        // the writer doesn't run before the test yields
+        let (queue, writer) = EndpointHitQueue::with_capacity(db.clone(), 1);
+        queue.push(interfacing::EndpointHit::default());
+        queue.push(interfacing::EndpointHit::default());
+        assert!(dropped() > dropped_before);
+
+        writer.finish().await;
+        assert_eq!(db::q::find_endpoint_hits(&db).unwrap().len(), 1);
+    }
+}
@@ -6,6 +6,9 @@ pub enum ApiError {
     #[error("Authentication failed")]
     AuthError(#[source] anyhow::Error),
 
+    #[error("CSRF check failed")]
+    CsrfError(#[source] anyhow::Error),
+
     #[error("Entry not found")]
     EntryNotFound,
 
@@ -26,6 +29,7 @@ impl axum::response::IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let trace_message = match &self {
             Self::AuthError(e) => format!("{}: {}", self, e.root_cause()),
+            Self::CsrfError(e) => format!("{}: {}", self, e.root_cause()),
             Self::DbError(e) => format!("{:?}", e),
             _ => self.to_string(),
         };
@@ -35,6 +39,7 @@ impl axum::response::IntoResponse for ApiError {
         match &self {
             Self::JsonRejection(_e) => StatusCode::BAD_REQUEST,
             Self::AuthError(_e) => StatusCode::UNAUTHORIZED,
+            Self::CsrfError(_e) => StatusCode::FORBIDDEN,
             Self::UnexpectedError(_e) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::EntryNotFound => StatusCode::NOT_FOUND,
             Self::BadRequest => StatusCode::BAD_REQUEST,
@@ -0,0 +1,164 @@
+// Feature flags
+//
+// Flags are configured under `features`, and the configured ones can be
+// overridden at runtime from the admin dashboard. Overrides are stored in
+// the feature_flags relation and take effect on the next request.
+//
+// Percentage flags are enabled for a stable share of visitors, picked by hashed IP.
+//
+
+use crate::{
+    conf::Conf,
+    db,
+    error::ApiError,
+    startup::{hash_ip, ip_address},
+};
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts, Extension};
+use interfacing::FeatureFlag;
+pub use interfacing::{OIDC_LOGIN_FEATURE, SNAKE_GAME_FEATURE, USERS_ONLINE_FEATURE};
+use sha2::Digest;
+use std::collections::BTreeMap;
+
+/// Feature flags of the requesting visitor
+pub struct Features {
+    flags: BTreeMap<String, FeatureFlag>,
+    visitor: String,
+}
+
+impl Features {
+    pub fn load(db: &cozo::DbInstance, conf: &Conf, visitor: String) -> db::Result<Self> {
+        let mut flags = current_flags(db, conf)?
+            .into_iter()
+            .map(|(name, (configured, overridden))| (name, overridden.unwrap_or(configured)))
+            .collect::<BTreeMap<_, _>>();
+        // for the frontend to offer single sign-on only when it can work
+        flags.insert(
+            OIDC_LOGIN_FEATURE.to_string(),
+            FeatureFlag::Enabled(conf.oidc.is_some()),
+        );
+
+        Ok(Self { flags, visitor })
+    }
+
+    /// Unknown flags are disabled
+    pub fn enabled(&self, name: &str) -> bool {
+        self.flags
+            .get(name)
+            .map_or(false, |flag| enabled_for(name, *flag, &self.visitor))
+    }
+
+    pub fn evaluated(&self) -> interfacing::Features {
+        self.flags
+            .keys()
+            .map(|name| (name.clone(), self.enabled(name)))
+            .collect()
+    }
+}
+
+/// Configured flags along with their overrides
+pub fn current_flags(
+    db: &cozo::DbInstance,
+    conf: &Conf,
+) -> db::Result<BTreeMap<String, (FeatureFlag, Option<FeatureFlag>)>> {
+    let mut overrides = db::q::find_feature_flag_overrides(db)?;
+
+    // overrides of flags no longer configured are ignored
+    Ok(conf
+        .features
+        .flags
+        .iter()
+        .map(|(name, configured)| (name.clone(), (*configured, overrides.remove(name))))
+        .collect())
+}
+
+pub fn enabled_for(name: &str, flag: FeatureFlag, visitor: &str) -> bool {
+    match flag {
+        FeatureFlag::Enabled(enabled) => enabled,
+        FeatureFlag::Percentage(percentage) => bucket(name, visitor) < percentage as u16,
+    }
+}
+
+// 0..100, independent between flags
+fn bucket(name: &str, visitor: &str) -> u16 {
+    let hash = sha2::Sha256::digest(format!("{name}:{visitor}"));
+    u16::from_be_bytes([hash[0], hash[1]]) % 100
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Features {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Extension(db) = Extension::<cozo::DbInstance>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiError::UnexpectedError(e.into()))?;
+        let Extension(conf) = Extension::<Conf>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiError::UnexpectedError(e.into()))?;
+
+        let visitor = hash_ip(ip_address(&parts.headers), &conf);
+
+        Ok(Self::load(&db, &conf, visitor)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentage_rollout() {
+        let visitors = (0..1000)
+            .map(|i| format!("visitor-{i}"))
+            .collect::<Vec<_>>();
+        let enabled = |name: &str, percentage: u8| {
+            visitors
+                .iter()
+                .filter(|visitor| enabled_for(name, FeatureFlag::Percentage(percentage), visitor))
+                .count()
+        };
+
+        assert_eq!(enabled("flag", 0), 0);
+        assert_eq!(enabled("flag", 100), visitors.len());
+        assert!((200..300).contains(&enabled("flag", 25)));
+
+        // growing percentage keeps already enabled visitors
+        assert!(visitors.iter().all(|visitor| {
+            !enabled_for("flag", FeatureFlag::Percentage(25), visitor)
+                || enabled_for("flag", FeatureFlag::Percentage(50), visitor)
+        }));
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        use crate::conf::{Env, EnvConf};
+
+        let mut env_conf = EnvConf::test_default();
+        env_conf.features.flags = [
+            (SNAKE_GAME_FEATURE.to_string(), FeatureFlag::Enabled(true)),
+            (USERS_ONLINE_FEATURE.to_string(), FeatureFlag::Enabled(true)),
+        ]
+        .into();
+        let conf = Conf::new(Env::Local, env_conf);
+
+        let db = &cozo::DbInstance::default();
+        db::q::create_feature_flags_table(db).unwrap();
+        db::q::put_feature_flag_override(db, USERS_ONLINE_FEATURE, FeatureFlag::Enabled(false))
+            .unwrap();
+        db::q::put_feature_flag_override(db, "removed", FeatureFlag::Enabled(true)).unwrap();
+
+        let features = Features::load(db, &conf, "visitor".into()).unwrap();
+        assert!(features.enabled(SNAKE_GAME_FEATURE));
+        assert!(!features.enabled(USERS_ONLINE_FEATURE));
+        assert!(!features.enabled("removed"));
+        assert_eq!(
+            features.evaluated(),
+            [
+                (OIDC_LOGIN_FEATURE.to_string(), false),
+                (SNAKE_GAME_FEATURE.to_string(), true),
+                (USERS_ONLINE_FEATURE.to_string(), false)
+            ]
+            .into()
+        );
+    }
+}
@@ -0,0 +1,421 @@
+// Feeds of published articles
+//
+// RSS 2.0, Atom and JSON Feed of the non-draft articles, of all of them or of one
+// tag. Contents are rendered with the markdown options of the frontend. Dates come
+// from article_meta, so articles last saved before it existed are undated.
+//
+
+use crate::conf::SiteConf;
+use crate::db;
+use static_routes::*;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl Format {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Rss => "application/rss+xml; charset=utf-8",
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+
+    fn path(&self) -> String {
+        let feeds = routes().root.feeds;
+        match self {
+            Self::Rss => feeds.rss.get().complete().to_owned(),
+            Self::Atom => feeds.atom.get().complete().to_owned(),
+            Self::Json => feeds.json.get().complete().to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub title: String,
+    pub url: String,
+    pub html: String,
+    pub tags: Vec<String>,
+    pub published: Option<SystemTime>,
+    pub updated: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Feed<'a> {
+    site: &'a SiteConf,
+    tag: Option<String>,
+    // newest first, undated last
+    entries: Vec<Entry>,
+}
+
+/// Feed of the published articles, of one tag when given
+pub fn load<'a>(
+    db: &cozo::DbInstance,
+    site: &'a SiteConf,
+    tag: Option<&str>,
+) -> db::Result<Feed<'a>> {
+    let articles = db::q::find_articles(db)?;
+    let meta = db::q::find_article_meta(db)?;
+    Ok(Feed::new(site, articles, meta, tag))
+}
+
+fn parse_date(date: &str) -> Option<SystemTime> {
+    humantime::parse_rfc3339_weak(date).ok()
+}
+
+impl<'a> Feed<'a> {
+    pub fn new(
+        site: &'a SiteConf,
+        articles: Vec<interfacing::ArticleWithId>,
+        meta: Vec<db::q::ArticleMeta>,
+        tag: Option<&str>,
+    ) -> Self {
+        let tag = tag.map(str::to_lowercase);
+        let articles_route = routes().root.articles.by_public_id;
+
+        let mut entries: Vec<Entry> = articles
+            .into_iter()
+            .filter(|article| !article.body().draft)
+            .filter(|article| match &tag {
+                Some(tag) => article.body().tags.contains(tag),
+                None => true,
+            })
+            .map(|article| {
+                let meta = meta.iter().find(|meta| meta.article_id == article.id);
+                let body = article.body;
+                let route = articles_route.with(body.public_id.as_str());
+
+                Entry {
+                    url: site.url(route.get().complete()),
+                    html: interfacing::markdown_to_html(&body.markdown),
+                    title: body.title,
+                    tags: body.tags,
+                    published: meta.and_then(|meta| parse_date(meta.published_at.as_ref()?)),
+                    updated: meta.and_then(|meta| parse_date(&meta.updated_at)),
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.published.cmp(&a.published));
+
+        Self { site, tag, entries }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Latest change of an entry, None when all are undated
+    pub fn updated(&self) -> Option<SystemTime> {
+        self.entries
+            .iter()
+            .flat_map(|entry| [entry.published, entry.updated])
+            .flatten()
+            .max()
+    }
+
+    fn title(&self) -> String {
+        match &self.tag {
+            Some(tag) => format!("{} - {tag}", self.site.title),
+            None => self.site.title.clone(),
+        }
+    }
+
+    fn self_url(&self, format: Format) -> String {
+        let url = self.site.url(&format.path());
+        match &self.tag {
+            Some(tag) => {
+                let tag: String = url::form_urlencoded::byte_serialize(tag.as_bytes()).collect();
+                format!("{url}?tag={tag}")
+            }
+            None => url,
+        }
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Rss => self.rss(),
+            Format::Atom => self.atom(),
+            Format::Json => self.json(),
+        }
+    }
+
+    fn rss(&self) -> String {
+        let mut items = String::new();
+        for entry in &self.entries {
+            items.push_str("<item>");
+            items.push_str(&element("title", &entry.title));
+            items.push_str(&element("link", &entry.url));
+            items.push_str(&format!(
+                r#"<guid isPermaLink="true">{}</guid>"#,
+                escape(&entry.url)
+            ));
+            if let Some(published) = entry.published {
+                items.push_str(&element("pubDate", &httpdate::fmt_http_date(published)));
+            }
+            for tag in &entry.tags {
+                items.push_str(&element("category", tag));
+            }
+            items.push_str(&element("description", &entry.html));
+            items.push_str("</item>\n");
+        }
+
+        let last_build = self
+            .updated()
+            .map(|updated| element("lastBuildDate", &httpdate::fmt_http_date(updated)))
+            .unwrap_or_default();
+
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">"#,
+                "\n<channel>\n{}{}{}",
+                r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+                "{}\n{}</channel>\n</rss>\n"
+            ),
+            element("title", &self.title()),
+            element("link", &self.site.url("/")),
+            element("description", &self.site.description),
+            escape(&self.self_url(Format::Rss)),
+            last_build,
+            items,
+        )
+    }
+
+    fn atom(&self) -> String {
+        // required, the epoch stands in when nothing is dated
+        let date = |time: SystemTime| humantime::format_rfc3339_seconds(time).to_string();
+        let updated = date(self.updated().unwrap_or(SystemTime::UNIX_EPOCH));
+
+        let mut entries = String::new();
+        for entry in &self.entries {
+            let entry_updated = entry.updated.or(entry.published);
+
+            entries.push_str("<entry>");
+            entries.push_str(&element("title", &entry.title));
+            entries.push_str(&element("id", &entry.url));
+            entries.push_str(&format!(r#"<link href="{}"/>"#, escape(&entry.url)));
+            if let Some(published) = entry.published {
+                entries.push_str(&element("published", &date(published)));
+            }
+            entries.push_str(&element(
+                "updated",
+                &date(entry_updated.unwrap_or(SystemTime::UNIX_EPOCH)),
+            ));
+            for tag in &entry.tags {
+                entries.push_str(&format!(r#"<category term="{}"/>"#, escape(tag)));
+            }
+            entries.push_str(&format!(
+                r#"<content type="html">{}</content>"#,
+                escape(&entry.html)
+            ));
+            entries.push_str("</entry>\n");
+        }
+
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<feed xmlns="http://www.w3.org/2005/Atom">"#,
+                "\n{}{}{}",
+                r#"<link rel="self" href="{}"/><link href="{}"/>"#,
+                "{}<author>{}</author>\n{}</feed>\n"
+            ),
+            element("title", &self.title()),
+            element("subtitle", &self.site.description),
+            element("id", &self.self_url(Format::Atom)),
+            escape(&self.self_url(Format::Atom)),
+            escape(&self.site.url("/")),
+            element("updated", &updated),
+            element("name", &self.site.title),
+            entries,
+        )
+    }
+
+    fn json(&self) -> String {
+        let date = |time: Option<SystemTime>| {
+            time.map(|time| humantime::format_rfc3339_seconds(time).to_string())
+        };
+
+        let items = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut item = serde_json::json!({
+                    "id": entry.url,
+                    "url": entry.url,
+                    "title": entry.title,
+                    "content_html": entry.html,
+                    "tags": entry.tags,
+                });
+                // omitted rather than null
+                if let Some(published) = date(entry.published) {
+                    item["date_published"] = published.into();
+                }
+                if let Some(updated) = date(entry.updated) {
+                    item["date_modified"] = updated.into();
+                }
+                item
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": self.title(),
+            "description": self.site.description,
+            "home_page_url": self.site.url("/"),
+            "feed_url": self.self_url(Format::Json),
+            "items": items,
+        })
+        .to_string()
+    }
+}
+
+fn element(name: &str, text: &str) -> String {
+    format!("<{name}>{}</{name}>", escape(text))
+}
+
+/// Escapes text of XML elements and attributes
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::EnvConf;
+
+    fn article(public_id: &str, draft: bool, tags: &[&str]) -> interfacing::ArticleWithId {
+        interfacing::ArticleWithId {
+            id: uuid::Uuid::new_v4().to_string(),
+            body: interfacing::Article {
+                title: format!("{public_id} & more"),
+                public_id: public_id.into(),
+                markdown: "~~struck~~ <b>".into(),
+                draft,
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            },
+        }
+    }
+
+    fn meta(article: &interfacing::ArticleWithId, published_at: &str) -> db::q::ArticleMeta {
+        db::q::ArticleMeta {
+            article_id: article.id.clone(),
+            tags: article.body().tags.clone(),
+            published_at: Some(published_at.into()),
+            updated_at: published_at.into(),
+        }
+    }
+
+    fn sample_feed(site: &SiteConf, tag: Option<&str>) -> Feed<'_> {
+        let (older, newer) = (
+            article("older", false, &["rust"]),
+            article("newer", false, &[]),
+        );
+        let metas = vec![
+            meta(&older, "2023-01-01T00:00:00Z"),
+            meta(&newer, "2023-02-01T00:00:00Z"),
+        ];
+        let articles = vec![
+            article("undated", false, &["rust"]),
+            older,
+            article("draft", true, &["rust"]),
+            newer,
+        ];
+
+        Feed::new(site, articles, metas, tag)
+    }
+
+    fn urls(feed: &Feed) -> Vec<&str> {
+        feed.entries()
+            .iter()
+            .map(|entry| entry.url.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn published_newest_first() {
+        let site = EnvConf::test_default().site;
+        let feed = sample_feed(&site, None);
+
+        assert_eq!(
+            urls(&feed),
+            [
+                "http://localhost:8000/articles/newer",
+                "http://localhost:8000/articles/older",
+                "http://localhost:8000/articles/undated",
+            ]
+        );
+        assert_eq!(feed.updated(), parse_date("2023-02-01T00:00:00Z"));
+        // markdown options of the frontend
+        assert!(feed.entries()[0].html.contains("<del>struck</del>"));
+    }
+
+    #[test]
+    fn of_a_tag() {
+        let site = EnvConf::test_default().site;
+        let feed = sample_feed(&site, Some("Rust"));
+
+        assert_eq!(
+            urls(&feed),
+            [
+                "http://localhost:8000/articles/older",
+                "http://localhost:8000/articles/undated",
+            ]
+        );
+        assert!(feed
+            .render(Format::Atom)
+            .contains("http://localhost:8000/atom.xml?tag=rust"));
+        assert!(sample_feed(&site, Some("unknown")).entries().is_empty());
+    }
+
+    #[test]
+    fn rendered() {
+        let site = EnvConf::test_default().site;
+        let feed = sample_feed(&site, None);
+
+        let rss = feed.render(Format::Rss);
+        assert!(rss.contains("<title>newer &amp; more</title>"));
+        assert!(rss.contains("<pubDate>Wed, 01 Feb 2023 00:00:00 GMT</pubDate>"));
+        assert!(rss.contains("<category>rust</category>"));
+        // html is escaped, raw html of the markdown as well
+        assert!(rss.contains("&lt;del&gt;struck&lt;/del&gt; &lt;b&gt;"));
+        assert_eq!(rss.matches("<item>").count(), 3);
+
+        let atom = feed.render(Format::Atom);
+        assert!(atom.contains("<updated>2023-02-01T00:00:00Z</updated>"));
+        assert!(atom.contains("<updated>1970-01-01T00:00:00Z</updated>"));
+        assert_eq!(atom.matches("<entry>").count(), 3);
+
+        let json: serde_json::Value = serde_json::from_str(&feed.render(Format::Json)).unwrap();
+        assert_eq!(json["feed_url"], "http://localhost:8000/feed.json");
+        assert_eq!(json["items"][0]["date_published"], "2023-02-01T00:00:00Z");
+        assert!(json["items"][2].get("date_published").is_none());
+        assert_eq!(json["items"][1]["tags"], serde_json::json!(["rust"]));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            escape(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+    }
+}
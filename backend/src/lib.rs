@@ -1,10 +1,30 @@
+pub mod audit;
 pub mod authentication;
+pub mod cli;
 pub mod conf;
+pub mod csrf;
 pub mod db;
+pub mod dev_frontend;
+pub mod endpoint_hits;
 pub mod error;
+pub mod features;
+pub mod feeds;
+pub mod media;
+pub mod metrics;
+pub mod oidc;
+pub mod openapi;
+pub mod page_meta;
+pub mod passkeys;
 pub mod serve_files;
+pub mod session_keys;
+pub mod sessions;
+pub mod shutdown;
+pub mod sitemap;
+#[cfg(feature = "ssr")]
+pub mod ssr;
 pub mod startup;
 pub mod timeout;
+pub mod tls;
 pub mod trace;
 
 mod routes;
@@ -1,30 +1,50 @@
+use backend::cli::{self, Command};
 use backend::conf::{self};
-use backend::serve_files;
+use backend::shutdown;
 use backend::startup::Application;
 use backend::trace;
 
 #[tokio::main]
-async fn main() -> hyper::Result<()> {
+async fn main() -> anyhow::Result<()> {
+    let cli = <cli::Cli as clap::Parser>::parse();
+    let command = cli.command.unwrap_or(Command::Serve);
+    let serve = matches!(command, Command::Serve);
+
     let env = conf::Env::derive();
     let env_conf = conf::EnvConf::derive(env);
 
     trace::TracingSubscriber::new()
         .pretty(env_conf.log.pretty)
+        .stderr(!serve)
         .set_global_default();
 
     tracing::debug!("Env: {}", env);
-    tracing::debug!("{:?}", env_conf);
+    tracing::debug!("{:?}", env_conf.redacted());
 
     let conf = conf::Conf::new(env, env_conf);
 
-    let application = Application::build(conf).await;
+    if !serve {
+        return cli::run(&conf, command);
+    }
+
+    let application = Application::build(conf.clone()).await?;
+
+    let shutdown = application.shutdown_handle();
+    tokio::spawn(async move {
+        shutdown::os_signal().await;
+        tracing::info!("Shutting down");
+        shutdown.trigger();
+    });
 
-    for f in serve_files::FRONTEND_DIR.files() {
-        let path = f.path().to_str().expect("paths to be normal");
-        let size = human_bytes::human_bytes(f.contents().len() as f64);
-        let served_file = serve_files::ServedFile { path, size: &size };
-        tracing::info!("Serving frontend file: {:?}", served_file);
+    #[cfg(feature = "embed-frontend")]
+    if conf.frontend.dist_path.is_none() {
+        for f in backend::serve_files::FRONTEND_DIR.files() {
+            let path = f.path().to_str().expect("paths to be normal");
+            let size = human_bytes::human_bytes(f.contents().len() as f64);
+            let served_file = backend::serve_files::ServedFile { path, size: &size };
+            tracing::info!("Serving frontend file: {:?}", served_file);
+        }
     }
 
-    application.server().await
+    Ok(application.server().await?)
 }
@@ -0,0 +1,198 @@
+// Media uploads
+//
+// Files are named by a hash of their contents, so uploading the same file twice
+// yields the same entry, and a served file never changes. Types are sniffed from
+// the contents rather than taken from the client. Contents are stored in media.dir,
+// or in the media_blobs relation when no directory is configured.
+//
+
+use crate::{conf::Conf, db};
+use anyhow::Context;
+use sha2::Digest;
+
+// sniffed type and the extension of stored names
+static ALLOWED: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/avif", "avif"),
+    ("application/pdf", "pdf"),
+    ("application/zip", "zip"),
+    ("video/mp4", "mp4"),
+    ("video/webm", "webm"),
+    ("audio/mpeg", "mp3"),
+    ("text/plain", "txt"),
+];
+
+/// Type of an allowed file, sniffed from its contents
+///
+/// SVG and HTML are not allowed, as they could run scripts on the site's origin.
+pub fn sniff(contents: &[u8]) -> Option<&'static str> {
+    let mime = match infer::get(contents) {
+        Some(kind) => kind.mime_type(),
+        None if is_text(contents) => "text/plain",
+        None => return None,
+    };
+
+    ALLOWED
+        .iter()
+        .find(|(allowed, _)| *allowed == mime)
+        .map(|(allowed, _)| *allowed)
+}
+
+fn is_text(contents: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(contents) else {
+        return false;
+    };
+    let markup = text.trim_start().starts_with('<');
+
+    !contents.is_empty() && !markup && !contents.contains(&0)
+}
+
+/// Content addressed name of a file of an allowed type
+pub fn media_id(contents: &[u8], mime: &str) -> Option<String> {
+    let (_, extension) = ALLOWED.iter().find(|(allowed, _)| *allowed == mime)?;
+    let hash = sha2::Sha256::digest(contents);
+
+    Some(format!("{}.{extension}", hex::encode(&hash[..16])))
+}
+
+// ids are validated before touching the filesystem
+fn valid_id(id: &str) -> bool {
+    match id.split_once('.') {
+        Some((hash, extension)) => {
+            hash.len() == 32
+                && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+                && ALLOWED.iter().any(|(_, allowed)| *allowed == extension)
+        }
+        None => false,
+    }
+}
+
+/// Stores a file of a sniffed type, returns the existing entry for known contents
+pub fn store(
+    db: &cozo::DbInstance,
+    conf: &Conf,
+    contents: Vec<u8>,
+    mime: &str,
+    name: &str,
+    uploaded_by: &str,
+) -> anyhow::Result<interfacing::MediaFile> {
+    let id = media_id(&contents, mime).context("type is not allowed")?;
+
+    if let Some(existing) = db::q::find_media_file_by_id(db, &id)? {
+        return Ok(existing);
+    }
+
+    let file = interfacing::MediaFile {
+        id,
+        name: name.into(),
+        mime: mime.into(),
+        size: contents.len() as u64,
+        uploaded_by: uploaded_by.into(),
+        uploaded_at: interfacing::formatted_now(),
+    };
+
+    match &conf.media.dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            std::fs::write(dir.join(&file.id), contents)
+                .with_context(|| format!("failed to write {} to {}", file.id, dir.display()))?;
+        }
+        None => db::q::put_media_blob(db, &file.id, contents)?,
+    }
+    // listed only once the contents are in place
+    db::q::put_media_file(db, file.clone())?;
+
+    Ok(file)
+}
+
+/// A stored file, without its contents
+pub fn find(db: &cozo::DbInstance, id: &str) -> anyhow::Result<Option<interfacing::MediaFile>> {
+    if !valid_id(id) {
+        return Ok(None);
+    }
+    Ok(db::q::find_media_file_by_id(db, id)?)
+}
+
+/// Contents of a file found listed
+pub fn contents(db: &cozo::DbInstance, conf: &Conf, id: &str) -> anyhow::Result<Vec<u8>> {
+    match &conf.media.dir {
+        Some(dir) => std::fs::read(dir.join(id))
+            .with_context(|| format!("failed to read {id} from {}", dir.display())),
+        None => db::q::find_media_blob(db, id)?.context("listed media without contents"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::{Env, EnvConf};
+
+    static PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn sniffing() {
+        assert_eq!(sniff(PNG), Some("image/png"));
+        assert_eq!(sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(sniff(b"plain notes"), Some("text/plain"));
+
+        assert_eq!(sniff(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), None);
+        assert_eq!(sniff(b"  <html><script></script></html>"), None);
+        assert_eq!(sniff(b"\x7fELF\x02\x01\x01\0"), None);
+        assert_eq!(sniff(b""), None);
+    }
+
+    #[test]
+    fn content_addressed_ids() {
+        let id = media_id(PNG, "image/png").unwrap();
+        assert!(id.ends_with(".png"));
+        assert!(valid_id(&id));
+        assert_eq!(Some(id), media_id(PNG, "image/png"));
+        assert_eq!(media_id(PNG, "image/svg+xml"), None);
+
+        assert!(!valid_id("../conf/prod.yaml"));
+        assert!(!valid_id(&"0".repeat(32)));
+        assert!(!valid_id(&format!("{}.html", "0".repeat(32))));
+    }
+
+    fn stored_and_loaded(conf: &Conf) {
+        let db = &cozo::DbInstance::default();
+        db::migrate(db);
+
+        let file = store(db, conf, PNG.to_vec(), "image/png", "diagram.png", "admin").unwrap();
+        assert_eq!(file.size, PNG.len() as u64);
+
+        // same contents, same entry
+        let again = store(db, conf, PNG.to_vec(), "image/png", "copy.png", "admin").unwrap();
+        assert_eq!(again, file);
+        assert_eq!(db::q::find_media_files(db).unwrap(), vec![file.clone()]);
+
+        assert_eq!(find(db, &file.id).unwrap(), Some(file.clone()));
+        assert_eq!(contents(db, conf, &file.id).unwrap(), PNG);
+
+        assert!(find(db, &media_id(b"other", "text/plain").unwrap())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn stored_in_db() {
+        stored_and_loaded(&Conf::new(Env::Local, EnvConf::test_default()));
+    }
+
+    #[test]
+    fn stored_in_dir() {
+        let dir = std::env::temp_dir().join(format!("media-{}", uuid::Uuid::new_v4()));
+        let mut env_conf = EnvConf::test_default();
+        env_conf.media.dir = Some(dir.clone());
+
+        stored_and_loaded(&Conf::new(Env::Local, env_conf));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,319 @@
+// Prometheus metrics
+//
+// Kept in one registry and served at /metrics in the text format. Requests are
+// counted and timed per matched route, the ones served by the fallback, the
+// frontend and its pages, share the "fallback" route. Scrapes are let through
+// by bearer token or by address, as configured under `metrics`, and denied
+// when neither is configured.
+//
+
+use crate::conf::MetricsConf;
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    Opts, Registry, TextEncoder,
+};
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub ws_connections: IntGauge,
+    pub users_online: IntGauge,
+    pub db_query_duration: HistogramVec,
+    pub endpoint_hit_queue: IntGauge,
+    pub endpoint_hits_dropped: IntCounter,
+}
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Responses by route and status class"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time to respond by route"),
+            &["method", "route"],
+        )
+        .unwrap();
+        let ws_connections = IntGauge::new(
+            "ws_connections",
+            "Open websocket connections of users online",
+        )
+        .unwrap();
+        let users_online = IntGauge::new("users_online", "Distinct addresses connected").unwrap();
+        let db_query_duration = HistogramVec::new(
+            HistogramOpts::new("db_query_duration_seconds", "Time to run scripts by query")
+                .buckets(vec![
+                    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25,
+                    0.5, 1.0,
+                ]),
+            &["query"],
+        )
+        .unwrap();
+        let endpoint_hit_queue = IntGauge::new(
+            "endpoint_hit_queue_depth",
+            "Endpoint hits waiting to be written",
+        )
+        .unwrap();
+        let endpoint_hits_dropped = IntCounter::new(
+            "endpoint_hits_dropped_total",
+            "Endpoint hits dropped while the queue was full",
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(ws_connections.clone())).unwrap();
+        registry.register(Box::new(users_online.clone())).unwrap();
+        registry
+            .register(Box::new(db_query_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(endpoint_hit_queue.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(endpoint_hits_dropped.clone()))
+            .unwrap();
+        // cpu, memory and file descriptors, read from /proc
+        #[cfg(target_os = "linux")]
+        registry
+            .register(Box::new(
+                prometheus::process_collector::ProcessCollector::for_self(),
+            ))
+            .unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            ws_connections,
+            users_online,
+            db_query_duration,
+            endpoint_hit_queue,
+            endpoint_hits_dropped,
+        }
+    }
+
+    /// All metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("to encode");
+        String::from_utf8(buffer).expect("text format to be UTF-8")
+    }
+}
+
+/// Observes the duration of a db::q function once dropped
+pub fn db_query_timer(query: &str) -> HistogramTimer {
+    metrics()
+        .db_query_duration
+        .with_label_values(&[query])
+        .start_timer()
+}
+
+pub fn status_class(status: hyper::StatusCode) -> &'static str {
+    match status.as_u16() {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+/// Method label, extension methods are client chosen and unbounded
+pub fn method_label(method: &hyper::Method) -> &'static str {
+    use hyper::Method;
+
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::PATCH => "PATCH",
+        Method::OPTIONS => "OPTIONS",
+        Method::CONNECT => "CONNECT",
+        Method::TRACE => "TRACE",
+        _ => "other",
+    }
+}
+
+/// Counts and times requests by their matched route
+pub async fn track_requests<B>(
+    request: hyper::Request<B>,
+    next: axum::middleware::Next<B>,
+) -> axum::response::Response {
+    // unmatched paths are unbounded
+    let route = request
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map_or("fallback", |path| path.as_str())
+        .to_owned();
+    let method = method_label(request.method());
+    let started = std::time::Instant::now();
+
+    let response = next.run(request).await;
+
+    let metrics = metrics();
+    metrics
+        .http_request_duration
+        .with_label_values(&[method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    metrics
+        .http_requests
+        .with_label_values(&[method, &route, status_class(response.status())])
+        .inc();
+
+    response
+}
+
+/// Address of a scraper, the forwarded one only when the peer is a trusted proxy
+pub fn scraper_ip(
+    conf: &MetricsConf,
+    headers: &hyper::HeaderMap,
+    peer: std::net::IpAddr,
+) -> std::net::IpAddr {
+    if conf.trusted_proxies.contains(&peer) {
+        crate::startup::get_x_forwarded_for(headers).unwrap_or(peer)
+    } else {
+        peer
+    }
+}
+
+pub fn scrape_allowed(
+    conf: &MetricsConf,
+    headers: &hyper::HeaderMap,
+    ip: std::net::IpAddr,
+) -> bool {
+    use secrecy::ExposeSecret;
+
+    let token = conf.token.as_ref().map_or(false, |token| {
+        headers
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map_or(false, |provided| {
+                crate::csrf::constant_time_eq(token.expose_secret().as_bytes(), provided.as_bytes())
+            })
+    });
+    token || conf.allowed_ips.contains(&ip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(authorization: Option<&'static str>) -> hyper::HeaderMap {
+        let mut headers = hyper::HeaderMap::new();
+        if let Some(value) = authorization {
+            headers.insert(hyper::header::AUTHORIZATION, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn scrapes_by_token_or_address() {
+        let local: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+        let remote: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+
+        let unconfigured = MetricsConf::default();
+        assert!(!scrape_allowed(&unconfigured, &headers(None), local));
+
+        let protected = MetricsConf {
+            token: Some(secrecy::SecretString::new("secret".into())),
+            allowed_ips: vec![local],
+            ..Default::default()
+        };
+        assert!(scrape_allowed(&protected, &headers(None), local));
+        assert!(scrape_allowed(
+            &protected,
+            &headers(Some("Bearer secret")),
+            remote
+        ));
+        assert!(!scrape_allowed(&protected, &headers(None), remote));
+        assert!(!scrape_allowed(
+            &protected,
+            &headers(Some("Bearer wrong")),
+            remote
+        ));
+        assert!(!scrape_allowed(
+            &protected,
+            &headers(Some("secret")),
+            remote
+        ));
+    }
+
+    #[test]
+    fn forwarded_only_by_trusted_proxies() {
+        let local: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+        let remote: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+        let mut spoofed = headers(None);
+        spoofed.insert("x-forwarded-for", "127.0.0.1".parse().unwrap());
+
+        let conf = MetricsConf {
+            allowed_ips: vec![local],
+            ..Default::default()
+        };
+        assert_eq!(scraper_ip(&conf, &spoofed, remote), remote);
+        assert!(!scrape_allowed(
+            &conf,
+            &spoofed,
+            scraper_ip(&conf, &spoofed, remote)
+        ));
+
+        let behind_proxy = MetricsConf {
+            trusted_proxies: vec![local],
+            ..conf
+        };
+        let mut forwarded = headers(None);
+        // appended by the proxy after what the client sent
+        forwarded.insert("x-forwarded-for", "127.0.0.1, 203.0.113.7".parse().unwrap());
+        assert_eq!(scraper_ip(&behind_proxy, &forwarded, local), remote);
+        assert_eq!(scraper_ip(&behind_proxy, &headers(None), local), local);
+    }
+
+    #[test]
+    fn rendered_as_text() {
+        metrics()
+            .http_requests
+            .with_label_values(&["GET", "/metrics", "2xx"])
+            .inc();
+        drop(db_query_timer("find_articles"));
+
+        let text = metrics().render();
+        assert!(text.contains(r#"http_requests_total{method="GET",route="/metrics",status="2xx"}"#));
+        assert!(text.contains(r#"db_query_duration_seconds_count{query="find_articles"}"#));
+        assert!(text.contains("# TYPE endpoint_hit_queue_depth gauge"));
+        assert!(text.contains("# TYPE endpoint_hits_dropped_total counter"));
+    }
+
+    #[test]
+    fn status_classes() {
+        assert_eq!(status_class(hyper::StatusCode::OK), "2xx");
+        assert_eq!(status_class(hyper::StatusCode::NOT_MODIFIED), "3xx");
+        assert_eq!(status_class(hyper::StatusCode::NOT_FOUND), "4xx");
+        assert_eq!(status_class(hyper::StatusCode::BAD_GATEWAY), "5xx");
+    }
+
+    #[test]
+    fn method_labels() {
+        assert_eq!(method_label(&hyper::Method::GET), "GET");
+        assert_eq!(method_label(&hyper::Method::PATCH), "PATCH");
+        let extension = hyper::Method::from_bytes(b"FOO1").unwrap();
+        assert_eq!(method_label(&extension), "other");
+    }
+}
@@ -0,0 +1,451 @@
+// OpenID Connect login, authorization code flow with PKCE
+//
+// The flow state lives in the session between the redirect to the provider
+// and the callback from it
+//
+
+use crate::conf::OidcConf;
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub static FLOW_KEY: &str = "oidc_flow";
+
+pub type Claims = serde_json::Map<String, serde_json::Value>;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Flow {
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+impl Flow {
+    pub fn generate() -> Self {
+        Self {
+            state: random_token(),
+            nonce: random_token(),
+            code_verifier: random_token(),
+        }
+    }
+
+    pub fn code_challenge(&self) -> String {
+        pkce_challenge(&self.code_verifier)
+    }
+}
+
+// S256 method
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+fn random_token() -> String {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    let random_bytes: Vec<u8> = (0..32).map(|_| rng.gen::<u8>()).collect();
+    URL_SAFE_NO_PAD.encode(random_bytes)
+}
+
+/// Client for requests to the identity provider, shared by all logins
+///
+/// A slow or hung provider must not hold login requests open.
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(5))
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .expect("a client of the default TLS backend")
+}
+
+pub async fn discover(http: &reqwest::Client, issuer: &str) -> anyhow::Result<ProviderMetadata> {
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    );
+
+    let metadata: ProviderMetadata = http
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("malformed provider metadata")?;
+
+    anyhow::ensure!(
+        metadata.issuer == issuer,
+        "provider metadata is of another issuer: {}",
+        metadata.issuer
+    );
+
+    Ok(metadata)
+}
+
+pub fn authorization_url(
+    metadata: &ProviderMetadata,
+    conf: &OidcConf,
+    flow: &Flow,
+) -> anyhow::Result<url::Url> {
+    let mut url = url::Url::parse(&metadata.authorization_endpoint)
+        .context("malformed authorization endpoint")?;
+
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &conf.client_id)
+        .append_pair("redirect_uri", &conf.redirect_url)
+        .append_pair("scope", &conf.scopes.join(" "))
+        .append_pair("state", &flow.state)
+        .append_pair("nonce", &flow.nonce)
+        .append_pair("code_challenge", &flow.code_challenge())
+        .append_pair("code_challenge_method", "S256");
+
+    Ok(url)
+}
+
+/// Exchanges the authorization code for an ID token
+pub async fn exchange_code(
+    http: &reqwest::Client,
+    metadata: &ProviderMetadata,
+    conf: &OidcConf,
+    flow: &Flow,
+    code: &str,
+) -> anyhow::Result<String> {
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        id_token: String,
+    }
+
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", &conf.redirect_url),
+        ("client_id", &conf.client_id),
+        ("code_verifier", &flow.code_verifier),
+    ];
+
+    if let Some(client_secret) = &conf.client_secret {
+        form.push(("client_secret", client_secret.expose_secret()));
+    }
+
+    let response: TokenResponse = http
+        .post(&metadata.token_endpoint)
+        .form(&form)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("malformed token response")?;
+
+    Ok(response.id_token)
+}
+
+pub async fn validate_id_token(
+    http: &reqwest::Client,
+    metadata: &ProviderMetadata,
+    conf: &OidcConf,
+    flow: &Flow,
+    id_token: &str,
+) -> anyhow::Result<Claims> {
+    let header = jsonwebtoken::decode_header(id_token).context("malformed ID token")?;
+
+    let key = match header.alg {
+        // signed with the client secret
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            let client_secret = conf
+                .client_secret
+                .as_ref()
+                .context("HMAC signed ID token without client_secret configured")?;
+            DecodingKey::from_secret(client_secret.expose_secret().as_bytes())
+        }
+        _ => {
+            let jwks: JwkSet = http
+                .get(&metadata.jwks_uri)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await
+                .context("malformed JWKS")?;
+
+            let jwk = match &header.kid {
+                Some(kid) => jwks.find(kid),
+                None => jwks.keys.first(),
+            }
+            .context("no key to verify ID token with")?;
+
+            DecodingKey::from_jwk(jwk)?
+        }
+    };
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&conf.client_id]);
+    validation.set_issuer(&[&metadata.issuer]);
+
+    let claims = jsonwebtoken::decode::<Claims>(id_token, &key, &validation)
+        .context("invalid ID token")?
+        .claims;
+
+    anyhow::ensure!(
+        claims.get("nonce").and_then(|v| v.as_str()) == Some(flow.nonce.as_str()),
+        "ID token nonce mismatch"
+    );
+
+    Ok(claims)
+}
+
+pub fn username_from_claims(claims: &Claims, conf: &OidcConf) -> anyhow::Result<String> {
+    // unverified emails can be set to anything, a missing claim included
+    if conf.username_claim == "email"
+        && claims.get("email_verified").and_then(|v| v.as_bool()) != Some(true)
+    {
+        anyhow::bail!("email is not verified");
+    }
+
+    claims
+        .get(&conf.username_claim)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+        .with_context(|| format!("ID token has no {} claim", conf.username_claim))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        routing::{get, post},
+        Form, Json, Router,
+    };
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    static KEY_PEM: &str = include_str!("../tests/fixtures/oidc_mock_issuer.pem");
+    static JWKS: &str = include_str!("../tests/fixtures/oidc_mock_issuer_jwks.json");
+    static CLIENT_ID: &str = "wsite";
+
+    // code -> (code challenge, nonce)
+    type Codes = Arc<Mutex<HashMap<String, (String, String)>>>;
+
+    struct MockIssuer {
+        url: String,
+        codes: Codes,
+    }
+
+    impl MockIssuer {
+        // what the provider does once the user authenticates
+        fn authorize(&self, authorization_url: &url::Url) -> String {
+            let query = authorization_url
+                .query_pairs()
+                .into_owned()
+                .collect::<HashMap<_, _>>();
+            assert_eq!(query["code_challenge_method"], "S256");
+
+            let code = random_token();
+            self.codes.lock().unwrap().insert(
+                code.clone(),
+                (query["code_challenge"].clone(), query["nonce"].clone()),
+            );
+            code
+        }
+    }
+
+    async fn spawn_mock_issuer() -> MockIssuer {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let codes = Codes::default();
+
+        let metadata = serde_json::json!({
+            "issuer": url,
+            "authorization_endpoint": format!("{url}/authorize"),
+            "token_endpoint": format!("{url}/token"),
+            "jwks_uri": format!("{url}/jwks"),
+        });
+
+        let token = {
+            let url = url.clone();
+            let codes = codes.clone();
+
+            move |Form(form): Form<HashMap<String, String>>| async move {
+                let (code_challenge, nonce) = codes
+                    .lock()
+                    .unwrap()
+                    .remove(&form["code"])
+                    .ok_or(hyper::StatusCode::BAD_REQUEST)?;
+
+                if pkce_challenge(&form["code_verifier"]) != code_challenge
+                    || form["client_id"] != CLIENT_ID
+                {
+                    return Err(hyper::StatusCode::BAD_REQUEST);
+                }
+
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+
+                let claims = serde_json::json!({
+                    "iss": url,
+                    "aud": CLIENT_ID,
+                    "sub": "1",
+                    "email": "editor@example.com",
+                    "email_verified": true,
+                    "nonce": nonce,
+                    "iat": now,
+                    "exp": now + 300,
+                });
+
+                let mut header = jsonwebtoken::Header::new(Algorithm::RS256);
+                header.kid = Some("mock".into());
+                let id_token = jsonwebtoken::encode(
+                    &header,
+                    &claims,
+                    &jsonwebtoken::EncodingKey::from_rsa_pem(KEY_PEM.as_bytes()).unwrap(),
+                )
+                .unwrap();
+
+                Ok(Json(serde_json::json!({
+                    "access_token": "access_token",
+                    "token_type": "Bearer",
+                    "id_token": id_token,
+                })))
+            }
+        };
+
+        let app = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get(move || async move { Json(metadata) }),
+            )
+            .route(
+                "/jwks",
+                get(|| async { Json(serde_json::from_str::<serde_json::Value>(JWKS).unwrap()) }),
+            )
+            .route("/token", post(token));
+
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        MockIssuer { url, codes }
+    }
+
+    fn conf(issuer: &MockIssuer) -> OidcConf {
+        OidcConf {
+            issuer: issuer.url.clone(),
+            client_id: CLIENT_ID.into(),
+            client_secret: None,
+            redirect_url: "http://localhost:8000/api/login/oidc/callback".into(),
+            scopes: OidcConf::default_scopes(),
+            username_claim: OidcConf::default_username_claim(),
+            provision_users: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn login_flow() {
+        let issuer = spawn_mock_issuer().await;
+        let conf = conf(&issuer);
+        let http = http_client();
+
+        let metadata = discover(&http, &conf.issuer).await.unwrap();
+        let flow = Flow::generate();
+        let url = authorization_url(&metadata, &conf, &flow).unwrap();
+        let code = issuer.authorize(&url);
+
+        let id_token = exchange_code(&http, &metadata, &conf, &flow, &code)
+            .await
+            .unwrap();
+        let claims = validate_id_token(&http, &metadata, &conf, &flow, &id_token)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            username_from_claims(&claims, &conf).unwrap(),
+            "editor@example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn code_verifier_must_match() {
+        let issuer = spawn_mock_issuer().await;
+        let conf = conf(&issuer);
+        let http = http_client();
+
+        let metadata = discover(&http, &conf.issuer).await.unwrap();
+        let flow = Flow::generate();
+        let code = issuer.authorize(&authorization_url(&metadata, &conf, &flow).unwrap());
+
+        let other_flow = Flow::generate();
+        assert!(exchange_code(&http, &metadata, &conf, &other_flow, &code)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn nonce_must_match() {
+        let issuer = spawn_mock_issuer().await;
+        let conf = conf(&issuer);
+        let http = http_client();
+
+        let metadata = discover(&http, &conf.issuer).await.unwrap();
+        let flow = Flow::generate();
+        let code = issuer.authorize(&authorization_url(&metadata, &conf, &flow).unwrap());
+        let id_token = exchange_code(&http, &metadata, &conf, &flow, &code)
+            .await
+            .unwrap();
+
+        let other_flow = Flow {
+            nonce: random_token(),
+            ..flow
+        };
+        assert!(
+            validate_id_token(&http, &metadata, &conf, &other_flow, &id_token)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn unverified_email_rejected() {
+        let conf = OidcConf {
+            issuer: String::new(),
+            client_id: CLIENT_ID.into(),
+            client_secret: None,
+            redirect_url: String::new(),
+            scopes: OidcConf::default_scopes(),
+            username_claim: OidcConf::default_username_claim(),
+            provision_users: false,
+        };
+
+        let claims = |value: serde_json::Value| value.as_object().unwrap().clone();
+
+        assert!(username_from_claims(
+            &claims(serde_json::json!({"email": "editor@example.com", "email_verified": false})),
+            &conf
+        )
+        .is_err());
+        // providers letting users set their email may leave the claim out
+        assert!(username_from_claims(
+            &claims(serde_json::json!({"email": "editor@example.com"})),
+            &conf
+        )
+        .is_err());
+        assert!(username_from_claims(&claims(serde_json::json!({})), &conf).is_err());
+    }
+}
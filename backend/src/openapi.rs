@@ -0,0 +1,586 @@
+// OpenAPI document of the API
+//
+// Paths come from static_routes and schemas from the interfacing types, while
+// which methods, parameters, bodies and auth each route has is listed here.
+// Operations are made from the routes themselves, by the method traits they
+// implement, so a path can't be listed that static_routes doesn't declare.
+// Every route `startup::router` registers is expected to be listed, a test
+// checks it.
+//
+
+use crate::routes::{CallbackParams, FeedQuery, IpToHit, MediaUpload};
+use hyper::Method;
+use once_cell::sync::Lazy;
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::ObjectValidation,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+use static_routes::*;
+
+static DOCUMENT: Lazy<Value> = Lazy::new(document);
+
+/// The document, generated on first use
+pub fn openapi() -> &'static Value {
+    &DOCUMENT
+}
+
+#[derive(Clone, Copy)]
+enum Auth {
+    Public,
+    // logged in session cookie
+    Session,
+    // logged in session cookie, with its CSRF token echoed, see csrf.rs
+    SessionCsrf,
+    // metrics.token as a bearer token, unless the address is allowed
+    MetricsToken,
+}
+
+enum Content {
+    Json(Value),
+    // schemaless, by media type
+    Other(&'static str),
+    None,
+}
+
+struct Operation {
+    method: Method,
+    // in axum syntax, parameters as :name and *name
+    path: String,
+    summary: &'static str,
+    auth: Auth,
+    parameters: Vec<Value>,
+    request: Content,
+    responses: Vec<(u16, &'static str, Content)>,
+}
+
+impl Operation {
+    fn get(route: &impl Get, summary: &'static str) -> Self {
+        Self::new(Method::GET, route.get(), summary)
+    }
+
+    fn post(route: &impl Post, summary: &'static str) -> Self {
+        Self::new(Method::POST, route.post(), summary)
+    }
+
+    fn put(route: &impl Put, summary: &'static str) -> Self {
+        Self::new(Method::PUT, route.put(), summary)
+    }
+
+    fn delete(route: &impl Delete, summary: &'static str) -> Self {
+        Self::new(Method::DELETE, route.delete(), summary)
+    }
+
+    // parameters are in the pattern, the route is requested filled in
+    fn get_pattern<R: WithParams>(route: &R, summary: &'static str) -> Self
+    where
+        Filled<R>: Get,
+    {
+        Self::new(Method::GET, route.pattern(), summary)
+    }
+
+    fn delete_pattern<R: WithParams>(route: &R, summary: &'static str) -> Self
+    where
+        Filled<R>: Delete,
+    {
+        Self::new(Method::DELETE, route.pattern(), summary)
+    }
+
+    fn new(method: Method, path: RelativePath, summary: &'static str) -> Self {
+        let path = path.complete().to_owned();
+        let parameters = path_parameters(&path)
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+
+        Self {
+            method,
+            path,
+            summary,
+            auth: Auth::Public,
+            parameters,
+            request: Content::None,
+            responses: vec![],
+        }
+    }
+
+    fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Query parameters, by the fields of T
+    fn query<T: JsonSchema>(mut self, gen: &mut SchemaGenerator) -> Self {
+        let schema = gen.root_schema_for::<T>().schema;
+        if let Some(object) = schema.object {
+            let ObjectValidation {
+                properties,
+                required,
+                ..
+            } = *object;
+            for (name, schema) in properties {
+                self.parameters.push(json!({
+                    "in": "query",
+                    "required": required.contains(&name),
+                    "name": name,
+                    "schema": schema,
+                }));
+            }
+        }
+        self
+    }
+
+    fn request(mut self, content: Content) -> Self {
+        self.request = content;
+        self
+    }
+
+    fn respond(mut self, status: u16, description: &'static str, content: Content) -> Self {
+        self.responses.push((status, description, content));
+        self
+    }
+
+    fn to_value(&self) -> Value {
+        let mut responses = Map::new();
+        for (status, description, body) in &self.responses {
+            let mut response = json!({ "description": description });
+            if let Some(body) = content(body) {
+                response["content"] = body;
+            }
+            responses.insert(status.to_string(), response);
+        }
+
+        let rejections: &[(u16, &str)] = match self.auth {
+            Auth::Public => &[],
+            Auth::Session => &[(401, "Not logged in")],
+            Auth::SessionCsrf => &[
+                (401, "Not logged in"),
+                (403, "Cross-site request, or the CSRF token is missing"),
+            ],
+            Auth::MetricsToken => &[(403, "Neither the token nor the address is allowed")],
+        };
+        for (status, description) in rejections {
+            responses
+                .entry(status.to_string())
+                .or_insert_with(|| json!({ "description": description }));
+        }
+
+        let mut operation = json!({
+            "summary": self.summary,
+            "responses": responses,
+        });
+        if !self.parameters.is_empty() {
+            operation["parameters"] = self.parameters.clone().into();
+        }
+        if let Some(body) = content(&self.request) {
+            operation["requestBody"] = json!({ "required": true, "content": body });
+        }
+        if let Some(security) = self.auth.security() {
+            operation["security"] = security;
+        }
+        operation
+    }
+}
+
+impl Auth {
+    fn security(self) -> Option<Value> {
+        match self {
+            Self::Public => None,
+            Self::Session => Some(json!([{ "session": [] }])),
+            Self::SessionCsrf => Some(json!([{ "session": [], "csrf": [] }])),
+            // the empty requirement stands for allowed addresses
+            Self::MetricsToken => Some(json!([{ "metrics_token": [] }, {}])),
+        }
+    }
+}
+
+fn content(content: &Content) -> Option<Value> {
+    let (media_type, schema) = match content {
+        Content::Json(schema) => ("application/json", json!({ "schema": schema })),
+        Content::Other(media_type) => (*media_type, json!({})),
+        Content::None => return None,
+    };
+    let mut content = Map::new();
+    content.insert(media_type.to_owned(), schema);
+    Some(content.into())
+}
+
+fn json<T: JsonSchema>(gen: &mut SchemaGenerator) -> Content {
+    Content::Json(serde_json::to_value(gen.subschema_for::<T>()).expect("to serialize"))
+}
+
+// defined by webauthn-rs, which has no schemas
+fn webauthn(description: &'static str) -> Content {
+    Content::Json(json!({ "type": "object", "description": description }))
+}
+
+fn path_parameters(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix(':').or(segment.strip_prefix('*')))
+}
+
+/// Path in OpenAPI syntax, parameters as {name}
+pub fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(
+            |segment| match segment.strip_prefix(':').or(segment.strip_prefix('*')) {
+                Some(name) => format!("{{{name}}}"),
+                None => segment.to_owned(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+
+    let mut paths = Map::new();
+    for operation in operations(&mut gen) {
+        let path = paths
+            .entry(openapi_path(&operation.path))
+            .or_insert_with(|| json!({}));
+        path[operation.method.as_str().to_lowercase()] = operation.to_value();
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "wsite",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": gen.take_definitions(),
+            "securitySchemes": {
+                "session": {
+                    "type": "apiKey",
+                    "in": "cookie",
+                    "name": crate::session_keys::SESSION_COOKIE_NAME,
+                },
+                "csrf": {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": interfacing::CSRF_TOKEN_HEADER,
+                },
+                "metrics_token": {
+                    "type": "http",
+                    "scheme": "bearer",
+                },
+            },
+        },
+    })
+}
+
+fn operations(gen: &mut SchemaGenerator) -> Vec<Operation> {
+    use crate::feeds::Format;
+    use Auth::*;
+
+    let api = routes().api;
+    let root = routes().root;
+    let ws = routes().ws;
+
+    vec![
+        // health
+        Operation::get(
+            &api.health_check,
+            "Responds while the server runs, kept for probes predating /api/health",
+        )
+        .respond(200, "Running", Content::None),
+        Operation::get(&api.health.live, "Responds while the server runs").respond(
+            200,
+            "Running",
+            Content::None,
+        ),
+        Operation::get(
+            &api.health.ready,
+            "Whether the db, static files and session keys are usable",
+        )
+        .respond(200, "Ready", json::<interfacing::Readiness>(gen))
+        .respond(503, "Not ready", json::<interfacing::Readiness>(gen)),
+        Operation::get(&api.version, "Build of the running backend").respond(
+            200,
+            "Build info",
+            json::<interfacing::BuildInfo>(gen),
+        ),
+        Operation::get(&api.openapi, "This document").respond(
+            200,
+            "OpenAPI 3 document",
+            Content::Other("application/json"),
+        ),
+        // login
+        Operation::post(&api.login, "Logs in by password")
+            .request(json::<interfacing::LoginForm>(gen))
+            .respond(200, "Logged in", Content::None)
+            .respond(
+                202,
+                "Password verified, to be completed by a passkey",
+                Content::None,
+            )
+            .respond(401, "Wrong credentials", Content::None),
+        Operation::post(&api.login.passkey.start, "Starts a passkey login")
+            .request(json::<interfacing::PasskeyLoginStart>(gen))
+            .respond(200, "Challenge", webauthn("RequestChallengeResponse")),
+        Operation::post(&api.login.passkey.finish, "Completes a passkey login")
+            .request(webauthn("PublicKeyCredential"))
+            .respond(200, "Logged in", Content::None)
+            .respond(401, "Rejected credential", Content::None),
+        Operation::get(
+            &api.login.oidc,
+            "Starts a login through the identity provider",
+        )
+        .respond(
+            303,
+            "To the identity provider, or back to the login page",
+            Content::None,
+        ),
+        Operation::get(
+            &api.login.oidc.callback,
+            "Completes a login through the identity provider",
+        )
+        .query::<CallbackParams>(gen)
+        .respond(
+            303,
+            "Logged in to the dashboard, or back to the login page",
+            Content::None,
+        ),
+        // articles
+        Operation::get(&api.articles, "Articles, drafts included when logged in").respond(
+            200,
+            "Articles",
+            json::<Vec<interfacing::ArticleWithId>>(gen),
+        ),
+        Operation::get_pattern(&api.articles.by_public_id, "Article by its public id")
+            .respond(200, "Article", json::<interfacing::ArticleWithId>(gen))
+            .respond(404, "No such article", Content::None),
+        Operation::delete_pattern(&api.articles.by_public_id, "Deletes an article by its id")
+            .auth(SessionCsrf)
+            .respond(200, "Deleted", Content::None),
+        Operation::post(&api.admin.articles, "Creates an article")
+            .auth(SessionCsrf)
+            .request(json::<interfacing::Article>(gen))
+            .respond(200, "Created", json::<interfacing::ArticleWithId>(gen)),
+        Operation::put(&api.admin.articles, "Updates an article")
+            .auth(SessionCsrf)
+            .request(json::<interfacing::ArticleWithId>(gen))
+            .respond(200, "Updated", Content::None),
+        // admin
+        Operation::get(
+            &api.admin.session,
+            "Logged in user and the session's CSRF token",
+        )
+        .auth(Session)
+        .respond(200, "Session", json::<interfacing::AdminSession>(gen)),
+        Operation::post(&api.admin.password, "Changes the password")
+            .auth(SessionCsrf)
+            .request(json::<interfacing::PasswordChangeForm>(gen))
+            .respond(200, "Changed", Content::None),
+        Operation::post(&api.admin.logout, "Logs out")
+            .auth(SessionCsrf)
+            .respond(200, "Logged out", Content::None),
+        Operation::get(
+            &api.admin.endpoint_hits,
+            "Endpoint hits, logged in only in prod",
+        )
+        .auth(Session)
+        .respond(
+            200,
+            "Endpoint hits",
+            json::<Vec<interfacing::EndpointHit>>(gen),
+        ),
+        Operation::get(
+            &api.admin.endpoint_hits.grouped,
+            "Endpoint hits by hashed address, logged in only in prod",
+        )
+        .auth(Session)
+        .respond(200, "Endpoint hits", json::<Vec<IpToHit>>(gen)),
+        Operation::get(&api.admin.audit_log, "Audit log entries")
+            .auth(Session)
+            .query::<interfacing::AuditLogFilter>(gen)
+            .respond(200, "Entries", json::<Vec<interfacing::AuditLogEntry>>(gen)),
+        Operation::get(&api.admin.sessions, "Sessions of the logged in user")
+            .auth(Session)
+            .respond(200, "Sessions", json::<Vec<interfacing::SessionInfo>>(gen)),
+        Operation::post(&api.admin.sessions.revoke, "Revokes a session")
+            .auth(SessionCsrf)
+            .request(json::<interfacing::RevokeSession>(gen))
+            .respond(200, "Revoked", Content::None),
+        Operation::post(
+            &api.admin.sessions.revoke_others,
+            "Revokes all sessions but the current one",
+        )
+        .auth(SessionCsrf)
+        .respond(200, "Revoked", Content::None),
+        Operation::post(
+            &api.admin.passkeys.register.start,
+            "Starts a passkey registration",
+        )
+        .auth(SessionCsrf)
+        .request(json::<interfacing::PasskeyRegistrationStart>(gen))
+        .respond(200, "Challenge", webauthn("CreationChallengeResponse")),
+        Operation::post(
+            &api.admin.passkeys.register.finish,
+            "Completes a passkey registration",
+        )
+        .auth(SessionCsrf)
+        .request(webauthn("RegisterPublicKeyCredential"))
+        .respond(200, "Registered", Content::None),
+        Operation::get(
+            &api.admin.features,
+            "Feature flags, configured and overridden",
+        )
+        .auth(Session)
+        .respond(
+            200,
+            "Feature flags",
+            json::<Vec<interfacing::FeatureFlagInfo>>(gen),
+        ),
+        Operation::post(
+            &api.admin.features.set,
+            "Overrides a feature flag, or clears its override",
+        )
+        .auth(SessionCsrf)
+        .request(json::<interfacing::FeatureFlagOverride>(gen))
+        .respond(200, "Set", Content::None),
+        Operation::get(&api.admin.media, "Uploaded media")
+            .auth(Session)
+            .respond(200, "Media", json::<Vec<interfacing::MediaFile>>(gen)),
+        Operation::post(&api.admin.media.upload, "Uploads a file as the body")
+            .auth(SessionCsrf)
+            .query::<MediaUpload>(gen)
+            .request(Content::Other("application/octet-stream"))
+            .respond(200, "Uploaded", json::<interfacing::MediaFile>(gen))
+            .respond(413, "Larger than media.max_size_bytes", Content::None),
+        // files
+        Operation::get_pattern(&api.static_files, "Static file")
+            .respond(200, "File", Content::Other("application/octet-stream"))
+            .respond(
+                206,
+                "Requested range",
+                Content::Other("application/octet-stream"),
+            )
+            .respond(304, "Not modified", Content::None)
+            .respond(404, "No such file", Content::None),
+        Operation::get_pattern(&api.media, "Uploaded file")
+            .respond(200, "File", Content::Other("application/octet-stream"))
+            .respond(304, "Not modified", Content::None)
+            .respond(404, "No such file", Content::None),
+        // visitors
+        Operation::get(&api.features, "Feature flags evaluated for the visitor").respond(
+            200,
+            "Feature flags",
+            json::<interfacing::Features>(gen),
+        ),
+        Operation::post(
+            &api.endpoint_hits.frontend,
+            "Counts a page visited in the frontend",
+        )
+        .request(json::<interfacing::FrontendEndpointHit>(gen))
+        .respond(200, "Counted", Content::None),
+        Operation::get(
+            &api.endpoint_hits.github.profile,
+            "Counts a view of the GitHub profile, embedded there as an image",
+        )
+        .respond(404, "Counted", Content::None),
+        Operation::get(
+            &api.endpoint_hits.github.wsite,
+            "Counts a view of the repository, embedded there as an image",
+        )
+        .respond(404, "Counted", Content::None),
+        Operation::get(
+            &ws.users_online,
+            "Websocket sending the number of users online",
+        )
+        .respond(101, "Upgraded", Content::None)
+        .respond(404, "Disabled by the users_online feature", Content::None),
+        Operation::get(
+            &ws.dev_reload,
+            "Websocket sending reload on frontend changes, when frontend.dist_path is set",
+        )
+        .respond(101, "Upgraded", Content::None),
+        // outside of /api
+        Operation::get(&root.feeds.rss, "RSS feed of published articles")
+            .query::<FeedQuery>(gen)
+            .respond(200, "Feed", Content::Other(Format::Rss.content_type())),
+        Operation::get(&root.feeds.atom, "Atom feed of published articles")
+            .query::<FeedQuery>(gen)
+            .respond(200, "Feed", Content::Other(Format::Atom.content_type())),
+        Operation::get(&root.feeds.json, "JSON feed of published articles")
+            .query::<FeedQuery>(gen)
+            .respond(200, "Feed", Content::Other(Format::Json.content_type())),
+        Operation::get(&root.sitemap, "Sitemap").respond(
+            200,
+            "Sitemap",
+            Content::Other("application/xml"),
+        ),
+        Operation::get(&root.robots, "robots.txt").respond(
+            200,
+            "robots.txt",
+            Content::Other("text/plain"),
+        ),
+        Operation::get(&root.metrics, "Prometheus metrics")
+            .auth(MetricsToken)
+            .respond(200, "Metrics", Content::Other(prometheus::TEXT_FORMAT)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::{Conf, Env, EnvConf};
+
+    #[test]
+    fn registered_routes_documented() {
+        let mut env_conf = EnvConf::test_default();
+        // ws.dev_reload is registered only then
+        env_conf.frontend.dist_path = Some("dist".into());
+        let conf = Conf::new(Env::Local, env_conf);
+
+        let paths = &openapi()["paths"];
+        for (method, path) in crate::startup::registered_routes(&conf) {
+            let operation = &paths[openapi_path(&path)][method.as_str().to_lowercase()];
+            assert!(operation.is_object(), "{method} {path} is not documented");
+        }
+    }
+
+    #[test]
+    fn schemas_resolve() {
+        let document = openapi();
+        let schemas = &document["components"]["schemas"];
+
+        let text = document.to_string();
+        let references = text
+            .split(r##""$ref":"#/components/schemas/"##)
+            .skip(1)
+            .map(|rest| &rest[..rest.find('"').unwrap()]);
+        for name in references {
+            assert!(schemas[name].is_object(), "{name} is not defined");
+        }
+        assert!(schemas["LoginForm"]["properties"]["password"].is_object());
+    }
+
+    #[test]
+    fn parameters() {
+        assert_eq!(
+            openapi_path("/api/articles/:public_id"),
+            "/api/articles/{public_id}"
+        );
+        assert_eq!(openapi_path("/api/static/*path"), "/api/static/{path}");
+
+        let paths = &openapi()["paths"];
+        let parameters = &paths["/api/articles/{public_id}"]["get"]["parameters"];
+        assert_eq!(parameters[0]["name"], "public_id");
+        assert_eq!(parameters[0]["in"], "path");
+
+        let parameters = &paths["/feed.xml"]["get"]["parameters"];
+        assert_eq!(parameters[0]["name"], "tag");
+        assert_eq!(parameters[0]["required"], false);
+    }
+}
@@ -0,0 +1,239 @@
+// Link previews of article pages
+//
+// Chat apps and crawlers unfurling a link read the served HTML without running
+// the frontend, so article pages get their title, an excerpt, OpenGraph and
+// Twitter card tags and a canonical link injected into the head of index.html.
+//
+
+use crate::conf::SiteConf;
+use crate::db;
+use crate::feeds::escape;
+use static_routes::*;
+
+// about what previews show before cutting off
+const DESCRIPTION_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageMeta {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    pub image: Option<String>,
+    pub site_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArticlePage {
+    Found(PageMeta),
+    // drafts are served as is, their titles are not for previews
+    Draft,
+    NotFound,
+}
+
+/// Public id of an /articles/:public_id path, without the leading slash
+pub fn article_public_id(path: &str) -> Option<&str> {
+    let public_id = path.strip_prefix("articles/")?;
+    (!public_id.is_empty() && !public_id.contains('/')).then_some(public_id)
+}
+
+pub fn article_page(
+    db: &cozo::DbInstance,
+    site: &SiteConf,
+    public_id: &str,
+) -> db::Result<ArticlePage> {
+    let article = routes().root.articles.by_public_id;
+    let url = site.url(article.with(public_id).get().complete());
+
+    if let Some(article) = interfacing::static_articles()
+        .into_iter()
+        .find(|article| article.public_id == public_id)
+    {
+        return Ok(ArticlePage::Found(PageMeta {
+            title: article.title,
+            description: site.description.clone(),
+            url,
+            image: None,
+            site_name: site.title.clone(),
+        }));
+    }
+
+    let page = match db::q::find_article_by_public_id(db, public_id)? {
+        None => ArticlePage::NotFound,
+        Some(article) if article.body().draft => ArticlePage::Draft,
+        Some(article) => {
+            let markdown = &article.body().markdown;
+            let description = match interfacing::markdown_excerpt(markdown, DESCRIPTION_CHARS) {
+                excerpt if excerpt.is_empty() => site.description.clone(),
+                excerpt => excerpt,
+            };
+            // previews fetch images from elsewhere, uploads are linked by path
+            let image = interfacing::markdown_first_image(markdown).map(|image| {
+                if image.starts_with('/') {
+                    site.url(&image)
+                } else {
+                    image
+                }
+            });
+
+            ArticlePage::Found(PageMeta {
+                title: article.body().title.clone(),
+                description,
+                url,
+                image,
+                site_name: site.title.clone(),
+            })
+        }
+    };
+
+    Ok(page)
+}
+
+impl PageMeta {
+    fn tags(&self) -> String {
+        let meta = |attribute: &str, name: &str, content: &str| {
+            format!(
+                r#"<meta {attribute}="{name}" content="{}" />"#,
+                escape(content)
+            )
+        };
+        let card = match self.image {
+            Some(_) => "summary_large_image",
+            None => "summary",
+        };
+
+        let mut tags = vec![
+            format!("<title>{}</title>", escape(&self.title)),
+            meta("name", "description", &self.description),
+            format!(r#"<link rel="canonical" href="{}" />"#, escape(&self.url)),
+            meta("property", "og:type", "article"),
+            meta("property", "og:site_name", &self.site_name),
+            meta("property", "og:title", &self.title),
+            meta("property", "og:description", &self.description),
+            meta("property", "og:url", &self.url),
+            meta("name", "twitter:card", card),
+            meta("name", "twitter:title", &self.title),
+            meta("name", "twitter:description", &self.description),
+        ];
+        if let Some(image) = &self.image {
+            tags.push(meta("property", "og:image", image));
+            tags.push(meta("name", "twitter:image", image));
+        }
+
+        tags.join("\n    ")
+    }
+
+    /// index.html with the tags at the end of its head, replacing its title
+    pub fn inject(&self, index: &str) -> String {
+        let index = without_title(index);
+
+        match index.find("</head>") {
+            Some(end) => format!("{}    {}\n{}", &index[..end], self.tags(), &index[end..]),
+            None => index,
+        }
+    }
+}
+
+fn without_title(index: &str) -> String {
+    let title = index.find("<title>").and_then(|start| {
+        let end = index[start..].find("</title>")? + start + "</title>".len();
+        Some((start, end))
+    });
+
+    match title {
+        Some((start, end)) => format!("{}{}", &index[..start], &index[end..]),
+        None => index.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::EnvConf;
+
+    static INDEX: &str =
+        "<html>\n<head>\n    <title>Frontend</title>\n</head>\n<body></body>\n</html>";
+
+    fn db_with(articles: &[interfacing::Article]) -> cozo::DbInstance {
+        let db = cozo::DbInstance::default();
+        db::migrate(&db);
+        for article in articles {
+            db::q::put_article(&db, article.clone()).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn article_paths() {
+        assert_eq!(article_public_id("articles/intro"), Some("intro"));
+        assert_eq!(article_public_id("articles/"), None);
+        assert_eq!(article_public_id("articles"), None);
+        assert_eq!(article_public_id("articles/intro/edit"), None);
+        assert_eq!(article_public_id("admin/articles/intro"), None);
+    }
+
+    #[test]
+    fn pages_by_article() {
+        let site = EnvConf::test_default().site;
+        let db = db_with(&[
+            interfacing::Article {
+                title: "Intro".into(),
+                public_id: "intro".into(),
+                markdown: "# Intro\n\n![diagram](/api/media/x.png)\n\nFirst words.".into(),
+                draft: false,
+                tags: vec![],
+            },
+            interfacing::Article {
+                title: "Secret".into(),
+                public_id: "secret".into(),
+                draft: true,
+                ..Default::default()
+            },
+        ]);
+
+        assert_eq!(
+            article_page(&db, &site, "intro").unwrap(),
+            ArticlePage::Found(PageMeta {
+                title: "Intro".into(),
+                description: "First words.".into(),
+                url: "http://localhost:8000/articles/intro".into(),
+                image: Some("http://localhost:8000/api/media/x.png".into()),
+                site_name: site.title.clone(),
+            })
+        );
+        assert_eq!(
+            article_page(&db, &site, "secret").unwrap(),
+            ArticlePage::Draft
+        );
+        assert_eq!(
+            article_page(&db, &site, "missing").unwrap(),
+            ArticlePage::NotFound
+        );
+        // built into the frontend
+        assert!(matches!(
+            article_page(&db, &site, "about").unwrap(),
+            ArticlePage::Found(PageMeta { title, .. }) if title == "About"
+        ));
+    }
+
+    #[test]
+    fn injected_into_head() {
+        let meta = PageMeta {
+            title: "Tom & \"Jerry\"".into(),
+            description: "<script>".into(),
+            url: "http://localhost:8000/articles/tom".into(),
+            image: None,
+            site_name: "site".into(),
+        };
+        let page = meta.inject(INDEX);
+
+        assert_eq!(page.matches("<title>").count(), 1);
+        assert!(page.contains("<title>Tom &amp; &quot;Jerry&quot;</title>"));
+        assert!(page.contains(r#"<meta name="description" content="&lt;script&gt;" />"#));
+        assert!(page.contains(r#"<meta name="twitter:card" content="summary" />"#));
+        assert!(
+            page.contains(r#"<link rel="canonical" href="http://localhost:8000/articles/tom" />"#)
+        );
+        assert!(page.find("og:title").unwrap() < page.find("</head>").unwrap());
+        assert!(!page.contains("og:image"));
+    }
+}
@@ -0,0 +1,192 @@
+// Passkey (WebAuthn) support
+//
+// Ceremony states are kept in the session between the start and finish requests.
+// Users without passkeys get a decoy login challenge, which no credential
+// satisfies, so the start of a login doesn't reveal who has passkeys.
+//
+
+use crate::{
+    conf::Conf,
+    db,
+    error::{ApiError, ApiResult},
+    session_keys::SessionKeys,
+};
+use anyhow::Context;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use webauthn_rs::prelude::*;
+
+pub static REGISTRATION_KEY: &str = "passkey_registration";
+pub static AUTHENTICATION_KEY: &str = "passkey_authentication";
+// set after password or single sign-on login of a user who has to use a passkey
+// as the second factor
+pub static FIRST_FACTOR_VERIFIED_KEY: &str = "password_verified_username";
+
+pub fn webauthn(conf: &Conf) -> anyhow::Result<Webauthn> {
+    let rp_origin = Url::parse(&conf.webauthn.rp_origin).context("invalid rp_origin")?;
+
+    let webauthn = WebauthnBuilder::new(&conf.webauthn.rp_id, &rp_origin)?
+        .rp_name(&conf.webauthn.rp_id)
+        // local ports vary
+        .allow_any_port(conf.env.local())
+        .build()?;
+
+    Ok(webauthn)
+}
+
+// users relation has no ids of its own
+pub fn user_unique_id(username: &str) -> Uuid {
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, username.as_bytes())
+}
+
+pub fn ceremony_error(e: WebauthnError) -> ApiError {
+    ApiError::AuthError(anyhow::Error::new(e).context("passkey ceremony failed"))
+}
+
+pub fn to_stored(username: &str, name: &str, passkey: &Passkey) -> ApiResult<db::q::StoredPasskey> {
+    Ok(db::q::StoredPasskey {
+        credential_id: hex::encode(passkey.cred_id()),
+        username: username.into(),
+        name: name.into(),
+        passkey: serde_json::to_string(passkey).context("failed to serialize passkey")?,
+        created_at: interfacing::EndpointHit::formatted_now(),
+    })
+}
+
+/// Login challenge like that of a user with a single passkey
+///
+/// Its credential id is derived from a session key and the username, so it's
+/// the same on every request, like that of a real passkey.
+pub fn decoy_challenge(
+    webauthn: &Webauthn,
+    keys: &SessionKeys,
+    username: &str,
+) -> ApiResult<RequestChallengeResponse> {
+    let (challenge, _) = webauthn
+        .start_discoverable_authentication()
+        .map_err(ceremony_error)?;
+
+    // edited in its wire form, the same for every webauthn-rs version
+    let mut challenge = serde_json::to_value(challenge).context("failed to serialize challenge")?;
+    if let Some(challenge) = challenge.as_object_mut() {
+        challenge.remove("mediation");
+    }
+    challenge["publicKey"]["allowCredentials"] = serde_json::json!([{
+        "type": "public-key",
+        "id": base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(decoy_credential_id(keys, username)),
+    }]);
+
+    Ok(serde_json::from_value(challenge).context("failed to deserialize challenge")?)
+}
+
+fn decoy_credential_id(keys: &SessionKeys, username: &str) -> Vec<u8> {
+    // the oldest key is the one kept the longest
+    Sha256::new()
+        .chain_update(keys.oldest().master())
+        .chain_update(username.as_bytes())
+        .finalize()
+        .to_vec()
+}
+
+/// Passkeys of a user along with their stored form
+pub fn find(
+    db: &cozo::DbInstance,
+    username: &str,
+) -> ApiResult<Vec<(db::q::StoredPasskey, Passkey)>> {
+    db::q::find_passkeys_by_username(db, username)?
+        .into_iter()
+        .map(|stored| {
+            let passkey =
+                serde_json::from_str(&stored.passkey).context("failed to deserialize passkey")?;
+            Ok((stored, passkey))
+        })
+        .collect()
+}
+
+/// Stores updated signature counters of the authenticated passkey
+pub fn update_credentials(
+    db: &cozo::DbInstance,
+    username: &str,
+    result: &AuthenticationResult,
+) -> ApiResult<()> {
+    for (stored, mut passkey) in find(db, username)? {
+        if passkey.update_credential(result) == Some(true) {
+            db::q::put_passkey(
+                db,
+                db::q::StoredPasskey {
+                    passkey: serde_json::to_string(&passkey)
+                        .context("failed to serialize passkey")?,
+                    ..stored
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::{Env, EnvConf};
+    use webauthn_authenticator_rs::{softpasskey::SoftPasskey, WebauthnAuthenticator};
+
+    #[test]
+    fn ceremonies_with_software_authenticator() {
+        let conf = Conf::new(Env::Local, EnvConf::test_default());
+        let webauthn = webauthn(&conf).unwrap();
+        let origin = Url::parse(&conf.webauthn.rp_origin).unwrap();
+
+        let db = &cozo::DbInstance::default();
+        db::q::create_users_table(db).unwrap();
+        db::q::create_passkeys_table(db).unwrap();
+        db::q::put_user(db, "admin", "pwd_hash").unwrap();
+
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+        // registration
+        let (challenge, state) = webauthn
+            .start_passkey_registration(user_unique_id("admin"), "admin", "admin", None)
+            .unwrap();
+        let credential = authenticator
+            .do_registration(origin.clone(), challenge)
+            .unwrap();
+        let passkey = webauthn
+            .finish_passkey_registration(&credential, &state)
+            .unwrap();
+
+        db::q::put_passkey(db, to_stored("admin", "laptop", &passkey).unwrap()).unwrap();
+
+        // authentication
+        let passkeys = find(db, "admin")
+            .unwrap()
+            .into_iter()
+            .map(|(_, passkey)| passkey)
+            .collect::<Vec<_>>();
+        assert_eq!(passkeys.len(), 1);
+
+        let (challenge, state) = webauthn.start_passkey_authentication(&passkeys).unwrap();
+        let credential = authenticator
+            .do_authentication(origin.clone(), challenge)
+            .unwrap();
+        let result = webauthn
+            .finish_passkey_authentication(&credential, &state)
+            .unwrap();
+        assert_eq!(result.cred_id(), passkey.cred_id());
+        update_credentials(db, "admin", &result).unwrap();
+
+        // a passkey of another authenticator is not accepted
+        let mut other_authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+        let (challenge, state) = webauthn.start_passkey_authentication(&passkeys).unwrap();
+        assert!(other_authenticator
+            .do_authentication(origin, challenge)
+            .map_err(|_| ())
+            .and_then(|credential| {
+                webauthn
+                    .finish_passkey_authentication(&credential, &state)
+                    .map_err(|_| ())
+            })
+            .is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::audit::article_summary;
 use crate::db;
 use crate::routes::imports::*;
 
@@ -11,8 +12,19 @@ fn valid_article(article: impl Into<interfacing::Article> + std::fmt::Debug) ->
 
     let valid_public_id = !article.public_id.is_empty() && valid_public_id_charset;
     let valid_title = !article.title.is_empty();
+    // tags end up in feed urls
+    let valid_tags = article.tags.iter().all(|tag| {
+        !tag.is_empty()
+            && tag
+                .chars()
+                .all(|c| char::is_alphanumeric(c) || ['-'].contains(&c))
+    });
 
-    valid_public_id && valid_title
+    valid_public_id && valid_title && valid_tags
+}
+
+fn normalize_tags(article: &mut interfacing::Article) {
+    article.tags = interfacing::Article::normalized_tags(article.tags.iter().map(String::as_str));
 }
 
 #[tracing::instrument(name = "Reject invalid article", skip_all)]
@@ -30,12 +42,29 @@ fn reject_invalid_article(
 pub async fn new_article(
     session: ReadableSession,
     Extension(db): Extension<cozo::DbInstance>,
-    Json(article): Json<interfacing::Article>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
+    Json(mut article): Json<interfacing::Article>,
 ) -> ApiResult<impl IntoResponse> {
-    reject_anonymous_users(&session)?;
+    let username = reject_anonymous_users(&session)?;
+    normalize_tags(&mut article);
     reject_invalid_article(article.clone())?;
     db::q::put_article(&db, article.clone())?;
-    let article = db::q::find_article_by_public_id(&db, &article.public_id)?.unwrap();
+    let id = db::q::find_article_by_public_id(&db, &article.public_id)?
+        .unwrap()
+        .id;
+    let article = interfacing::ArticleWithId { id, body: article };
+    db::q::put_article_meta(&db, db::q::ArticleMeta::saved(&article, None))?;
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::ArticleCreate,
+        target_id: &article.id,
+        before: None,
+        after: Some(article_summary(article.body())),
+    }
+    .write(&db, &conf, &h);
+
     Ok(Json(article))
 }
 
@@ -43,11 +72,27 @@ pub async fn new_article(
 pub async fn update_article(
     session: ReadableSession,
     Extension(db): Extension<cozo::DbInstance>,
-    Json(article): Json<interfacing::ArticleWithId>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
+    Json(mut article): Json<interfacing::ArticleWithId>,
 ) -> ApiResult<impl IntoResponse> {
-    reject_anonymous_users(&session)?;
+    let username = reject_anonymous_users(&session)?;
+    normalize_tags(article.body_mut());
     reject_invalid_article(article.body().clone())?;
-    db::q::update_article(&db, article)?;
+    let before = db::q::find_article_by_id(&db, &article.id)?;
+    let before_meta = db::q::find_article_meta_by_id(&db, &article.id)?;
+    db::q::update_article(&db, article.clone())?;
+    db::q::put_article_meta(&db, db::q::ArticleMeta::saved(&article, before_meta))?;
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::ArticleUpdate,
+        target_id: &article.id,
+        before: before.map(|before| article_summary(before.body())),
+        after: Some(article_summary(article.body())),
+    }
+    .write(&db, &conf, &h);
+
     Ok(())
 }
 
@@ -56,9 +101,23 @@ pub async fn delete_article(
     session: ReadableSession,
     Path(id): Path<String>,
     Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
 ) -> ApiResult<impl IntoResponse> {
-    reject_anonymous_users(&session)?;
+    let username = reject_anonymous_users(&session)?;
+    let before = db::q::find_article_by_id(&db, &id)?;
     db::q::rm_article(&db, &id)?;
+    db::q::rm_article_meta(&db, &id)?;
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::ArticleDelete,
+        target_id: &id,
+        before: before.map(|before| article_summary(before.body())),
+        after: None,
+    }
+    .write(&db, &conf, &h);
+
     Ok(())
 }
 
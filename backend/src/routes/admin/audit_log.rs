@@ -0,0 +1,22 @@
+use itertools::Itertools;
+
+use crate::db;
+use crate::routes::imports::*;
+
+pub async fn audit_log(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Query(filter): Query<interfacing::AuditLogFilter>,
+) -> ApiResult<Json<Vec<interfacing::AuditLogEntry>>> {
+    reject_anonymous_users(&session)?;
+
+    let result = db::q::find_audit_log_entries(&db)?
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        // malformed timestamps sort as the oldest
+        .sorted_by_key(|entry| entry.timestamp())
+        .rev()
+        .collect_vec();
+
+    Ok(Json(result))
+}
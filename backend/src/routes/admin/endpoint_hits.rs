@@ -3,7 +3,7 @@ use itertools::Itertools;
 
 use crate::db;
 use crate::routes::imports::*;
-use crate::startup::ip_address;
+use crate::startup::{hash_ip, ip_address};
 
 #[allow(unused)]
 pub async fn endpoint_hits(
@@ -18,8 +18,8 @@ pub async fn endpoint_hits(
     Ok(Json(result))
 }
 
-#[derive(serde::Serialize)]
-struct IpToHit {
+#[derive(serde::Serialize, schemars::JsonSchema)]
+pub struct IpToHit {
     hashed_ip: String,
     hits: Vec<interfacing::EndpointHit>,
 }
@@ -114,11 +114,3 @@ pub async fn wsite_github_hit(
     db::q::put_endpoint_hit(&db, hit)?;
     Ok(StatusCode::NOT_FOUND)
 }
-
-fn hash_ip(ip: std::net::IpAddr, conf: &Conf) -> String {
-    if conf.env.local() {
-        ip.to_string()
-    } else {
-        interfacing::EndpointHit::hash_ip(ip)
-    }
-}
@@ -0,0 +1,61 @@
+use itertools::Itertools;
+
+use crate::db;
+use crate::features::current_flags;
+use crate::routes::imports::*;
+
+pub async fn feature_flags(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+) -> ApiResult<Json<Vec<interfacing::FeatureFlagInfo>>> {
+    reject_anonymous_users(&session)?;
+
+    let result = current_flags(&db, &conf)?
+        .into_iter()
+        .map(
+            |(name, (configured, overridden))| interfacing::FeatureFlagInfo {
+                name,
+                configured,
+                overridden,
+            },
+        )
+        .collect_vec();
+
+    Ok(Json(result))
+}
+
+pub async fn set_feature_flag(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
+    maybe_form: Result<Json<interfacing::FeatureFlagOverride>, JsonRejection>,
+) -> ApiResult<()> {
+    let username = reject_anonymous_users(&session)?;
+    let Json(form) = maybe_form?;
+
+    // only configured flags can be overridden
+    let Some((_configured, overridden)) = current_flags(&db, &conf)?.remove(&form.name) else {
+        return Err(ApiError::EntryNotFound);
+    };
+
+    match form.value {
+        Some(interfacing::FeatureFlag::Percentage(percentage)) if percentage > 100 => {
+            return Err(ApiError::BadRequest)
+        }
+        Some(value) => db::q::put_feature_flag_override(&db, &form.name, value)?,
+        None => db::q::rm_feature_flag_override(&db, &form.name)?,
+    }
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::FeatureFlagSet,
+        target_id: &form.name,
+        before: overridden.map(|v| serde_json::to_string(&v).unwrap()),
+        after: form.value.map(|v| serde_json::to_string(&v).unwrap()),
+    }
+    .write(&db, &conf, &h);
+
+    Ok(())
+}
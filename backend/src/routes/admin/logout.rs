@@ -1,11 +1,25 @@
 use crate::routes::imports::*;
 
-pub async fn logout(mut session: WritableSession) {
+pub async fn logout(
+    mut session: WritableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
+) {
     match session.get::<String>("username") {
         None => {}
         Some(username) => {
             session.destroy();
             tracing::info!("User {username} successfully logged out.");
+
+            AuditRecord {
+                actor: &username,
+                action: AuditAction::Logout,
+                target_id: &username,
+                before: None,
+                after: None,
+            }
+            .write(&db, &conf, &h);
         }
     }
 }
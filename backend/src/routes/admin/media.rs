@@ -0,0 +1,56 @@
+use crate::db;
+use crate::media;
+use crate::routes::imports::*;
+use crate::trace::spawn_blocking_with_tracing;
+
+pub async fn media_list(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+) -> ApiResult<Json<Vec<interfacing::MediaFile>>> {
+    reject_anonymous_users(&session)?;
+
+    Ok(Json(db::q::find_media_files(&db)?))
+}
+
+#[derive(Deserialize, Debug, schemars::JsonSchema)]
+pub struct MediaUpload {
+    // original file name, for display
+    name: String,
+}
+
+/// Stores the request body, limited by media.max_size_bytes
+pub async fn upload_media(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
+    Query(upload): Query<MediaUpload>,
+    body: bytes::Bytes,
+) -> ApiResult<Json<interfacing::MediaFile>> {
+    let username = reject_anonymous_users(&session)?;
+
+    if body.is_empty() || body.len() > conf.media.max_size_bytes {
+        return Err(ApiError::BadRequest);
+    }
+    let Some(mime) = media::sniff(&body) else {
+        return Err(ApiError::BadRequest);
+    };
+
+    let file = spawn_blocking_with_tracing({
+        let (db, conf, username) = (db.clone(), conf.clone(), username.clone());
+        move || media::store(&db, &conf, body.to_vec(), mime, &upload.name, &username)
+    })
+    .await
+    .context("failed to spawn blocking task")??;
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::MediaUpload,
+        target_id: &file.id,
+        before: None,
+        after: Some(file.name.clone()),
+    }
+    .write(&db, &conf, &h);
+
+    Ok(Json(file))
+}
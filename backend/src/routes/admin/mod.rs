@@ -1,10 +1,18 @@
 mod articles;
+mod audit_log;
 mod endpoint_hits;
+mod features;
 mod logout;
+mod media;
 mod password;
 mod session;
+mod sessions;
 pub use articles::*;
+pub use audit_log::*;
 pub use endpoint_hits::*;
+pub use features::*;
 pub use logout::*;
+pub use media::*;
 pub use password::*;
 pub use session::*;
+pub use sessions::*;
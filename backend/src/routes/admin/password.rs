@@ -5,6 +5,8 @@ use interfacing::PasswordChangeForm;
 pub async fn change_password(
     session: ReadableSession,
     Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
     Json(form): Json<PasswordChangeForm>,
 ) -> ApiResult<impl IntoResponse> {
     let username = reject_anonymous_users(&session)?;
@@ -28,5 +30,14 @@ pub async fn change_password(
 
     tracing::info!("{}'s password has been changed", username);
 
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::PasswordChange,
+        target_id: &username,
+        before: None,
+        after: None,
+    }
+    .write(&db, &conf, &h);
+
     Ok(())
 }
@@ -5,7 +5,7 @@ use interfacing::AdminSession;
 pub async fn admin_session(
     Extension(db): Extension<cozo::DbInstance>,
     Extension(conf): Extension<Conf>,
-    session: ReadableSession,
+    mut session: WritableSession,
 ) -> ApiResult<Json<AdminSession>> {
     // returns user info if logged in, else 403
 
@@ -18,8 +18,12 @@ pub async fn admin_session(
         Some(username) => {
             let user = db::q::find_user_by_username(&db, &username)?.unwrap(); // TODO safen
             let username = user.username;
+            let csrf_token = crate::csrf::session_token(&mut session)?;
 
-            AdminSession { username }
+            AdminSession {
+                username,
+                csrf_token,
+            }
         }
     };
 
@@ -0,0 +1,86 @@
+use itertools::Itertools;
+
+use crate::db;
+use crate::routes::imports::*;
+
+pub async fn session_list(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+) -> ApiResult<Json<Vec<interfacing::SessionInfo>>> {
+    let username = reject_anonymous_users(&session)?;
+    let now = interfacing::EndpointHit::formatted_now();
+
+    let result = db::q::find_sessions_by_username(&db, &username)?
+        .into_iter()
+        // may linger until the next purge
+        .filter(|info| !matches!(&info.expires_at, Some(expires_at) if expires_at < &now))
+        .map(|info| interfacing::SessionInfo {
+            current: info.id == session.id(),
+            ..info
+        })
+        .sorted_by(|a, b| b.last_seen_at.cmp(&a.last_seen_at))
+        .collect_vec();
+
+    Ok(Json(result))
+}
+
+pub async fn revoke_session(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
+    maybe_form: Result<Json<interfacing::RevokeSession>, JsonRejection>,
+) -> ApiResult<()> {
+    let username = reject_anonymous_users(&session)?;
+    let Json(form) = maybe_form?;
+
+    // logout is there for the current one
+    if form.id == session.id() {
+        return Err(ApiError::BadRequest);
+    }
+
+    // only own sessions can be revoked
+    let owned = db::q::find_sessions_by_username(&db, &username)?
+        .into_iter()
+        .any(|info| info.id == form.id);
+
+    if !owned {
+        return Err(ApiError::EntryNotFound);
+    }
+
+    db::q::rm_session(&db, &form.id)?;
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::SessionRevoke,
+        target_id: &form.id,
+        before: None,
+        after: None,
+    }
+    .write(&db, &conf, &h);
+
+    Ok(())
+}
+
+pub async fn revoke_other_sessions(
+    session: ReadableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
+) -> ApiResult<()> {
+    let username = reject_anonymous_users(&session)?;
+
+    db::q::rm_sessions_by_username_except(&db, &username, session.id())?;
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::SessionRevokeOthers,
+        // the one kept
+        target_id: session.id(),
+        before: None,
+        after: None,
+    }
+    .write(&db, &conf, &h);
+
+    Ok(())
+}
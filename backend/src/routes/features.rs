@@ -0,0 +1,6 @@
+use crate::{features::Features, routes::imports::*};
+
+// flags evaluated for the visitor, for the frontend to hide disabled features
+pub async fn feature_list(features: Features) -> Json<interfacing::Features> {
+    Json(features.evaluated())
+}
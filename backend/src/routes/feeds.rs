@@ -0,0 +1,57 @@
+use crate::feeds::{self, Format};
+use crate::routes::generated_response;
+use crate::routes::imports::*;
+use axum::http::HeaderMap;
+
+// readers poll, unchanged feeds are answered with 304
+static CACHE_CONTROL: &str = "no-cache";
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct FeedQuery {
+    tag: Option<String>,
+}
+
+pub async fn rss_feed(
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    feed(Format::Rss, &db, &conf, query, &headers)
+}
+
+pub async fn atom_feed(
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    feed(Format::Atom, &db, &conf, query, &headers)
+}
+
+pub async fn json_feed(
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    feed(Format::Json, &db, &conf, query, &headers)
+}
+
+fn feed(
+    format: Format,
+    db: &cozo::DbInstance,
+    conf: &Conf,
+    query: FeedQuery,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    let feed = feeds::load(db, &conf.site, query.tag.as_deref())?;
+
+    Ok(generated_response(
+        headers,
+        feed.render(format),
+        format.content_type(),
+        feed.updated(),
+        CACHE_CONTROL,
+    ))
+}
@@ -1,5 +1,100 @@
 use crate::routes::imports::*;
+use crate::{db, session_keys::SessionKeys, trace::spawn_blocking_with_tracing};
 
+// kept for probes configured before liveness and readiness were split
 pub async fn health_check() -> StatusCode {
     StatusCode::OK
 }
+
+/// Responds as long as the server runs
+pub async fn health_live() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Whether requests can be served, 503 with the failed checks otherwise
+///
+/// Public, so failures are only logged in detail
+pub async fn health_ready(
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+) -> Response {
+    let db = TimeoutStrategy::Once {
+        timeout: std::time::Duration::from_secs(2),
+    }
+    .execute(|| {
+        let db = db.clone();
+        spawn_blocking_with_tracing(move || db::q::ping(&db))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|joined| joined.map_err(|e| e.to_string()))
+    .and_then(|pinged| pinged.map_err(|e| format!("{e:?}")));
+
+    let static_files = if conf.static_files.root.is_dir() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is not a directory",
+            conf.static_files.root.display()
+        ))
+    };
+
+    let session_secret = SessionKeys::verify(&conf).map_err(|e| format!("{e:#}"));
+
+    let readiness = interfacing::Readiness::new(
+        [
+            ("db", db),
+            ("static_files", static_files),
+            ("session_secret", session_secret),
+        ]
+        .into_iter()
+        .map(|(name, result)| {
+            if let Err(e) = &result {
+                tracing::error!("readiness check {name} failed: {e}");
+            }
+            interfacing::ReadinessCheck {
+                name: name.into(),
+                ok: result.is_ok(),
+            }
+        })
+        .collect(),
+    );
+
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
+pub async fn version(Extension(conf): Extension<Conf>) -> Json<interfacing::BuildInfo> {
+    Json(build_info(&conf))
+}
+
+pub fn build_info(conf: &Conf) -> interfacing::BuildInfo {
+    // when the executable was linked
+    let built_at = std::env::current_exe()
+        .and_then(|exe| exe.metadata()?.modified())
+        .map(|time| humantime::format_rfc3339_seconds(time).to_string())
+        .unwrap_or_else(|_| "unknown".into());
+
+    let mut versions = std::collections::BTreeMap::from([(
+        env!("CARGO_PKG_NAME").to_owned(),
+        env!("CARGO_PKG_VERSION").to_owned(),
+    )]);
+    versions.extend(
+        env!("BUILD_DEP_VERSIONS")
+            .split(',')
+            .filter_map(|v| v.split_once('='))
+            .map(|(name, version)| (name.to_owned(), version.to_owned())),
+    );
+
+    interfacing::BuildInfo {
+        // set by whoever builds, `GIT_SHA=$(git rev-parse HEAD) cargo build`
+        git_sha: option_env!("GIT_SHA").unwrap_or("unknown").into(),
+        build_time: built_at,
+        env: conf.env.to_string(),
+        versions,
+    }
+}
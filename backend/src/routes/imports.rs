@@ -2,6 +2,7 @@
 
 pub use crate::static_routes::extend::*;
 pub use crate::{
+    audit::{AuditAction, AuditRecord},
     authentication::{reject_anonymous_users, validate_credentials, Credentials},
     conf::Conf,
     error::{ApiError, ApiResult},
@@ -1,4 +1,4 @@
-use crate::routes::imports::*;
+use crate::{db, passkeys::FIRST_FACTOR_VERIFIED_KEY, routes::imports::*};
 use interfacing::LoginForm;
 
 #[tracing::instrument(
@@ -9,22 +9,77 @@ use interfacing::LoginForm;
 pub async fn login(
     mut session: WritableSession,
     Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    h: hyper::HeaderMap,
     maybe_form: Result<Json<LoginForm>, JsonRejection>,
-) -> ApiResult<()> {
+) -> ApiResult<StatusCode> {
     let Json(form) = maybe_form?;
     let credentials: Credentials = form.into();
     tracing::Span::current().record("username", &tracing::field::display(&credentials.username));
 
     let credentials = credentials.clone();
 
-    validate_credentials(db, &credentials).await?;
+    validate_credentials(db.clone(), &credentials).await?;
+
+    if second_factor_required(&mut session, &db, &conf, &credentials.username)? {
+        // to be completed by passkey login
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    log_in(&mut session, &db, &conf, &h, &credentials.username)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Whether the login is to be completed by a passkey, for all login methods
+///
+/// If so, the verified first factor is remembered for the passkey login.
+pub fn second_factor_required(
+    session: &mut WritableSession,
+    db: &cozo::DbInstance,
+    conf: &Conf,
+    username: &str,
+) -> ApiResult<bool> {
+    if !conf.webauthn.second_factor || db::q::find_passkeys_by_username(db, username)?.is_empty() {
+        return Ok(false);
+    }
+
+    session
+        .insert(FIRST_FACTOR_VERIFIED_KEY, username.to_owned())
+        .context("Failed to register verified first factor in a session")?;
+
+    Ok(true)
+}
 
+/// Turns the session into a logged in one, the same way for all login methods
+pub fn log_in(
+    session: &mut WritableSession,
+    db: &cozo::DbInstance,
+    conf: &Conf,
+    h: &hyper::HeaderMap,
+    username: &str,
+) -> ApiResult<()> {
     session.regenerate();
+    // a token issued before login must not outlive it
+    crate::csrf::forget_session_token(session);
+    session.remove(FIRST_FACTOR_VERIFIED_KEY);
 
     session
-        .insert("username", credentials.username)
+        .insert("username", username.to_owned())
         .context("Failed to register username in a session")?;
 
+    crate::sessions::record_meta(session, conf, h)
+        .context("Failed to register metadata in a session")?;
+
+    AuditRecord {
+        actor: username,
+        action: AuditAction::Login,
+        target_id: username,
+        before: None,
+        after: None,
+    }
+    .write(db, conf, h);
+
     Ok(())
 }
 
@@ -0,0 +1,46 @@
+use crate::media;
+use crate::routes::imports::*;
+use crate::routes::{file_response, not_modified};
+use crate::serve_files::Validators;
+use axum::http::HeaderMap;
+
+// content addressed, so never changes
+static CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+pub async fn serve_media(
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let Some(file) = media::find(&db, &id)? else {
+        return Err(ApiError::EntryNotFound);
+    };
+
+    // the id is the content hash, so the contents are read only when sent
+    let etag = format!("\"{}\"", file.id);
+    let validators = Validators {
+        etag: &etag,
+        modified: None,
+    };
+
+    let mut response = if validators.not_modified(&headers) {
+        not_modified(validators, CACHE_CONTROL)
+    } else {
+        let contents = media::contents(&db, &conf, &file.id)?;
+        let mut response = file_response(contents, &file.id, validators, CACHE_CONTROL);
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            axum::http::HeaderValue::from_str(&file.mime)
+                .context("stored media type is not a header value")?,
+        );
+        response
+    };
+    // served as sniffed on upload
+    response.headers_mut().insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        axum::http::HeaderValue::from_static("nosniff"),
+    );
+
+    Ok(response)
+}
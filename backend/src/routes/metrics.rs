@@ -0,0 +1,32 @@
+use crate::metrics::{metrics, scrape_allowed, scraper_ip};
+use crate::routes::imports::*;
+use crate::startup::UserConnectInfo;
+use axum::extract::connect_info::ConnectInfo;
+use axum::http::HeaderMap;
+
+pub async fn serve_metrics(
+    ConnectInfo(con_info): ConnectInfo<UserConnectInfo>,
+    headers: HeaderMap,
+    Extension(conf): Extension<Conf>,
+    State(state): State<AppState>,
+) -> Response {
+    let ip = scraper_ip(&conf.metrics, &headers, con_info.remote_addr.ip());
+    if !scrape_allowed(&conf.metrics, &headers, ip) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let metrics = metrics();
+    {
+        let cons = state.users_online.cons.lock().await;
+        metrics
+            .ws_connections
+            .set(cons.values().map(|count| *count as i64).sum());
+        metrics.users_online.set(cons.len() as i64);
+    }
+
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics.render(),
+    )
+        .into_response()
+}
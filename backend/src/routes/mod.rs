@@ -1,12 +1,28 @@
 mod imports;
 
 mod admin;
+mod features;
+mod feeds;
 mod health_check;
 mod login;
+mod media;
+mod metrics;
+mod oidc;
+mod openapi;
+mod passkeys;
 mod serve_files;
+mod sitemap;
 mod users_online;
 pub use admin::*;
+pub use features::*;
+pub use feeds::*;
 pub use health_check::*;
 pub use login::*;
+pub use media::*;
+pub use metrics::*;
+pub use oidc::*;
+pub use openapi::*;
+pub use passkeys::*;
 pub use serve_files::*;
+pub use sitemap::*;
 pub use users_online::*;
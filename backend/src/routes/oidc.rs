@@ -0,0 +1,139 @@
+use crate::{
+    db,
+    oidc::{self, Flow, FLOW_KEY},
+    routes::{
+        imports::*,
+        login::{log_in, second_factor_required},
+    },
+    trace::spawn_blocking_with_tracing,
+};
+use axum::response::Redirect;
+
+#[derive(Deserialize, Debug, schemars::JsonSchema)]
+pub struct CallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+// redirects to the identity provider
+pub async fn oidc_login(
+    mut session: WritableSession,
+    Extension(conf): Extension<Conf>,
+    State(state): State<AppState>,
+) -> Response {
+    match start(&mut session, &conf, &state.oidc_http).await {
+        Ok(url) => Redirect::to(url.as_str()).into_response(),
+        Err(e) => failure_redirect(e),
+    }
+}
+
+// the identity provider redirects back here
+pub async fn oidc_callback(
+    mut session: WritableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    State(state): State<AppState>,
+    h: hyper::HeaderMap,
+    Query(params): Query<CallbackParams>,
+) -> Response {
+    match finish(&mut session, &db, &conf, &state.oidc_http, &h, params).await {
+        Ok(Finished::LoggedIn) => routes().root.admin.dashboard.redirect_to().into_response(),
+        Ok(Finished::SecondFactor(username)) => {
+            // the login page continues with a passkey login of the user
+            let login = routes().root.login.get().complete().to_owned();
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("passkey", &username)
+                .finish();
+            Redirect::to(&format!("{login}?{query}")).into_response()
+        }
+        Err(e) => failure_redirect(e),
+    }
+}
+
+enum Finished {
+    LoggedIn,
+    // to be completed by passkey login, like after a password
+    SecondFactor(String),
+}
+
+async fn start(
+    session: &mut WritableSession,
+    conf: &Conf,
+    http: &reqwest::Client,
+) -> anyhow::Result<url::Url> {
+    let oidc_conf = conf.oidc.as_ref().context("OIDC is not configured")?;
+
+    let metadata = oidc::discover(http, &oidc_conf.issuer).await?;
+    let flow = Flow::generate();
+    let url = oidc::authorization_url(&metadata, oidc_conf, &flow)?;
+
+    session
+        .insert(FLOW_KEY, flow)
+        .context("Failed to register OIDC flow in a session")?;
+
+    Ok(url)
+}
+
+#[tracing::instrument(name = "Finish OIDC login", skip_all)]
+async fn finish(
+    session: &mut WritableSession,
+    db: &cozo::DbInstance,
+    conf: &Conf,
+    http: &reqwest::Client,
+    h: &hyper::HeaderMap,
+    params: CallbackParams,
+) -> anyhow::Result<Finished> {
+    let oidc_conf = conf.oidc.as_ref().context("OIDC is not configured")?;
+
+    // single use
+    let flow = session
+        .get::<Flow>(FLOW_KEY)
+        .context("no OIDC flow in progress")?;
+    session.remove(FLOW_KEY);
+
+    if let Some(error) = params.error {
+        anyhow::bail!("identity provider responded with error: {error}");
+    }
+
+    anyhow::ensure!(
+        params.state.as_ref() == Some(&flow.state),
+        "OIDC state mismatch"
+    );
+    let code = params.code.context("no authorization code")?;
+
+    let metadata = oidc::discover(http, &oidc_conf.issuer).await?;
+    let id_token = oidc::exchange_code(http, &metadata, oidc_conf, &flow, &code).await?;
+    let claims = oidc::validate_id_token(http, &metadata, oidc_conf, &flow, &id_token).await?;
+    let username = oidc::username_from_claims(&claims, oidc_conf)?;
+
+    if db::q::find_user_by_username(db, &username)?.is_none() {
+        anyhow::ensure!(oidc_conf.provision_users, "user {username} does not exist");
+
+        // password login stays impossible for provisioned users
+        let pwd_hash = spawn_blocking_with_tracing(|| {
+            use rand::Rng;
+            let password: [u8; 32] = rand::thread_rng().gen();
+            auth::hash_pwd(password.as_slice())
+        })
+        .await??;
+
+        db::q::put_user(db, &username, &pwd_hash)?;
+        tracing::info!("provisioned user {username}");
+    }
+
+    if second_factor_required(session, db, conf, &username)? {
+        return Ok(Finished::SecondFactor(username));
+    }
+
+    log_in(session, db, conf, h, &username)?;
+
+    Ok(Finished::LoggedIn)
+}
+
+fn failure_redirect(e: anyhow::Error) -> Response {
+    tracing::error!("OIDC login failed: {e:?}");
+
+    let login = routes().root.login.get().complete().to_owned();
+    Redirect::to(&format!("{login}?error=Single%20sign-on%20failed")).into_response()
+}
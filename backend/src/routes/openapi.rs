@@ -0,0 +1,6 @@
+use crate::openapi::openapi;
+use crate::routes::imports::*;
+
+pub async fn serve_openapi() -> Json<&'static serde_json::Value> {
+    Json(openapi())
+}
@@ -0,0 +1,144 @@
+use crate::{
+    passkeys::{self, AUTHENTICATION_KEY, FIRST_FACTOR_VERIFIED_KEY, REGISTRATION_KEY},
+    routes::{imports::*, login::log_in},
+    session_keys::SessionKeys,
+};
+use itertools::Itertools;
+use std::sync::Arc;
+use webauthn_rs::prelude::*;
+
+pub async fn passkey_registration_start(
+    mut session: WritableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(webauthn): Extension<Arc<Webauthn>>,
+    maybe_form: Result<Json<interfacing::PasskeyRegistrationStart>, JsonRejection>,
+) -> ApiResult<Json<CreationChallengeResponse>> {
+    let username = reject_anonymous_users(&session)?;
+    let Json(form) = maybe_form?;
+
+    // the same authenticator must not be registered twice
+    let exclude_credentials = passkeys::find(&db, &username)?
+        .into_iter()
+        .map(|(_, passkey)| passkey.cred_id().clone())
+        .collect_vec();
+
+    let (challenge, state) = webauthn
+        .start_passkey_registration(
+            passkeys::user_unique_id(&username),
+            &username,
+            &username,
+            Some(exclude_credentials),
+        )
+        .map_err(passkeys::ceremony_error)?;
+
+    session
+        .insert(REGISTRATION_KEY, (form.name, state))
+        .context("Failed to register passkey registration in a session")?;
+
+    Ok(Json(challenge))
+}
+
+pub async fn passkey_registration_finish(
+    mut session: WritableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    Extension(webauthn): Extension<Arc<Webauthn>>,
+    h: hyper::HeaderMap,
+    maybe_credential: Result<Json<RegisterPublicKeyCredential>, JsonRejection>,
+) -> ApiResult<()> {
+    let username = reject_anonymous_users(&session)?;
+    let Json(credential) = maybe_credential?;
+
+    let (name, state) = session
+        .get::<(String, PasskeyRegistration)>(REGISTRATION_KEY)
+        .ok_or(ApiError::BadRequest)?;
+    session.remove(REGISTRATION_KEY);
+
+    let passkey = webauthn
+        .finish_passkey_registration(&credential, &state)
+        .map_err(passkeys::ceremony_error)?;
+
+    crate::db::q::put_passkey(&db, passkeys::to_stored(&username, &name, &passkey)?)?;
+
+    AuditRecord {
+        actor: &username,
+        action: AuditAction::PasskeyRegister,
+        target_id: &username,
+        before: None,
+        after: Some(format!("name: {name:?}")),
+    }
+    .write(&db, &conf, &h);
+
+    Ok(())
+}
+
+pub async fn passkey_login_start(
+    mut session: WritableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(webauthn): Extension<Arc<Webauthn>>,
+    Extension(session_keys): Extension<SessionKeys>,
+    maybe_form: Result<Json<interfacing::PasskeyLoginStart>, JsonRejection>,
+) -> ApiResult<Json<RequestChallengeResponse>> {
+    let Json(form) = maybe_form?;
+
+    let passkeys = passkeys::find(&db, &form.username)?
+        .into_iter()
+        .map(|(_, passkey)| passkey)
+        .collect_vec();
+
+    // the same for unknown users, not to reveal who exists or has passkeys
+    let (challenge, state) = if passkeys.is_empty() {
+        let challenge = passkeys::decoy_challenge(&webauthn, &session_keys, &form.username)?;
+        (challenge, None)
+    } else {
+        let (challenge, state) = webauthn
+            .start_passkey_authentication(&passkeys)
+            .map_err(passkeys::ceremony_error)?;
+        (challenge, Some(state))
+    };
+
+    session
+        .insert(AUTHENTICATION_KEY, (form.username, state))
+        .context("Failed to register passkey authentication in a session")?;
+
+    Ok(Json(challenge))
+}
+
+pub async fn passkey_login_finish(
+    mut session: WritableSession,
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    Extension(webauthn): Extension<Arc<Webauthn>>,
+    h: hyper::HeaderMap,
+    maybe_credential: Result<Json<PublicKeyCredential>, JsonRejection>,
+) -> ApiResult<()> {
+    let Json(credential) = maybe_credential?;
+
+    let (username, state) = session
+        .get::<(String, Option<PasskeyAuthentication>)>(AUTHENTICATION_KEY)
+        .ok_or(ApiError::BadRequest)?;
+    session.remove(AUTHENTICATION_KEY);
+
+    // after a decoy challenge, failing like a credential that doesn't match
+    let state = state.ok_or_else(|| {
+        ApiError::AuthError(anyhow::anyhow!(
+            "no passkey to authenticate {username:?} with"
+        ))
+    })?;
+
+    let result = webauthn
+        .finish_passkey_authentication(&credential, &state)
+        .map_err(passkeys::ceremony_error)?;
+
+    if conf.webauthn.second_factor
+        && session.get::<String>(FIRST_FACTOR_VERIFIED_KEY).as_ref() != Some(&username)
+    {
+        return Err(ApiError::AuthError(anyhow::anyhow!(
+            "Password or single sign-on must be verified first"
+        )));
+    }
+
+    passkeys::update_credentials(&db, &username, &result)?;
+
+    log_in(&mut session, &db, &conf, &h, &username)
+}
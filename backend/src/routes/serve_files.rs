@@ -1,46 +1,419 @@
+use crate::conf::SymlinkPolicy;
+use crate::dev_frontend::with_reload_script;
+use crate::page_meta::{article_page, article_public_id, ArticlePage};
 use crate::routes::imports::*;
-use crate::serve_files::{FRONTEND_DIR, INDEX_HTML, STATIC_DIR};
+use crate::serve_files::{byte_range, cache_control, resolve_static, ByteRange, Validators};
+#[cfg(feature = "embed-frontend")]
+use crate::serve_files::{
+    encoded_etag, frontend_etag, frontend_variant, index_html_etag, preferred_encoding, Encoding,
+    FRONTEND_DIR, INDEX_HTML,
+};
+#[cfg(feature = "ssr")]
+use crate::{
+    features::Features,
+    startup::{hash_ip, ip_address},
+};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocketUpgrade};
+use axum::http::HeaderMap;
 
 #[axum_macros::debug_handler]
-pub async fn serve_static(Path(path): Path<String>) -> ApiResult<Response> {
-    let path = std::path::PathBuf::from(STATIC_DIR).join(path);
+pub async fn serve_static(
+    Extension(conf): Extension<Conf>,
+    Path(path): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let static_files = &conf.static_files;
+    // rejected paths are indistinguishable from missing ones
+    let Some(path) = resolve_static(&static_files.root, &path, static_files.symlinks) else {
+        return Ok(IntoResponse::into_response(StatusCode::NOT_FOUND));
+    };
 
     match std::fs::read(&path) {
         Err(_e) => return Ok(IntoResponse::into_response(StatusCode::NOT_FOUND)),
         Ok(file) => {
-            let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
-            return Ok(file_response(file, &path, modified));
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            let etag = crate::serve_files::etag(&file);
+            let validators = Validators {
+                etag: &etag,
+                modified,
+            };
+
+            if validators.not_modified(&headers) {
+                return Ok(not_modified(validators, "no-cache"));
+            }
+
+            let range = if validators.range_applies(&headers) {
+                byte_range(&headers, file.len() as u64)
+            } else {
+                ByteRange::Full
+            };
+
+            let mut response = match range {
+                ByteRange::Full => file_response(file, &path, validators, "no-cache"),
+                ByteRange::Partial(start, end) => {
+                    let len = file.len();
+                    let part = bytes::Bytes::from(file).slice(start as usize..=end as usize);
+                    let mut response = file_response(part, &path, validators, "no-cache");
+                    *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+                    response.headers_mut().insert(
+                        header::CONTENT_RANGE,
+                        format!("bytes {start}-{end}/{len}").parse().unwrap(),
+                    );
+                    response
+                }
+                ByteRange::Unsatisfiable => {
+                    let content_range = format!("bytes */{}", file.len());
+                    (
+                        StatusCode::RANGE_NOT_SATISFIABLE,
+                        [(header::CONTENT_RANGE, content_range)],
+                    )
+                        .into_response()
+                }
+            };
+            response.headers_mut().insert(
+                header::ACCEPT_RANGES,
+                axum::http::HeaderValue::from_static("bytes"),
+            );
+
+            Ok(response)
         }
     }
 }
 
-pub async fn fallback(uri: axum::http::Uri) -> Response {
-    let path = uri.to_string();
+pub async fn fallback(
+    Extension(conf): Extension<Conf>,
+    Extension(db): Extension<cozo::DbInstance>,
+    uri: axum::http::Uri,
+    headers: HeaderMap,
+) -> Response {
+    let path = uri.path();
     let path = path.trim_start_matches('/');
 
+    // link previews don't run the frontend
+    let mut meta = None;
+    if let Some(public_id) = article_public_id(path) {
+        match article_page(&db, &conf.site, public_id) {
+            Ok(ArticlePage::Found(found)) => meta = Some(found),
+            Ok(ArticlePage::NotFound) => return article_not_found(&conf),
+            Ok(ArticlePage::Draft) => (),
+            // served without a preview
+            Err(e) => tracing::error!("failed to look up article {public_id}: {e:?}"),
+        }
+    }
+
+    // index.html as served for the path, when the server adds to it
+    let mut index = None;
+    // rendered with the features of the visitor
+    let mut per_visitor = false;
+
+    #[cfg(feature = "ssr")]
+    if let Some(page) = server_rendered(&conf, &db, &headers, uri.path()).await {
+        index = index_html(&conf).map(|index| page.inject(&index));
+        per_visitor = index.is_some();
+    }
+    if let Some(meta) = meta {
+        index = index
+            .or_else(|| index_html(&conf))
+            .map(|index| meta.inject(&index));
+    }
+    if let Some(index) = index {
+        return index_response(&conf, &headers, index, per_visitor);
+    }
+
+    match &conf.frontend.dist_path {
+        Some(dist) => disk_frontend_response(&headers, dist, path),
+        #[cfg(feature = "embed-frontend")]
+        None => embedded_frontend_response(&headers, path),
+        // rejected on startup
+        #[cfg(not(feature = "embed-frontend"))]
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+// as served for client side routes
+fn index_html(conf: &Conf) -> Option<String> {
+    match &conf.frontend.dist_path {
+        Some(dist) => disk_index_html(dist),
+        #[cfg(feature = "embed-frontend")]
+        None => Some(INDEX_HTML.to_owned()),
+        #[cfg(not(feature = "embed-frontend"))]
+        None => None,
+    }
+}
+
+// rendered pages are served as the client side routes are, but kept out of
+// shared caches, not to hand one visitor's features to everyone
+fn index_response(conf: &Conf, headers: &HeaderMap, index: String, per_visitor: bool) -> Response {
+    let cache_control = match conf.frontend.dist_path {
+        _ if per_visitor => "private, no-cache",
+        Some(_) => "no-cache",
+        None => cache_control("index.html"),
+    };
+
+    generated_response(
+        headers,
+        index,
+        "text/html; charset=utf-8",
+        None,
+        cache_control,
+    )
+}
+
+#[cfg(feature = "ssr")]
+// the browser renders pages the server does not
+async fn server_rendered(
+    conf: &Conf,
+    db: &cozo::DbInstance,
+    headers: &HeaderMap,
+    path: &str,
+) -> Option<crate::ssr::Page> {
+    let prefetched = crate::ssr::prefetch(db, path).and_then(|prefetched| match prefetched {
+        Some(mut prefetched) => {
+            let visitor = hash_ip(ip_address(headers), conf);
+            prefetched.features = Some(Features::load(db, conf, visitor)?.evaluated());
+            Ok(Some(prefetched))
+        }
+        None => Ok(None),
+    });
+
+    match prefetched {
+        Ok(prefetched) => Some(crate::ssr::Page::render(path, prefetched?).await),
+        Err(e) => {
+            tracing::error!("failed to prefetch {path}: {e:?}");
+            None
+        }
+    }
+}
+
+// the frontend still renders its not found page
+fn article_not_found(conf: &Conf) -> Response {
+    match index_html(conf) {
+        Some(index) => (StatusCode::NOT_FOUND, axum::response::Html(index)).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(feature = "embed-frontend")]
+fn embedded_frontend_response(headers: &HeaderMap, path: &str) -> Response {
     match FRONTEND_DIR.get_file(path) {
-        None => IntoResponse::into_response(axum::response::Html(INDEX_HTML)),
-        Some(file) => {
-            let modified = file.metadata().unwrap().modified();
-            file_response(file.contents(), path, modified)
+        // client side routes
+        None => frontend_file_response(
+            headers,
+            "index.html",
+            INDEX_HTML.as_bytes(),
+            index_html_etag(),
+            FRONTEND_DIR
+                .get_file("index.html")
+                .and_then(|file| file.metadata())
+                .map(|metadata| metadata.modified()),
+        ),
+        Some(file) => frontend_file_response(
+            headers,
+            path,
+            file.contents(),
+            frontend_etag(path).expect("to be hashed with the rest"),
+            file.metadata().map(|metadata| metadata.modified()),
+        ),
+    }
+}
+
+// development mode, index.html gets the reload script
+fn disk_frontend_response(headers: &HeaderMap, dist: &std::path::Path, path: &str) -> Response {
+    let file = resolve_static(dist, path, SymlinkPolicy::WithinRoot)
+        .filter(|_| path != "index.html")
+        .and_then(|file| std::fs::read(file).ok());
+
+    let (path, contents) = match file {
+        Some(contents) => (path, contents),
+        // client side routes
+        None => match disk_index_html(dist) {
+            Some(index) => ("index.html", index.into_bytes()),
+            None => return StatusCode::NOT_FOUND.into_response(),
+        },
+    };
+
+    // names outlive contents between builds
+    let etag = crate::serve_files::etag(&contents);
+    let validators = Validators {
+        etag: &etag,
+        modified: None,
+    };
+
+    if validators.not_modified(headers) {
+        not_modified(validators, "no-cache")
+    } else {
+        file_response(contents, path, validators, "no-cache")
+    }
+}
+
+fn disk_index_html(dist: &std::path::Path) -> Option<String> {
+    match std::fs::read_to_string(dist.join("index.html")) {
+        Ok(index) => Some(with_reload_script(&index)),
+        Err(e) => {
+            tracing::error!("failed to read index.html from {}: {e}", dist.display());
+            None
         }
     }
 }
 
+/// Tells the page to reload once the frontend changes on disk
+pub async fn ws_dev_reload(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    let mut reloads = state.dev_reload.subscribe();
+
+    ws.on_upgrade(move |mut socket| async move {
+        tokio::select! {
+            _ = reloads.recv() => {
+                let _ = socket.send(Message::Text("reload".into())).await;
+            }
+            () = state.shutdown.signaled() => {
+                let _ = socket
+                    .send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "server is shutting down".into(),
+                    })))
+                    .await;
+            }
+        }
+    })
+}
+
+#[cfg(feature = "embed-frontend")]
+// serves the precompressed variant the client prefers, if any
+fn frontend_file_response(
+    headers: &HeaderMap,
+    path: &str,
+    contents: &'static [u8],
+    etag: &str,
+    modified: Option<std::time::SystemTime>,
+) -> Response {
+    let available = Encoding::ALL
+        .into_iter()
+        .filter(|encoding| frontend_variant(path, *encoding).is_some())
+        .collect::<Vec<_>>();
+
+    let (contents, etag, encoding) = match preferred_encoding(headers, &available) {
+        Some(encoding) => (
+            frontend_variant(path, encoding).expect("to be available"),
+            encoded_etag(etag, encoding),
+            Some(encoding),
+        ),
+        None => (contents, etag.to_owned(), None),
+    };
+    let validators = Validators {
+        etag: &etag,
+        modified,
+    };
+    let cache_control = cache_control(path);
+
+    let mut response = if validators.not_modified(headers) {
+        not_modified(validators, cache_control)
+    } else {
+        let mut response = file_response(contents, path, validators, cache_control);
+        if let Some(encoding) = encoding {
+            response.headers_mut().insert(
+                header::CONTENT_ENCODING,
+                axum::http::HeaderValue::from_static(encoding.name()),
+            );
+        }
+        response
+    };
+
+    if !available.is_empty() {
+        response.headers_mut().insert(
+            header::VARY,
+            axum::http::HeaderValue::from_static("accept-encoding"),
+        );
+    }
+    response
+}
+
 pub fn file_response(
     contents: impl Into<axum::body::Full<bytes::Bytes>>,
     path: impl AsRef<std::path::Path>,
-    modified: std::time::SystemTime,
+    validators: Validators,
+    cache_control: &'static str,
 ) -> axum::response::Response {
-    let last_modified = httpdate::fmt_http_date(modified);
     let mime_type = mime_guess::from_path(path).first_or_text_plain();
-    axum::http::Response::builder()
+    let mut response = axum::http::Response::builder()
         .status(axum::http::StatusCode::OK)
         .header(
             axum::http::header::CONTENT_TYPE,
             axum::http::HeaderValue::from_str(mime_type.as_ref()).unwrap(),
         )
-        .header(axum::http::header::LAST_MODIFIED, last_modified)
+        .header(axum::http::header::CACHE_CONTROL, cache_control)
         .body(axum::body::boxed(contents.into()))
-        .unwrap_or_else(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        .unwrap_or_else(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response());
+
+    validators.headers(response.headers_mut());
+    response
+}
+
+pub fn not_modified(validators: Validators, cache_control: &'static str) -> Response {
+    let mut response = (
+        StatusCode::NOT_MODIFIED,
+        [(header::CACHE_CONTROL, cache_control)],
+    )
+        .into_response();
+    validators.headers(response.headers_mut());
+    response
+}
+
+/// Response of generated contents, validated by their hash
+///
+/// The ETag is weak, as the compression layer may encode the contents.
+pub fn generated_response(
+    headers: &HeaderMap,
+    contents: String,
+    content_type: &'static str,
+    modified: Option<std::time::SystemTime>,
+    cache_control: &'static str,
+) -> Response {
+    let etag = crate::serve_files::weak_etag(contents.as_bytes());
+    let validators = Validators {
+        etag: &etag,
+        modified,
+    };
+
+    if validators.not_modified(headers) {
+        return not_modified(validators, cache_control);
+    }
+
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        contents,
+    )
+        .into_response();
+    validators.headers(response.headers_mut());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::{Env, EnvConf};
+
+    #[test]
+    fn per_visitor_pages_kept_private() {
+        let mut env_conf = EnvConf::test_default();
+        env_conf.frontend.dist_path = None;
+        let conf = Conf::new(Env::Local, env_conf);
+        let cache_control = |per_visitor| {
+            index_response(&conf, &HeaderMap::new(), "<html>".into(), per_visitor).headers()
+                [header::CACHE_CONTROL]
+                .to_str()
+                .unwrap()
+                .to_owned()
+        };
+
+        assert_eq!(cache_control(true), "private, no-cache");
+        assert_eq!(
+            cache_control(false),
+            crate::serve_files::cache_control("index.html")
+        );
+    }
 }
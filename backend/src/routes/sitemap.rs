@@ -0,0 +1,32 @@
+use crate::routes::generated_response;
+use crate::routes::imports::*;
+use crate::sitemap;
+use axum::http::HeaderMap;
+
+static CACHE_CONTROL: &str = "public, max-age=3600";
+
+pub async fn serve_sitemap(
+    Extension(db): Extension<cozo::DbInstance>,
+    Extension(conf): Extension<Conf>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let sitemap = sitemap::load(&db, &conf.site)?;
+
+    Ok(generated_response(
+        &headers,
+        sitemap,
+        "application/xml; charset=utf-8",
+        None,
+        CACHE_CONTROL,
+    ))
+}
+
+pub async fn serve_robots(Extension(conf): Extension<Conf>, headers: HeaderMap) -> Response {
+    generated_response(
+        &headers,
+        sitemap::robots(&conf.site),
+        "text/plain; charset=utf-8",
+        None,
+        CACHE_CONTROL,
+    )
+}
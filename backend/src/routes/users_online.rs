@@ -1,8 +1,10 @@
+use crate::features::{Features, USERS_ONLINE_FEATURE};
 use crate::routes::imports::*;
+use crate::shutdown::Shutdown;
 use crate::startup::ip_address;
 use crate::startup::UserConnectInfo;
 use axum::extract::connect_info::ConnectInfo;
-use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
 use futures_util::{
     sink::SinkExt,
     stream::{SplitSink, SplitStream, StreamExt},
@@ -14,7 +16,12 @@ pub async fn ws_users_online(
     headers: hyper::HeaderMap,
     Extension(conf): Extension<Conf>,
     State(state): State<AppState>,
+    features: Features,
 ) -> Response {
+    if !features.enabled(USERS_ONLINE_FEATURE) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
     let ws = match maybe_ws {
         Ok(ws) => ws,
         Err(e) => {
@@ -55,7 +62,7 @@ async fn handle_socket(socket: WebSocket, state: AppState, sock: std::net::Socke
     let (sender, receiver) = socket.split();
     let rh = tokio::spawn(read(receiver));
     let con_count_r = state.users_online.con_count_r.clone();
-    let wh = tokio::spawn(write(sender, con_count_r));
+    let wh = tokio::spawn(write(sender, con_count_r, state.shutdown.clone()));
 
     // as soon as a closed channel error returns from any of these procedures,
     // cancel the other
@@ -102,9 +109,22 @@ async fn read(mut receiver: SplitStream<WebSocket>) {
 async fn write(
     mut sender: SplitSink<WebSocket, Message>,
     mut con_count_r: async_broadcast::Receiver<usize>,
+    shutdown: Shutdown,
 ) {
     loop {
-        match con_count_r.recv().await {
+        let received = tokio::select! {
+            received = con_count_r.recv() => received,
+            () = shutdown.signaled() => {
+                let close = Message::Close(Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "server is shutting down".into(),
+                }));
+                let _ = sender.send(close).await;
+                return;
+            }
+        };
+
+        match received {
             Ok(i) => {
                 let msg = Message::Text(format!("users_online:{i}"));
                 match sender.send(msg.clone()).await {
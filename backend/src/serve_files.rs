@@ -1,9 +1,22 @@
+use crate::conf::SymlinkPolicy;
+use axum::http::{header, HeaderMap, HeaderValue};
+use sha2::Digest;
+use std::{
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+#[cfg(feature = "embed-frontend")]
 pub static FRONTEND_DIR: include_dir::Dir<'_> =
     include_dir::include_dir!("$CARGO_MANIFEST_DIR/../frontend/dist/");
 
-pub static INDEX_HTML: &str = include_str!("../../frontend/dist/index.html");
+/// Brotli and gzip variants of `FRONTEND_DIR` files, compressed by the build script
+#[cfg(feature = "embed-frontend")]
+pub static FRONTEND_DIR_COMPRESSED: include_dir::Dir<'_> =
+    include_dir::include_dir!("$OUT_DIR/dist");
 
-pub static STATIC_DIR: &str = "static/";
+#[cfg(feature = "embed-frontend")]
+pub static INDEX_HTML: &str = include_str!("../../frontend/dist/index.html");
 
 #[derive(Debug)]
 #[allow(unused)]
@@ -11,3 +24,578 @@ pub struct ServedFile<'a> {
     pub path: &'a str,
     pub size: &'a str,
 }
+
+/// Resolves a requested path to a file confined to the static root
+///
+/// Requests with `..`, absolute or hidden components are rejected before touching
+/// the filesystem, and symlinks are served according to the policy.
+pub fn resolve_static(root: &Path, requested: &str, symlinks: SymlinkPolicy) -> Option<PathBuf> {
+    let requested = Path::new(requested);
+
+    let confined = requested.components().all(|component| match component {
+        Component::Normal(name) => !name.to_str().map_or(true, |name| name.starts_with('.')),
+        _ => false,
+    });
+    if !confined || requested.as_os_str().is_empty() {
+        return None;
+    }
+
+    let root = root.canonicalize().ok()?;
+
+    if symlinks == SymlinkPolicy::Deny {
+        let mut path = root.clone();
+        for component in requested.components() {
+            path.push(component);
+            if path.symlink_metadata().ok()?.file_type().is_symlink() {
+                return None;
+            }
+        }
+    }
+
+    let resolved = root.join(requested).canonicalize().ok()?;
+
+    let allowed = match symlinks {
+        SymlinkPolicy::Deny | SymlinkPolicy::WithinRoot => resolved.starts_with(&root),
+        SymlinkPolicy::Follow => true,
+    };
+
+    (allowed && resolved.is_file()).then_some(resolved)
+}
+
+/// Strong validator, a quoted content hash
+pub fn etag(contents: &[u8]) -> String {
+    let hash = sha2::Sha256::digest(contents);
+    format!("\"{}\"", hex::encode(&hash[..16]))
+}
+
+/// Weak validator, for contents the compression layer may encode
+pub fn weak_etag(contents: &[u8]) -> String {
+    format!("W/{}", etag(contents))
+}
+
+/// Whether the compression layer may encode a response
+///
+/// A strong ETag stands for the exact bytes sent, ranges of them included, so
+/// those responses are sent as they are. Precompressed ones carry their own.
+pub fn compressible(
+    status: axum::http::StatusCode,
+    _version: axum::http::Version,
+    headers: &HeaderMap,
+    _extensions: &axum::http::Extensions,
+) -> bool {
+    let strong_etag = headers
+        .get(header::ETAG)
+        .map_or(false, |etag| !etag.as_bytes().starts_with(b"W/"));
+
+    status != axum::http::StatusCode::PARTIAL_CONTENT && !strong_etag
+}
+
+/// ETag of an embedded frontend file, hashed once
+#[cfg(feature = "embed-frontend")]
+pub fn frontend_etag(path: &str) -> Option<&'static str> {
+    static ETAGS: std::sync::OnceLock<std::collections::HashMap<&'static str, String>> =
+        std::sync::OnceLock::new();
+
+    ETAGS
+        .get_or_init(|| {
+            let mut etags = std::collections::HashMap::new();
+            collect_etags(&FRONTEND_DIR, &mut etags);
+            etags
+        })
+        .get(path)
+        .map(String::as_str)
+}
+
+#[cfg(feature = "embed-frontend")]
+fn collect_etags(
+    dir: &'static include_dir::Dir<'static>,
+    etags: &mut std::collections::HashMap<&'static str, String>,
+) {
+    for entry in dir.entries() {
+        match entry {
+            include_dir::DirEntry::Dir(dir) => collect_etags(dir, etags),
+            include_dir::DirEntry::File(file) => {
+                if let Some(path) = file.path().to_str() {
+                    etags.insert(path, etag(file.contents()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embed-frontend")]
+pub fn index_html_etag() -> &'static str {
+    static ETAG: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    ETAG.get_or_init(|| etag(INDEX_HTML.as_bytes()))
+}
+
+/// Cache policy by file path
+///
+/// Trunk puts a content hash into asset names, so those never change,
+/// while index.html is revalidated shortly to pick up new deployments.
+pub fn cache_control(path: &str) -> &'static str {
+    if path.is_empty() || path == "index.html" {
+        "public, max-age=60"
+    } else if is_hashed_asset(path) {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    }
+}
+
+// like frontend-3b1c0a9f8e7d6c5b_bg.wasm or index-3b1c0a9f8e7d6c5b.css
+fn is_hashed_asset(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let stem = name.split('.').next().unwrap_or(name);
+    let stem = stem.strip_suffix("_bg").unwrap_or(stem);
+
+    stem.rsplit_once('-').map_or(false, |(_, hash)| {
+        // hex formatted without leading zeros
+        (12..=16).contains(&hash.len()) && hash.bytes().all(|b| b.is_ascii_hexdigit())
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// In order of preference on equal weights
+    pub const ALL: [Self; 2] = [Self::Brotli, Self::Gzip];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gz",
+        }
+    }
+}
+
+/// Precompressed variant of an embedded frontend file
+#[cfg(feature = "embed-frontend")]
+pub fn frontend_variant(path: &str, encoding: Encoding) -> Option<&'static [u8]> {
+    FRONTEND_DIR_COMPRESSED
+        .get_file(format!("{path}.{}", encoding.extension()))
+        .map(|file| file.contents())
+}
+
+/// ETag of a variant, distinct from the one of the original
+pub fn encoded_etag(etag: &str, encoding: Encoding) -> String {
+    format!("\"{}-{}\"", etag.trim_matches('"'), encoding.extension())
+}
+
+/// The available encoding weighted highest by `Accept-Encoding`, if any is acceptable
+pub fn preferred_encoding(headers: &HeaderMap, available: &[Encoding]) -> Option<Encoding> {
+    let accepted = headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|coding| {
+            let mut params = coding.split(';');
+            let name = params.next()?.trim().to_ascii_lowercase();
+            let weight = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((name, weight))
+        })
+        .collect::<Vec<_>>();
+
+    let weight = |encoding: Encoding| {
+        let named = |name: &str| {
+            name == encoding.name() || (encoding == Encoding::Gzip && name == "x-gzip")
+        };
+        accepted
+            .iter()
+            .find(|(name, _)| named(name))
+            .or_else(|| accepted.iter().find(|(name, _)| name == "*"))
+            .map_or(0.0, |(_, weight)| *weight)
+    };
+
+    let mut preferred = None;
+    for encoding in available.iter().copied() {
+        let weight = weight(encoding);
+        if weight > preferred.map_or(0.0, |(_, best)| best) {
+            preferred = Some((encoding, weight));
+        }
+    }
+    preferred.map(|(encoding, _)| encoding)
+}
+
+/// Validators of a served representation
+#[derive(Clone, Copy)]
+pub struct Validators<'a> {
+    pub etag: &'a str,
+    pub modified: Option<SystemTime>,
+}
+
+impl Validators<'_> {
+    /// Whether a conditional GET can be answered with 304
+    ///
+    /// If-Modified-Since is ignored when If-None-Match is present.
+    pub fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+            return if_none_match
+                .to_str()
+                .map_or(false, |tags| etag_listed(tags, self.etag));
+        }
+
+        match (
+            self.modified,
+            http_date(headers.get(header::IF_MODIFIED_SINCE)),
+        ) {
+            (Some(modified), Some(since)) => truncate_to_secs(modified) <= since,
+            _ => false,
+        }
+    }
+
+    /// Whether a ranged request applies to this representation
+    ///
+    /// Dates are too coarse to be strong validators, so only ETags match.
+    pub fn range_applies(&self, headers: &HeaderMap) -> bool {
+        match headers.get(header::IF_RANGE) {
+            None => true,
+            Some(if_range) => if_range.to_str().map_or(false, |tag| tag == self.etag),
+        }
+    }
+
+    pub fn headers(&self, headers: &mut HeaderMap) {
+        if let Ok(etag) = HeaderValue::from_str(self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(modified) = self.modified {
+            if let Ok(modified) = HeaderValue::from_str(&httpdate::fmt_http_date(modified)) {
+                headers.insert(header::LAST_MODIFIED, modified);
+            }
+        }
+    }
+}
+
+// weak comparison, as If-None-Match calls for
+fn etag_listed(tags: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+
+    tags.split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn http_date(value: Option<&HeaderValue>) -> Option<SystemTime> {
+    httpdate::parse_http_date(value?.to_str().ok()?).ok()
+}
+
+fn truncate_to_secs(time: SystemTime) -> SystemTime {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since_epoch) => {
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(since_epoch.as_secs())
+        }
+        Err(_) => time,
+    }
+}
+
+/// Byte range requested with a `Range` header
+#[derive(Debug, PartialEq)]
+pub enum ByteRange {
+    /// No range or an unsupported one, the whole file is served
+    Full,
+    /// Inclusive bounds
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parses a single range of a file of length `len`
+///
+/// Multiple ranges are served as the full file, which the spec allows.
+pub fn byte_range(headers: &HeaderMap, len: u64) -> ByteRange {
+    let Some(range) = headers.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return ByteRange::Full;
+    };
+    let Some(range) = range.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if range.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = range.split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // last `suffix` bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, len.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+    };
+
+    if len == 0 || start >= len {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), HeaderValue::from_str(value).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn strong_etags_not_compressed() {
+        use axum::http::{Extensions, StatusCode, Version};
+
+        let may_compress = |status, etag: Option<String>| {
+            let headers = match &etag {
+                Some(etag) => headers(&[(header::ETAG, etag.as_str())]),
+                None => HeaderMap::new(),
+            };
+            compressible(status, Version::HTTP_11, &headers, &Extensions::new())
+        };
+
+        assert!(may_compress(StatusCode::OK, None));
+        assert!(may_compress(StatusCode::OK, Some(weak_etag(b"feed"))));
+        assert!(!may_compress(StatusCode::OK, Some(etag(b"file"))));
+        assert!(!may_compress(StatusCode::PARTIAL_CONTENT, None));
+
+        let validators = Validators {
+            etag: &weak_etag(b"feed"),
+            modified: None,
+        };
+        let revalidated = headers(&[(header::IF_NONE_MATCH, etag(b"feed").as_str())]);
+        assert!(validators.not_modified(&revalidated));
+    }
+
+    #[test]
+    fn static_paths_confined() {
+        let root = std::env::temp_dir().join(format!("static-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("styles")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("styles/latte.css"), "").unwrap();
+        std::fs::write(root.join(".env"), "").unwrap();
+        std::fs::write(root.join(".git/config"), "").unwrap();
+
+        let resolve = |path: &str| resolve_static(&root, path, SymlinkPolicy::WithinRoot);
+
+        assert_eq!(
+            resolve("styles/latte.css"),
+            Some(root.canonicalize().unwrap().join("styles/latte.css"))
+        );
+        assert_eq!(resolve("styles/../styles/latte.css"), None);
+        assert_eq!(resolve("../etc/passwd"), None);
+        assert_eq!(resolve("/etc/passwd"), None);
+        assert_eq!(resolve("./styles/latte.css"), None);
+        assert_eq!(resolve(".env"), None);
+        assert_eq!(resolve(".git/config"), None);
+        assert_eq!(resolve("styles"), None);
+        assert_eq!(resolve("styles/missing.css"), None);
+        assert_eq!(resolve(""), None);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn static_symlink_policies() {
+        let dir = std::env::temp_dir().join(format!("static-{}", uuid::Uuid::new_v4()));
+        let root = dir.join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("inside.css"), "").unwrap();
+        std::fs::write(dir.join("outside.css"), "").unwrap();
+        std::os::unix::fs::symlink(root.join("inside.css"), root.join("inner.css")).unwrap();
+        std::os::unix::fs::symlink(dir.join("outside.css"), root.join("outer.css")).unwrap();
+
+        let resolves = |path: &str, symlinks| resolve_static(&root, path, symlinks).is_some();
+
+        for symlinks in [
+            SymlinkPolicy::Deny,
+            SymlinkPolicy::WithinRoot,
+            SymlinkPolicy::Follow,
+        ] {
+            assert!(resolves("inside.css", symlinks));
+        }
+
+        assert!(!resolves("inner.css", SymlinkPolicy::Deny));
+        assert!(!resolves("outer.css", SymlinkPolicy::Deny));
+        assert!(resolves("inner.css", SymlinkPolicy::WithinRoot));
+        assert!(!resolves("outer.css", SymlinkPolicy::WithinRoot));
+        assert!(resolves("inner.css", SymlinkPolicy::Follow));
+        assert!(resolves("outer.css", SymlinkPolicy::Follow));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn etags_are_strong_content_hashes() {
+        let a = etag(b"a");
+        assert!(a.starts_with('"') && a.ends_with('"'));
+        assert_eq!(a, etag(b"a"));
+        assert_ne!(a, etag(b"b"));
+    }
+
+    #[cfg(feature = "embed-frontend")]
+    #[test]
+    fn embedded_etags_hashed_once() {
+        assert_eq!(
+            frontend_etag("index.html"),
+            Some(etag(FRONTEND_DIR.get_file("index.html").unwrap().contents()).as_str())
+        );
+        assert_eq!(frontend_etag("missing.js"), None);
+    }
+
+    #[test]
+    fn encoding_negotiation() {
+        let preferred = |value: &str, available: &[Encoding]| {
+            preferred_encoding(&headers(&[(header::ACCEPT_ENCODING, value)]), available)
+        };
+        let all = &Encoding::ALL;
+
+        assert_eq!(preferred_encoding(&HeaderMap::new(), all), None);
+        assert_eq!(preferred("gzip, deflate, br", all), Some(Encoding::Brotli));
+        assert_eq!(
+            preferred("gzip, deflate, br", &[Encoding::Gzip]),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(preferred("br;q=0.5, gzip", all), Some(Encoding::Gzip));
+        assert_eq!(preferred("x-gzip", all), Some(Encoding::Gzip));
+        assert_eq!(preferred("*", all), Some(Encoding::Brotli));
+        assert_eq!(preferred("*, br;q=0", all), Some(Encoding::Gzip));
+        assert_eq!(preferred("deflate", all), None);
+        assert_eq!(preferred("br", &[]), None);
+
+        assert_ne!(encoded_etag("\"abc\"", Encoding::Brotli), "\"abc\"");
+        assert_ne!(
+            encoded_etag("\"abc\"", Encoding::Brotli),
+            encoded_etag("\"abc\"", Encoding::Gzip)
+        );
+    }
+
+    #[cfg(feature = "embed-frontend")]
+    #[test]
+    fn variants_are_embedded() {
+        let extension = |file: &include_dir::File| {
+            file.path()
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default()
+                .to_owned()
+        };
+        assert!(
+            FRONTEND_DIR.files().any(|file| extension(file) == "wasm"),
+            "no wasm bundle embedded"
+        );
+        let mut embedded = std::collections::HashMap::<&str, usize>::new();
+
+        for file in FRONTEND_DIR.files() {
+            let path = file.path().to_str().unwrap();
+            let extension = extension(file);
+            for encoding in Encoding::ALL {
+                match frontend_variant(path, encoding) {
+                    Some(variant) => {
+                        assert!(variant.len() < file.contents().len());
+                        *embedded.entry(encoding.name()).or_default() += 1;
+                    }
+                    // the bundle always compresses well, a tiny stylesheet may not
+                    None if matches!(extension.as_str(), "js" | "wasm") => {
+                        panic!("{path} has no {} variant", encoding.name())
+                    }
+                    None => (),
+                }
+            }
+        }
+
+        for encoding in Encoding::ALL {
+            assert!(
+                embedded.get(encoding.name()).copied().unwrap_or_default() > 0,
+                "no {} variants embedded",
+                encoding.name()
+            );
+        }
+    }
+
+    #[test]
+    fn cache_policies() {
+        let immutable = "public, max-age=31536000, immutable";
+
+        assert_eq!(
+            cache_control("frontend-3b1c0a9f8e7d6c5b_bg.wasm"),
+            immutable
+        );
+        assert_eq!(cache_control("frontend-3b1c0a9f8e7d6c5b.js"), immutable);
+        assert_eq!(cache_control("index-b1c0a9f8e7d6c5b.css"), immutable);
+        assert_eq!(cache_control("index.html"), "public, max-age=60");
+        assert_eq!(cache_control(""), "public, max-age=60");
+        assert_eq!(cache_control("favicon.ico"), "no-cache");
+        assert_eq!(cache_control("code_styles/dark-theme.css"), "no-cache");
+    }
+
+    #[test]
+    fn conditional_requests() {
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1_700_000_000_500);
+        let validators = Validators {
+            etag: "\"abc\"",
+            modified: Some(modified),
+        };
+        let date = httpdate::fmt_http_date(modified);
+        let earlier = httpdate::fmt_http_date(modified - std::time::Duration::from_secs(60));
+
+        assert!(!validators.not_modified(&HeaderMap::new()));
+        assert!(validators.not_modified(&headers(&[(header::IF_NONE_MATCH, "\"abc\"")])));
+        assert!(validators.not_modified(&headers(&[(header::IF_NONE_MATCH, "\"x\", W/\"abc\"")])));
+        assert!(validators.not_modified(&headers(&[(header::IF_NONE_MATCH, "*")])));
+        assert!(validators.not_modified(&headers(&[(header::IF_MODIFIED_SINCE, &date)])));
+        assert!(!validators.not_modified(&headers(&[(header::IF_MODIFIED_SINCE, &earlier)])));
+
+        // If-None-Match wins
+        assert!(!validators.not_modified(&headers(&[
+            (header::IF_NONE_MATCH, "\"x\""),
+            (header::IF_MODIFIED_SINCE, &date)
+        ])));
+
+        assert!(validators.range_applies(&HeaderMap::new()));
+        assert!(validators.range_applies(&headers(&[(header::IF_RANGE, "\"abc\"")])));
+        assert!(!validators.range_applies(&headers(&[(header::IF_RANGE, &date)])));
+    }
+
+    #[test]
+    fn byte_ranges() {
+        let range = |value: &str| byte_range(&headers(&[(header::RANGE, value)]), 100);
+
+        assert_eq!(byte_range(&HeaderMap::new(), 100), ByteRange::Full);
+        assert_eq!(range("bytes=0-9"), ByteRange::Partial(0, 9));
+        assert_eq!(range("bytes=90-"), ByteRange::Partial(90, 99));
+        assert_eq!(range("bytes=-10"), ByteRange::Partial(90, 99));
+        assert_eq!(range("bytes=-1000"), ByteRange::Partial(0, 99));
+        assert_eq!(range("bytes=50-1000"), ByteRange::Partial(50, 99));
+        assert_eq!(range("bytes=100-"), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=-0"), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=0-1,5-6"), ByteRange::Full);
+        assert_eq!(range("bytes=9-0"), ByteRange::Full);
+        assert_eq!(range("items=0-9"), ByteRange::Full);
+        assert_eq!(range("bytes=a-b"), ByteRange::Full);
+    }
+}
@@ -0,0 +1,379 @@
+// Session cookie signing keys
+//
+// Keys are ordered oldest first. Cookies are signed with the newest one,
+// and a cookie signed with an older one is re-signed with the newest
+// before it reaches SessionLayer, and sent back to the browser re-signed.
+//
+// Keys come from `session_secret` as comma separated hex, oldest first,
+// or from KEYS_FILE with one hex key per line, generated when missing.
+//
+
+use crate::conf::Conf;
+use anyhow::Context;
+use axum::extract::State;
+use cookie::{Cookie, CookieJar, Key};
+use std::sync::Arc;
+
+pub static SESSION_COOKIE_NAME: &str = "sid";
+
+static KEYS_FILE: &str = "secrets/session_keys.hex";
+// single key file used before rotation was supported
+static LEGACY_KEY_FILE: &str = "secrets/token.hex";
+
+// cookie::Key requires at least 64 bytes
+const KEY_LEN: usize = 64;
+
+#[derive(Clone)]
+pub struct SessionKeys {
+    keys: Arc<Vec<Key>>,
+}
+
+impl SessionKeys {
+    pub fn from_hex_list<'a>(hex_keys: impl IntoIterator<Item = &'a str>) -> anyhow::Result<Self> {
+        let keys = hex_keys
+            .into_iter()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                let bytes = hex::decode(v).context("session key is not HEX")?;
+                Key::try_from(bytes.as_slice()).context("session key is too short")
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        anyhow::ensure!(!keys.is_empty(), "no session keys");
+
+        Ok(Self {
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// Keys from `session_secret` if configured, else from KEYS_FILE
+    pub fn load(conf: &Conf) -> anyhow::Result<Self> {
+        match &conf.session_secret {
+            Some(secret) => Self::from_hex_list(secret.split(',')),
+            None => Self::from_hex_list(read_keys_file()?.lines()),
+        }
+    }
+
+    /// Checks the keys `load` would read, without generating missing ones
+    pub fn verify(conf: &Conf) -> anyhow::Result<()> {
+        let keys = match &conf.session_secret {
+            Some(secret) => Self::from_hex_list(secret.split(',')),
+            None => std::fs::read_to_string(KEYS_FILE)
+                .with_context(|| format!("failed to read {KEYS_FILE}"))
+                .and_then(|contents| Self::from_hex_list(contents.lines())),
+        };
+        keys.map(|_| ())
+    }
+
+    /// Generates a new key and retires the oldest ones, keeping at most `keep`
+    pub fn rotate(&self, keep: usize) -> Self {
+        let mut keys = self.keys.as_ref().clone();
+        keys.push(Key::try_from(random_key().as_slice()).expect("key of valid length"));
+
+        let retired = keys.len().saturating_sub(keep.max(1));
+        keys.drain(..retired);
+
+        Self {
+            keys: Arc::new(keys),
+        }
+    }
+
+    pub fn oldest(&self) -> &Key {
+        self.keys.first().expect("at least one key")
+    }
+
+    pub fn newest(&self) -> &Key {
+        self.keys.last().expect("at least one key")
+    }
+
+    pub fn count(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn to_hex_list(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .map(|key| hex::encode(key.master()))
+            .collect()
+    }
+
+    /// Writes the keys to KEYS_FILE, for keys that were not configured by `session_secret`
+    pub fn save(&self) -> std::io::Result<()> {
+        std::fs::create_dir_all(std::path::Path::new(KEYS_FILE).parent().unwrap())?;
+        std::fs::write(KEYS_FILE, self.to_hex_list().join("\n") + "\n")
+    }
+
+    /// Cookie value signed with the newest key, if it's currently signed with an older one
+    fn resign(&self, name: &str, signed_value: &str) -> Option<String> {
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::new(name.to_owned(), signed_value.to_owned()));
+
+        if jar.signed(self.newest()).get(name).is_some() {
+            return None;
+        }
+
+        let cookie = self
+            .keys
+            .iter()
+            .rev()
+            .skip(1)
+            .find_map(|key| jar.signed(key).get(name))?;
+
+        let mut jar = CookieJar::new();
+        jar.signed_mut(self.newest()).add(cookie);
+        jar.get(name).map(|cookie| cookie.value().to_owned())
+    }
+}
+
+fn random_key() -> Vec<u8> {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    (0..KEY_LEN).map(|_| rng.gen::<u8>()).collect()
+}
+
+fn read_keys_file() -> anyhow::Result<String> {
+    if let Ok(contents) = std::fs::read_to_string(KEYS_FILE) {
+        return Ok(contents);
+    }
+
+    // keep sessions signed with the legacy key valid
+    let contents = match std::fs::read_to_string(LEGACY_KEY_FILE) {
+        Ok(legacy_key) if !legacy_key.trim().is_empty() => legacy_key.trim().to_owned(),
+        _ => hex::encode(random_key()),
+    };
+
+    std::fs::create_dir_all(std::path::Path::new(KEYS_FILE).parent().unwrap())?;
+    std::fs::write(KEYS_FILE, format!("{contents}\n"))
+        .with_context(|| format!("failed to write {KEYS_FILE}"))?;
+
+    Ok(contents)
+}
+
+// to be layered outside of SessionLayer
+//
+// SessionLayer responds with a cookie only when the session is new or changed,
+// so the re-signed one is set here, for browsers to stop sending the old one.
+pub async fn resign_session_cookie<B>(
+    State((keys, ttl)): State<(SessionKeys, std::time::Duration)>,
+    mut request: hyper::http::Request<B>,
+    next: axum::middleware::Next<B>,
+) -> axum::response::Response {
+    let mut resigned_value = None;
+    if keys.count() > 1 {
+        let resigned = request
+            .headers()
+            .get(hyper::header::COOKIE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|header| resign_cookie_header(&keys, header));
+
+        if let Some((header, value)) = resigned {
+            if let Ok(header) = header.parse() {
+                request.headers_mut().insert(hyper::header::COOKIE, header);
+                resigned_value = Some(value);
+            }
+        }
+    }
+
+    let mut response = next.run(request).await;
+    if let Some(value) = resigned_value {
+        set_resigned_cookie(response.headers_mut(), value, ttl);
+    }
+
+    response
+}
+
+/// Header with the session cookie re-signed, and the re-signed value
+fn resign_cookie_header(keys: &SessionKeys, header: &str) -> Option<(String, String)> {
+    let mut resigned = None;
+
+    let pairs = header
+        .split(';')
+        .map(str::trim)
+        .map(|pair| match Cookie::parse_encoded(pair) {
+            Ok(cookie) if cookie.name() == SESSION_COOKIE_NAME => {
+                match keys.resign(cookie.name(), cookie.value()) {
+                    None => pair.to_owned(),
+                    Some(value) => {
+                        let pair = Cookie::new(SESSION_COOKIE_NAME, value.clone())
+                            .encoded()
+                            .to_string();
+                        resigned = Some(value);
+                        pair
+                    }
+                }
+            }
+            _ => pair.to_owned(),
+        })
+        .collect::<Vec<_>>();
+
+    resigned.map(|value| (pairs.join("; "), value))
+}
+
+// with the attributes SessionLayer sets, unless it has set the cookie itself
+fn set_resigned_cookie(headers: &mut hyper::HeaderMap, value: String, ttl: std::time::Duration) {
+    let prefix = format!("{SESSION_COOKIE_NAME}=");
+    let already_set = headers
+        .get_all(hyper::header::SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.starts_with(&prefix));
+    if already_set {
+        return;
+    }
+
+    let cookie = Cookie::build(SESSION_COOKIE_NAME, value)
+        .path("/")
+        .secure(true)
+        .http_only(true)
+        .same_site(cookie::SameSite::Lax)
+        .max_age(cookie::time::Duration::seconds(ttl.as_secs() as i64))
+        .finish();
+    if let Ok(header) = cookie.encoded().to_string().parse() {
+        headers.append(hyper::header::SET_COOKIE, header);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_cookie(key: &Key, value: &str) -> String {
+        let mut jar = CookieJar::new();
+        jar.signed_mut(key)
+            .add(Cookie::new(SESSION_COOKIE_NAME, value.to_owned()));
+        jar.get(SESSION_COOKIE_NAME).unwrap().value().to_owned()
+    }
+
+    fn verified(key: &Key, signed_value: &str) -> Option<String> {
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::new(SESSION_COOKIE_NAME, signed_value.to_owned()));
+        jar.signed(key)
+            .get(SESSION_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_owned())
+    }
+
+    #[test]
+    fn rotation() {
+        let keys = SessionKeys::from_hex_list([hex::encode(random_key()).as_str()]).unwrap();
+
+        let rotated = keys.rotate(2);
+        assert_eq!(rotated.count(), 2);
+        assert_eq!(rotated.to_hex_list()[0], keys.to_hex_list()[0]);
+
+        let rotated_again = rotated.rotate(2);
+        assert_eq!(rotated_again.count(), 2);
+        assert_eq!(rotated_again.to_hex_list()[0], rotated.to_hex_list()[1]);
+
+        assert_eq!(rotated_again.rotate(1).count(), 1);
+    }
+
+    #[test]
+    fn invalid_keys_rejected() {
+        assert!(SessionKeys::from_hex_list([""]).is_err());
+        assert!(SessionKeys::from_hex_list(["not hex"]).is_err());
+        assert!(SessionKeys::from_hex_list(["abcd"]).is_err());
+    }
+
+    #[test]
+    fn cookie_signed_with_older_key_resigned() {
+        let old = SessionKeys::from_hex_list([hex::encode(random_key()).as_str()]).unwrap();
+        let keys = old.rotate(2);
+
+        let value = hex::encode(random_key());
+        let old_cookie = signed_cookie(old.newest(), &value);
+        let header = format!("other=1; {SESSION_COOKIE_NAME}={old_cookie}");
+
+        let (resigned, new_value) = resign_cookie_header(&keys, &header).expect("to be re-signed");
+        let pair = resigned
+            .split("; ")
+            .find_map(|pair| pair.strip_prefix(&format!("{SESSION_COOKIE_NAME}=")))
+            .unwrap();
+        let new_cookie = Cookie::parse_encoded(format!("{SESSION_COOKIE_NAME}={pair}")).unwrap();
+
+        assert!(resigned.starts_with("other=1; "));
+        assert_eq!(new_cookie.value(), new_value);
+        assert_eq!(verified(keys.newest(), new_cookie.value()), Some(value));
+    }
+
+    async fn set_cookies(keys: &SessionKeys, cookie: &str, handler_sets: bool) -> Vec<String> {
+        use tower::ServiceExt;
+
+        let handler = move || async move {
+            let mut headers = hyper::HeaderMap::new();
+            if handler_sets {
+                let cookie = format!("{SESSION_COOKIE_NAME}=from_session_layer");
+                headers.insert(hyper::header::SET_COOKIE, cookie.parse().unwrap());
+            }
+            headers
+        };
+        let app = axum::Router::new()
+            .route("/", axum::routing::get(handler))
+            .layer(axum::middleware::from_fn_with_state(
+                (keys.clone(), std::time::Duration::from_secs(60)),
+                resign_session_cookie,
+            ));
+        let request = hyper::Request::builder()
+            .uri("/")
+            .header(
+                hyper::header::COOKIE,
+                format!("{SESSION_COOKIE_NAME}={cookie}"),
+            )
+            .body(hyper::Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get_all(hyper::header::SET_COOKIE)
+            .iter()
+            .map(|v| v.to_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn resigned_cookie_sent_back() {
+        let old = SessionKeys::from_hex_list([hex::encode(random_key()).as_str()]).unwrap();
+        let keys = old.rotate(2);
+        let value = hex::encode(random_key());
+
+        let old_cookie = signed_cookie(old.newest(), &value);
+        let set = set_cookies(&keys, &old_cookie, false).await;
+        assert_eq!(set.len(), 1);
+        let cookie = Cookie::parse_encoded(set[0].clone()).unwrap();
+        assert_eq!(verified(keys.newest(), cookie.value()), Some(value.clone()));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.max_age(), Some(cookie::time::Duration::seconds(60)));
+
+        // the one SessionLayer sets wins
+        let set = set_cookies(&keys, &old_cookie, true).await;
+        assert_eq!(
+            set,
+            vec![format!("{SESSION_COOKIE_NAME}=from_session_layer")]
+        );
+
+        let current_cookie = signed_cookie(keys.newest(), &value);
+        assert!(set_cookies(&keys, &current_cookie, false).await.is_empty());
+    }
+
+    #[test]
+    fn current_and_unknown_cookies_kept() {
+        let keys = SessionKeys::from_hex_list([hex::encode(random_key()).as_str()])
+            .unwrap()
+            .rotate(2);
+
+        let current = signed_cookie(keys.newest(), "value");
+        assert_eq!(
+            resign_cookie_header(&keys, &format!("{SESSION_COOKIE_NAME}={current}")),
+            None
+        );
+
+        let unknown_key = Key::try_from(random_key().as_slice()).unwrap();
+        let unknown = signed_cookie(&unknown_key, "value");
+        assert_eq!(
+            resign_cookie_header(&keys, &format!("{SESSION_COOKIE_NAME}={unknown}")),
+            None
+        );
+    }
+}
@@ -0,0 +1,211 @@
+// Session store backed by the sessions relation
+//
+// Each session is stored with metadata shown to its owner:
+// when it was created and last seen, where from and with what browser
+//
+
+use crate::{conf::Conf, db, startup};
+use axum_sessions::{
+    async_session::{async_trait, Session, SessionStore},
+    extractors::WritableSession,
+};
+use serde::{Deserialize, Serialize};
+
+static META_KEY: &str = "meta";
+// last seen is written at most this often by reads alone
+const LAST_SEEN_PERIOD: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct SessionMeta {
+    created_at: String,
+    user_agent_class: Option<String>,
+    hashed_ip: Option<String>,
+}
+
+/// Records where the session is used from, to be called on login
+pub fn record_meta(
+    session: &mut WritableSession,
+    conf: &Conf,
+    h: &hyper::HeaderMap,
+) -> anyhow::Result<()> {
+    let user_agent_class = h
+        .get(hyper::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(user_agent_class);
+
+    let meta = SessionMeta {
+        created_at: interfacing::EndpointHit::formatted_now(),
+        user_agent_class,
+        hashed_ip: Some(startup::hash_ip(startup::ip_address(h), conf)),
+    };
+
+    Ok(session.insert(META_KEY, meta)?)
+}
+
+// coarse enough to not identify anyone, fine enough to recognize own devices
+pub fn user_agent_class(user_agent: &str) -> String {
+    let browser = [
+        ("Firefox/", "Firefox"),
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ]
+    .into_iter()
+    .find(|(pattern, _)| user_agent.contains(pattern))
+    .map(|(_, name)| name);
+
+    let os = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .into_iter()
+    .find(|(pattern, _)| user_agent.contains(pattern))
+    .map(|(_, name)| name);
+
+    match (browser, os) {
+        (Some(browser), Some(os)) => format!("{browser} on {os}"),
+        (Some(browser), None) => browser.into(),
+        (None, Some(os)) => format!("Other on {os}"),
+        (None, None) => "Other".into(),
+    }
+}
+
+#[derive(derivative::Derivative, Clone)]
+#[derivative(Debug)]
+pub struct BonsaiDBSessionStore {
+    #[derivative(Debug = "ignore")]
+    pub db: cozo::DbInstance,
+}
+
+#[async_trait]
+impl SessionStore for BonsaiDBSessionStore {
+    async fn load_session(
+        &self,
+        cookie_value: String,
+    ) -> axum_sessions::async_session::Result<Option<Session>> {
+        let id = Session::id_from_cookie_value(&cookie_value)?;
+
+        let session: Option<Session> = db::q::find_session_by_id(&self.db, &id)?
+            .map(|v| serde_json::from_str(&v).ok())
+            .flatten()
+            .and_then(Session::validate);
+
+        if let Some(session) = &session {
+            let now = std::time::SystemTime::now();
+            let stale_before = humantime::format_rfc3339(now - LAST_SEEN_PERIOD).to_string();
+            let _result = db::q::touch_session(
+                &self.db,
+                session.id(),
+                &humantime::format_rfc3339(now).to_string(),
+                &stale_before,
+            )
+            .map_err(|e| tracing::error!("{e:?}"));
+        }
+
+        Ok(session)
+    }
+
+    async fn store_session(
+        &self,
+        mut session: Session,
+    ) -> axum_sessions::async_session::Result<Option<String>> {
+        let now = interfacing::EndpointHit::formatted_now();
+
+        // anonymous sessions don't go through login
+        let meta = match session.get::<SessionMeta>(META_KEY) {
+            Some(meta) => meta,
+            None => {
+                let meta = SessionMeta {
+                    created_at: now.clone(),
+                    ..Default::default()
+                };
+                session.insert(META_KEY, meta.clone())?;
+                meta
+            }
+        };
+
+        let expires_at = session
+            .expires_in()
+            .map(|ttl| humantime::format_rfc3339(std::time::SystemTime::now() + ttl).to_string());
+
+        db::q::put_session(
+            &self.db,
+            db::q::StoredSession {
+                id: session.id().into(),
+                value: serde_json::to_string(&session)?,
+                username: session.get::<String>("username"),
+                expires_at,
+                created_at: meta.created_at,
+                last_seen_at: now,
+                user_agent_class: meta.user_agent_class,
+                hashed_ip: meta.hashed_ip,
+            },
+        )?;
+        session.reset_data_changed();
+        Ok(session.into_cookie_value())
+    }
+
+    async fn destroy_session(&self, session: Session) -> axum_sessions::async_session::Result {
+        db::q::rm_session(&self.db, session.id())?;
+        Ok(())
+    }
+
+    async fn clear_store(&self) -> axum_sessions::async_session::Result {
+        tracing::info!("clear session store");
+        db::q::rm_all_sessions(&self.db)?;
+        Ok(())
+    }
+}
+
+/// Removes expired sessions every period, they are never loaded again anyway
+pub async fn purge_expired_sessions(
+    db: cozo::DbInstance,
+    period: std::time::Duration,
+    shutdown: crate::shutdown::Shutdown,
+) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => (),
+            // not to hold on to the database
+            () = shutdown.signaled() => return,
+        }
+
+        let now = interfacing::EndpointHit::formatted_now();
+        let _result = db::q::rm_expired_sessions(&db, &now).map_err(|e| tracing::error!("{e:?}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::user_agent_class;
+
+    #[test]
+    fn user_agent_classes() {
+        assert_eq!(
+            user_agent_class(
+                "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0"
+            ),
+            "Firefox on Linux"
+        );
+        assert_eq!(
+            user_agent_class("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Safari/537.36 Edg/116.0.1938.69"),
+            "Edge on Windows"
+        );
+        assert_eq!(
+            user_agent_class("Mozilla/5.0 (iPhone; CPU iPhone OS 16_6 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.6 Mobile/15E148 Safari/604.1"),
+            "Safari on iOS"
+        );
+        assert_eq!(
+            user_agent_class("Mozilla/5.0 (Linux; Android 13) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.5845.163 Mobile Safari/537.36"),
+            "Chrome on Android"
+        );
+        assert_eq!(user_agent_class("curl/8.2.1"), "Other");
+    }
+}
@@ -0,0 +1,153 @@
+// Graceful shutdown
+//
+// Once triggered, servers stop accepting connections, websockets are sent
+// a close frame, and in-flight requests have until the drain deadline to finish.
+//
+
+use std::sync::Arc;
+use tokio::sync::watch;
+
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self {
+            tx: Arc::new(watch::channel(false).0),
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn triggered(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Resolves once triggered
+    pub async fn signaled(&self) {
+        let mut rx = self.tx.subscribe();
+        while !*rx.borrow_and_update() {
+            // the sender lives as long as self
+            let _ = rx.changed().await;
+        }
+    }
+}
+
+/// Resolves on SIGINT, or SIGTERM on unix
+pub async fn os_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("failed to listen for SIGINT: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => (),
+        () = terminate => (),
+    }
+}
+
+/// Runs the server until it drains, or until the deadline passes after shutdown is triggered
+pub async fn drain<F>(
+    server: F,
+    shutdown: Shutdown,
+    deadline: std::time::Duration,
+) -> std::io::Result<()>
+where
+    F: std::future::Future<Output = std::io::Result<()>>,
+{
+    let deadline_passed = async {
+        shutdown.signaled().await;
+        tokio::time::sleep(deadline).await;
+    };
+
+    tokio::select! {
+        result = server => result,
+        () = deadline_passed => {
+            tracing::warn!("drain deadline passed, dropping remaining connections");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn signaled_after_trigger() {
+        let shutdown = Shutdown::default();
+        assert!(!shutdown.triggered());
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.signaled().await }
+        });
+
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("to be signaled")
+            .unwrap();
+
+        // already triggered
+        assert!(shutdown.triggered());
+        shutdown.signaled().await;
+    }
+
+    #[tokio::test]
+    async fn drain_deadline() {
+        let shutdown = Shutdown::default();
+        let deadline = Duration::from_millis(10);
+
+        // a server that drains in time
+        let draining = drain(
+            {
+                let shutdown = shutdown.clone();
+                async move {
+                    shutdown.signaled().await;
+                    Ok(())
+                }
+            },
+            shutdown.clone(),
+            deadline,
+        );
+        // a server that never drains
+        let stuck = drain(std::future::pending(), shutdown.clone(), deadline);
+
+        shutdown.trigger();
+        let (draining, stuck) = tokio::time::timeout(Duration::from_secs(1), async {
+            tokio::join!(draining, stuck)
+        })
+        .await
+        .expect("both to finish");
+
+        assert!(draining.is_ok());
+        assert!(stuck.is_ok());
+    }
+}
@@ -0,0 +1,160 @@
+// Sitemap and robots.txt for crawlers
+//
+// The sitemap lists the public root routes, the static articles of the frontend
+// and the published articles, the latter dated by article_meta when known.
+//
+
+use crate::conf::SiteConf;
+use crate::db;
+use crate::feeds::escape;
+use static_routes::*;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub url: String,
+    pub modified: Option<SystemTime>,
+}
+
+/// Sitemap of the site as is
+pub fn load(db: &cozo::DbInstance, site: &SiteConf) -> db::Result<String> {
+    let articles = db::q::find_articles(db)?;
+    let meta = db::q::find_article_meta(db)?;
+    Ok(render(&locations(site, articles, meta)))
+}
+
+pub fn locations(
+    site: &SiteConf,
+    articles: Vec<interfacing::ArticleWithId>,
+    meta: Vec<db::q::ArticleMeta>,
+) -> Vec<Location> {
+    let routes = routes().root;
+    let by_public_id = &routes.articles.by_public_id;
+    let article_url = |public_id: &str| site.url(by_public_id.with(public_id).get().complete());
+
+    // login and admin pages are of no use to crawlers
+    let root = [routes.home.get(), routes.articles.get()]
+        .into_iter()
+        .map(|route| Location {
+            url: site.url(route.complete()),
+            modified: None,
+        });
+
+    let static_articles = interfacing::static_articles()
+        .into_iter()
+        .map(|article| Location {
+            url: article_url(&article.public_id),
+            modified: None,
+        });
+
+    let published = articles
+        .into_iter()
+        .filter(|article| !article.body().draft)
+        .map(|article| Location {
+            url: article_url(&article.body().public_id),
+            modified: meta
+                .iter()
+                .find(|meta| meta.article_id == article.id)
+                .and_then(|meta| humantime::parse_rfc3339_weak(&meta.updated_at).ok()),
+        });
+
+    root.chain(static_articles).chain(published).collect()
+}
+
+pub fn render(locations: &[Location]) -> String {
+    let mut urls = String::new();
+    for location in locations {
+        urls.push_str(&format!("<url><loc>{}</loc>", escape(&location.url)));
+        if let Some(modified) = location.modified {
+            urls.push_str(&format!(
+                "<lastmod>{}</lastmod>",
+                humantime::format_rfc3339_seconds(modified)
+            ));
+        }
+        urls.push_str("</url>\n");
+    }
+
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+            "\n{}</urlset>\n"
+        ),
+        urls
+    )
+}
+
+pub fn robots(site: &SiteConf) -> String {
+    let mut robots = String::from("User-agent: *\n");
+    for path in &site.robots.disallow {
+        robots.push_str(&format!("Disallow: {path}\n"));
+    }
+    robots.push_str(&format!(
+        "\nSitemap: {}\n",
+        site.url(routes().root.sitemap.get().complete())
+    ));
+    robots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::EnvConf;
+
+    fn article(public_id: &str, draft: bool) -> interfacing::ArticleWithId {
+        interfacing::ArticleWithId {
+            id: uuid::Uuid::new_v4().to_string(),
+            body: interfacing::Article {
+                title: public_id.into(),
+                public_id: public_id.into(),
+                draft,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn locations_listed() {
+        let site = EnvConf::test_default().site;
+        let published = article("published", false);
+        let meta = vec![db::q::ArticleMeta {
+            article_id: published.id.clone(),
+            tags: vec![],
+            published_at: None,
+            updated_at: "2023-01-01T00:00:00Z".into(),
+        }];
+
+        let locations = locations(&site, vec![published, article("draft", true)], meta);
+        let urls: Vec<&str> = locations
+            .iter()
+            .map(|location| location.url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "http://localhost:8000/",
+                "http://localhost:8000/articles",
+                "http://localhost:8000/articles/about",
+                "http://localhost:8000/articles/md-article-editor",
+                "http://localhost:8000/articles/snake",
+                "http://localhost:8000/articles/published",
+            ]
+        );
+
+        let sitemap = render(&locations);
+        assert_eq!(sitemap.matches("<url>").count(), 6);
+        assert!(sitemap.contains(
+            "<url><loc>http://localhost:8000/articles/published</loc><lastmod>2023-01-01T00:00:00Z</lastmod></url>"
+        ));
+    }
+
+    #[test]
+    fn robots_disallow_admin() {
+        let site = EnvConf::test_default().site;
+        assert_eq!(
+            robots(&site),
+            "User-agent: *\nDisallow: /admin\nDisallow: /api/admin\n\nSitemap: http://localhost:8000/sitemap.xml\n"
+        );
+    }
+}
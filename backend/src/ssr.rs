@@ -0,0 +1,170 @@
+// Server side rendering of public pages
+//
+// The frontend, compiled for the server, renders the article list, article
+// views and the snake page. The data their components fetch is prefetched
+// from the db and embedded into the page, so the browser hydrates with it
+// instead of fetching again. Admin pages depend on the session and are left
+// to the browser.
+//
+
+use crate::db;
+use frontend::{Routable, Route};
+
+/// Data the page of a path is rendered with, None when it is not rendered here
+///
+/// Features are left out, they are evaluated per visitor.
+pub fn prefetch(db: &cozo::DbInstance, path: &str) -> db::Result<Option<interfacing::Prefetched>> {
+    let mut prefetched = interfacing::Prefetched::default();
+
+    match Route::recognize(path) {
+        Some(Route::Home | Route::ArticleList) => {
+            let articles = db::q::find_articles(db)?
+                .into_iter()
+                .filter(|article| !article.body().draft)
+                .collect();
+            prefetched.articles = Some(articles);
+        }
+        Some(Route::ArticleViewer { public_id }) => {
+            let is_static = interfacing::static_articles()
+                .into_iter()
+                .any(|article| article.public_id == public_id);
+
+            if !is_static {
+                match db::q::find_article_by_public_id(db, &public_id)? {
+                    Some(article) if !article.body().draft => prefetched.article = Some(article),
+                    _ => return Ok(None),
+                }
+            }
+        }
+        Some(Route::Snake) => (),
+        _ => return Ok(None),
+    }
+
+    Ok(Some(prefetched))
+}
+
+/// Page rendered with prefetched data, to be put into index.html
+pub struct Page {
+    pub rendered: frontend::ServerRendered,
+    pub prefetched: interfacing::Prefetched,
+}
+
+impl Page {
+    pub async fn render(path: &str, prefetched: interfacing::Prefetched) -> Self {
+        Self {
+            rendered: frontend::render(path.to_owned(), prefetched.clone()).await,
+            prefetched,
+        }
+    }
+
+    /// index.html with styles and prefetched data in its head, and the page as its body
+    pub fn inject(&self, index: &str) -> String {
+        // not to end the script early
+        let prefetched = serde_json::to_string(&self.prefetched)
+            .expect("to serialize")
+            .replace("</", "<\\/");
+        let head = format!(
+            r#"{}<script id="{}" type="application/json">{prefetched}</script>"#,
+            self.rendered.styles,
+            interfacing::PREFETCHED_ELEMENT_ID,
+        );
+
+        let index = match index.find("</head>") {
+            Some(end) => format!("{}{head}\n{}", &index[..end], &index[end..]),
+            None => return index.to_owned(),
+        };
+
+        // trunk leaves the body out when index.html has none
+        match index.find("<body") {
+            Some(start) => match index[start..].find('>') {
+                Some(end) => {
+                    let end = start + end + 1;
+                    format!("{}{}{}", &index[..end], self.rendered.body, &index[end..])
+                }
+                None => index,
+            },
+            None => match index.rfind("</html>") {
+                Some(end) => format!(
+                    "{}<body>{}</body>\n{}",
+                    &index[..end],
+                    self.rendered.body,
+                    &index[end..]
+                ),
+                None => format!("{index}<body>{}</body>", self.rendered.body),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static INDEX: &str = "<html>\n<head>\n    <title>Frontend</title>\n</head>\n</html>";
+
+    fn db_with(articles: &[interfacing::Article]) -> cozo::DbInstance {
+        let db = cozo::DbInstance::default();
+        db::migrate(&db);
+        for article in articles {
+            db::q::put_article(&db, article.clone()).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn prefetched_by_route() {
+        let db = db_with(&[
+            interfacing::Article {
+                title: "Intro".into(),
+                public_id: "intro".into(),
+                ..Default::default()
+            },
+            interfacing::Article {
+                title: "Secret".into(),
+                public_id: "secret".into(),
+                draft: true,
+                ..Default::default()
+            },
+        ]);
+        let prefetch = |path| prefetch(&db, path).unwrap();
+
+        let articles = prefetch("/articles").unwrap().articles.unwrap();
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].body().public_id, "intro");
+
+        let article = prefetch("/articles/intro").unwrap().article.unwrap();
+        assert_eq!(article.body().title, "Intro");
+
+        // built into the frontend
+        assert_eq!(prefetch("/articles/about"), Some(Default::default()));
+
+        assert_eq!(prefetch("/articles/secret"), None);
+        assert_eq!(prefetch("/articles/missing"), None);
+        assert_eq!(prefetch("/admin/dashboard"), None);
+    }
+
+    #[tokio::test]
+    async fn injected_into_index() {
+        let prefetched = interfacing::Prefetched {
+            article: Some(interfacing::ArticleWithId {
+                id: "id".into(),
+                body: interfacing::Article {
+                    title: "Scripts".into(),
+                    public_id: "scripts".into(),
+                    markdown: "Closes with `</script>`".into(),
+                    ..Default::default()
+                },
+            }),
+            features: Some(Default::default()),
+            ..Default::default()
+        };
+        let page = Page::render("/articles/scripts", prefetched).await;
+        let html = page.inject(INDEX);
+
+        let body = html.find("<body>").unwrap();
+        assert!(html.find(r#"<script id="prefetched""#).unwrap() < html.find("</head>").unwrap());
+        assert_eq!(html.matches("</script>").count(), 1);
+        assert!(html[body..].contains("Closes with"));
+        assert!(html.find("</body>").unwrap() < html.find("</html>").unwrap());
+    }
+}
@@ -1,142 +1,267 @@
 use crate::conf::Conf;
+use crate::endpoint_hits::{EndpointHitQueue, EndpointHitWriter};
+use crate::shutdown::Shutdown;
+use anyhow::Context;
 use static_routes::*;
 
-use axum::{
-    routing::{delete, get, post, put},
-    Router,
-};
-use axum_sessions::{
-    async_session::{async_trait, Session, SessionStore},
-    SessionLayer,
-};
+use axum::Router;
+use axum_sessions::SessionLayer;
 use std::sync::Arc;
-use tower_http::{add_extension::AddExtensionLayer, compression::CompressionLayer};
+use tower_http::{
+    add_extension::AddExtensionLayer,
+    compression::{
+        predicate::{DefaultPredicate, Predicate},
+        CompressionLayer,
+    },
+};
 
-pub fn router(conf: Conf, db: cozo::DbInstance) -> Router<AppState> {
+pub fn router(
+    conf: Conf,
+    db: cozo::DbInstance,
+    endpoint_hits: EndpointHitQueue,
+    session_keys: crate::session_keys::SessionKeys,
+) -> Router<AppState> {
     use crate::routes::*;
 
+    registered(&conf)
+        .router
+        .fallback(fallback)
+        // leaves precompressed frontend files alone, as they have Content-Encoding
+        // set, and files validated by a strong ETag, ranges of them included
+        .layer(
+            CompressionLayer::new()
+                .compress_when(DefaultPredicate::new().and(crate::serve_files::compressible)),
+        )
+        .layer(axum::middleware::from_fn(endpoint_hit_middleware))
+        .layer(axum::middleware::from_fn(crate::metrics::track_requests))
+        .layer(AddExtensionLayer::new(endpoint_hits))
+        .layer(AddExtensionLayer::new(db.clone()))
+        .layer(AddExtensionLayer::new(conf.clone()))
+        .layer(AddExtensionLayer::new(Arc::new(
+            crate::passkeys::webauthn(&conf).expect("valid webauthn configuration"),
+        )))
+        .layer(AddExtensionLayer::new(session_keys.clone()))
+        .layer(crate::trace::request_trace_layer())
+        .layer({
+            // let store = axum_sessions::async_session::MemoryStore::new();
+            let store = crate::sessions::BonsaiDBSessionStore { db: db.clone() };
+
+            // !!! Remember that if site not accessed through HTTPS using public IP,
+            // cookie won't be preserved and you would debug it like an idiot once again
+            SessionLayer::new(store, session_keys.newest().master())
+                .with_secure(true)
+                .with_cookie_name(crate::session_keys::SESSION_COOKIE_NAME)
+                .with_session_ttl(Some(conf.session.ttl()))
+                // sent along with the redirect back from an OIDC provider,
+                // mutations are guarded by csrf middleware
+                .with_same_site_policy(axum_sessions::SameSite::Lax)
+        })
+        .layer(axum::middleware::from_fn_with_state(
+            (session_keys, conf.session.ttl()),
+            crate::session_keys::resign_session_cookie,
+        ))
+}
+
+/// Routes of `router`, recorded for the OpenAPI document to be checked against
+fn registered(conf: &Conf) -> RecordedRouter<AppState> {
+    use crate::routes::*;
+    use axum::handler::Handler;
+
+    let root = routes().root;
+    let ws = routes().ws;
     let routes = routes().api;
 
-    let api_router = Router::new()
-        .route(routes.health_check.get().postfix(), get(health_check))
-        .route(routes.login.post().postfix(), post(login))
-        .route(
-            routes.admin.password.post().postfix(),
-            post(change_password),
+    // cookie authenticated mutations
+    let csrf_protected_router = RecordedRouter::new()
+        .post(routes.admin.password.post().postfix(), change_password)
+        .post(routes.admin.logout.post().postfix(), logout)
+        .delete(
+            routes.articles.by_public_id.pattern().postfix(),
+            delete_article,
+        )
+        .post(routes.admin.articles.post().postfix(), new_article)
+        .post(
+            routes.admin.media.upload.post().postfix(),
+            upload_media.layer(axum::extract::DefaultBodyLimit::max(
+                conf.media.max_size_bytes,
+            )),
+        )
+        .put(routes.admin.articles.put().postfix(), update_article)
+        .post(
+            routes.admin.passkeys.register.start.post().postfix(),
+            passkey_registration_start,
+        )
+        .post(
+            routes.admin.passkeys.register.finish.post().postfix(),
+            passkey_registration_finish,
+        )
+        .post(
+            routes.admin.sessions.revoke.post().postfix(),
+            revoke_session,
+        )
+        .post(
+            routes.admin.sessions.revoke_others.post().postfix(),
+            revoke_other_sessions,
+        )
+        .post(routes.admin.features.set.post().postfix(), set_feature_flag)
+        .route_layer(axum::middleware::from_fn(
+            crate::csrf::reject_cross_site_requests,
+        ));
+
+    let api_router = RecordedRouter::new()
+        .get(routes.health_check.get().postfix(), health_check)
+        .get(routes.health.live.get().postfix(), health_live)
+        .get(routes.health.ready.get().postfix(), health_ready)
+        .get(routes.version.get().postfix(), version)
+        .get(routes.openapi.get().postfix(), serve_openapi)
+        .post(routes.login.post().postfix(), login)
+        .post(
+            routes.login.passkey.start.post().postfix(),
+            passkey_login_start,
+        )
+        .post(
+            routes.login.passkey.finish.post().postfix(),
+            passkey_login_finish,
         )
-        .route(routes.admin.logout.post().postfix(), post(logout))
-        .route(routes.admin.session.get().postfix(), get(admin_session))
-        .route(routes.articles.get().postfix(), get(article_list))
-        .route("/articles/:public_id", get(article_by_public_id))
-        .route("/articles/:public_id", delete(delete_article))
-        .route(routes.admin.articles.post().postfix(), post(new_article))
-        .route("/admin/articles", put(update_article))
-        .route("/static/*path", get(serve_static))
-        // .route("/admin/endpoint_hits", get(endpoint_hits))
-        .route(
-            routes.admin.endpoint_hits.get().postfix(),
-            get(endpoint_hits),
+        .get(routes.login.oidc.get().postfix(), oidc_login)
+        .get(routes.login.oidc.callback.get().postfix(), oidc_callback)
+        .get(routes.admin.session.get().postfix(), admin_session)
+        .get(routes.articles.get().postfix(), article_list)
+        .get(
+            routes.articles.by_public_id.pattern().postfix(),
+            article_by_public_id,
         )
-        .route(
+        .merge(csrf_protected_router)
+        .get(routes.static_files.pattern().postfix(), serve_static)
+        .get(routes.admin.endpoint_hits.get().postfix(), endpoint_hits)
+        .get(
             routes.admin.endpoint_hits.grouped.get().postfix(),
-            get(endpoint_hits_grouped),
+            endpoint_hits_grouped,
         )
-        // .route("/endpoint_hits/frontend", post(frontend_endpoint_hit))
-        .route(
+        .get(routes.admin.audit_log.get().postfix(), audit_log)
+        .get(routes.admin.sessions.get().postfix(), session_list)
+        .get(routes.admin.features.get().postfix(), feature_flags)
+        .get(routes.admin.media.get().postfix(), media_list)
+        .get(routes.media.pattern().postfix(), serve_media)
+        .get(routes.features.get().postfix(), feature_list)
+        .post(
             routes.endpoint_hits.frontend.post().postfix(),
-            post(frontend_endpoint_hit),
+            frontend_endpoint_hit,
         )
-        .route(
+        .get(
             routes.endpoint_hits.github.profile.get().postfix(),
-            get(github_hit),
+            github_hit,
         )
-        .route(
+        .get(
             routes.endpoint_hits.github.wsite.get().postfix(),
-            get(wsite_github_hit),
+            wsite_github_hit,
         );
 
-    let ws_router = Router::new().route("/users_online", get(ws_users_online));
+    let mut ws_router =
+        RecordedRouter::new().get(ws.users_online.get().complete(), ws_users_online);
+    if conf.frontend.dist_path.is_some() {
+        ws_router = ws_router.get(ws.dev_reload.get().complete(), ws_dev_reload);
+    }
+
+    let feeds = root.feeds;
 
-    Router::new()
+    RecordedRouter::new()
         .nest("/api", api_router)
-        .nest("/ws", ws_router)
-        .fallback(fallback)
-        .layer(CompressionLayer::new())
-        .layer(axum::middleware::from_fn(endpoint_hit_middleware))
-        .layer(AddExtensionLayer::new(db.clone()))
-        .layer(AddExtensionLayer::new(conf.clone()))
-        .layer(crate::trace::request_trace_layer())
-        .layer({
-            // let store = axum_sessions::async_session::MemoryStore::new();
-            let store = BonsaiDBSessionStore { db: db.clone() };
-
-            // generate it, and reuse on restarts
-            fn gen_session_secret() -> std::io::Result<String> {
-                fn random_hex_string(n: usize) -> String {
-                    use rand::Rng;
-                    let mut rng = rand::thread_rng();
-                    let random_bytes: Vec<u8> = (0..n).map(|_| rng.gen::<u8>()).collect();
-                    hex::encode(random_bytes)
-                }
+        .merge(ws_router)
+        .get(feeds.rss.get().postfix(), rss_feed)
+        .get(feeds.atom.get().postfix(), atom_feed)
+        .get(feeds.json.get().postfix(), json_feed)
+        .get(root.sitemap.get().postfix(), serve_sitemap)
+        .get(root.robots.get().postfix(), serve_robots)
+        .get(root.metrics.get().postfix(), serve_metrics)
+}
 
-                use std::fs::{self, OpenOptions};
-                use std::io::prelude::*;
-                use std::path::Path;
+/// Method and path of each route `router` registers, paths in axum syntax
+pub fn registered_routes(conf: &Conf) -> Vec<(hyper::Method, String)> {
+    registered(conf).routes
+}
 
-                let directory_path = "secrets";
-                let file_path = "secrets/token.hex";
+/// Router that records the method and path of the routes added through it
+struct RecordedRouter<S> {
+    router: Router<S>,
+    routes: Vec<(hyper::Method, String)>,
+}
 
-                // Create the directory if it doesn't exist
-                if !Path::new(directory_path).exists() {
-                    fs::create_dir_all(directory_path)?;
-                }
+impl<S: Clone + Send + Sync + 'static> RecordedRouter<S> {
+    fn new() -> Self {
+        Self {
+            router: Router::new(),
+            routes: vec![],
+        }
+    }
 
-                // Create or open the file
-                let mut file = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .read(true)
-                    .open(file_path)
-                    .unwrap();
-
-                let contents = {
-                    let mut contents = String::new();
-                    file.read_to_string(&mut contents)?;
-                    contents
-                };
-
-                if contents.is_empty() {
-                    let secret = random_hex_string(64);
-                    file.write(secret.as_bytes()).unwrap();
-                    Ok(secret)
-                } else {
-                    Ok(contents)
-                }
-            }
+    fn on<H, T>(mut self, method: hyper::Method, path: &str, handler: H) -> Self
+    where
+        H: axum::handler::Handler<T, S>,
+        T: 'static,
+    {
+        let filter =
+            axum::routing::MethodFilter::try_from(method.clone()).expect("method to be routable");
+        self.router = self.router.route(path, axum::routing::on(filter, handler));
+        self.routes.push((method, path.to_owned()));
+        self
+    }
 
-            let session_secret = match conf.session_secret.clone() {
-                // in case it's not provided in env var
-                None => gen_session_secret().expect("to generate session secret"),
-                Some(v) => v,
-            };
+    fn get<H: axum::handler::Handler<T, S>, T: 'static>(self, path: &str, handler: H) -> Self {
+        self.on(hyper::Method::GET, path, handler)
+    }
 
-            let decoded =
-                hex::decode(session_secret).expect("Successful HEX Decoding of session secret");
+    fn post<H: axum::handler::Handler<T, S>, T: 'static>(self, path: &str, handler: H) -> Self {
+        self.on(hyper::Method::POST, path, handler)
+    }
 
-            // use rand::Rng;
-            // let mut secret = [0_u8; 128];
-            // rand::thread_rng().fill(&mut secret);
-            // dbg!(hex::encode(secret));
+    fn put<H: axum::handler::Handler<T, S>, T: 'static>(self, path: &str, handler: H) -> Self {
+        self.on(hyper::Method::PUT, path, handler)
+    }
 
-            // !!! Remember that if site not accessed through HTTPS using public IP,
-            // cookie won't be preserved and you would debug it like an idiot once again
-            SessionLayer::new(store, decoded.as_slice()).with_secure(true)
-        })
+    fn delete<H: axum::handler::Handler<T, S>, T: 'static>(self, path: &str, handler: H) -> Self {
+        self.on(hyper::Method::DELETE, path, handler)
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.router = self.router.merge(other.router);
+        self.routes.extend(other.routes);
+        self
+    }
+
+    fn nest(mut self, prefix: &str, other: Self) -> Self {
+        self.router = self.router.nest(prefix, other.router);
+        self.routes.extend(
+            other
+                .routes
+                .into_iter()
+                .map(|(method, path)| (method, format!("{prefix}{path}"))),
+        );
+        self
+    }
+
+    /// Layers the routes added so far, like `Router::route_layer`
+    ///
+    /// In place of a way to reach the router itself, which could add routes
+    /// that go unrecorded.
+    fn route_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<axum::routing::Route> + Clone + Send + 'static,
+        L::Service: tower::Service<hyper::Request<hyper::Body>> + Clone + Send + 'static,
+        <L::Service as tower::Service<hyper::Request<hyper::Body>>>::Response:
+            axum::response::IntoResponse + 'static,
+        <L::Service as tower::Service<hyper::Request<hyper::Body>>>::Error:
+            Into<std::convert::Infallible> + 'static,
+        <L::Service as tower::Service<hyper::Request<hyper::Body>>>::Future: Send + 'static,
+    {
+        self.router = self.router.route_layer(layer);
+        self
+    }
 }
 
 async fn endpoint_hit_middleware<B>(
     h: hyper::HeaderMap,
-    axum::extract::Extension(db): axum::extract::Extension<cozo::DbInstance>,
+    axum::extract::Extension(endpoint_hits): axum::extract::Extension<EndpointHitQueue>,
     axum::extract::Extension(conf): axum::extract::Extension<Conf>,
     // TODO request hangs if this extractor is used
     // session: axum_sessions::extractors::ReadableSession,
@@ -147,6 +272,8 @@ async fn endpoint_hit_middleware<B>(
     let method = request.method().to_string();
 
     let response = next.run(request).await;
+    let root = routes().root;
+    let ws = routes().ws;
     let routes = routes().api;
 
     let skip_endpoint_starts = [
@@ -155,12 +282,19 @@ async fn endpoint_hit_middleware<B>(
         "/_trunk/ws".into(),
         // after upgrade ip defaults to localhost
         // now not worth to bother implementing correctly
-        "/ws/users_online".into(),
+        ws.users_online.get().complete().into(),
+        ws.dev_reload.get().complete().into(),
+        // embedded in articles, like frontend files
+        "/api/media/".into(),
+        // polled by probes
+        "/api/health/".into(),
         routes.admin.endpoint_hits.get().complete().into(),
+        routes.features.get().complete().into(),
         routes.admin.endpoint_hits.grouped.get().complete().into(),
         routes.endpoint_hits.frontend.post().complete().into(),
         routes.endpoint_hits.github.profile.get().complete().into(),
         routes.endpoint_hits.github.wsite.get().complete().into(),
+        root.metrics.get().complete().into(),
     ];
 
     let js_file = endpoint.starts_with("/frontend") && endpoint.ends_with(".js");
@@ -180,78 +314,29 @@ async fn endpoint_hit_middleware<B>(
         let system_time = interfacing::EndpointHit::formatted_now();
 
         let ip = ip_address(&h);
-        let hashed_ip = if conf.env.local() {
-            ip.to_string()
-        } else {
-            interfacing::EndpointHit::hash_ip(ip)
-        };
+        let hashed_ip = hash_ip(ip, &conf);
 
         let status = response.status().as_u16();
 
-        let _result = db::q::put_endpoint_hit(
-            &db,
-            interfacing::EndpointHit {
-                hashed_ip,
-                endpoint,
-                method,
-                status,
-                timestamp: system_time,
-            },
-        )
-        .map_err(|e| tracing::error!("{e:?}"));
+        endpoint_hits.push(interfacing::EndpointHit {
+            hashed_ip,
+            endpoint,
+            method,
+            status,
+            timestamp: system_time,
+        });
     }
 
     response
 }
 
-#[derive(derivative::Derivative, Clone)]
-#[derivative(Debug)]
-struct BonsaiDBSessionStore {
-    #[derivative(Debug = "ignore")]
-    db: cozo::DbInstance,
-}
-
-use crate::db;
-
-#[async_trait]
-impl SessionStore for BonsaiDBSessionStore {
-    async fn load_session(
-        &self,
-        cookie_value: String,
-    ) -> axum_sessions::async_session::Result<Option<Session>> {
-        let id = Session::id_from_cookie_value(&cookie_value)?;
-
-        let session: Option<Session> = db::q::find_session_by_id(&self.db, &id)?
-            .map(|v| serde_json::from_str(&v).ok())
-            .flatten()
-            .and_then(Session::validate);
-
-        Ok(session)
-    }
-
-    async fn store_session(
-        &self,
-        session: Session,
-    ) -> axum_sessions::async_session::Result<Option<String>> {
-        db::q::put_session(&self.db, session.id(), &serde_json::to_string(&session)?)?;
-        session.reset_data_changed();
-        Ok(session.into_cookie_value())
-    }
-
-    async fn destroy_session(&self, session: Session) -> axum_sessions::async_session::Result {
-        db::q::rm_session(&self.db, session.id())?;
-        Ok(())
-    }
-
-    async fn clear_store(&self) -> axum_sessions::async_session::Result {
-        tracing::info!("clear session store");
-        unimplemented!("find out how to clear session storage")
-    }
-}
-
 #[derive(Clone)]
 pub struct AppState {
     pub users_online: UsersOnline,
+    pub shutdown: Shutdown,
+    pub dev_reload: crate::dev_frontend::DevReload,
+    // for requests to the OIDC provider
+    pub oidc_http: reqwest::Client,
 }
 
 pub type Cons = Arc<tokio::sync::Mutex<std::collections::HashMap<std::net::SocketAddr, i32>>>;
@@ -284,53 +369,158 @@ impl UsersOnline {
     }
 }
 
+type Server = std::pin::Pin<Box<dyn std::future::Future<Output = std::io::Result<()>> + Send>>;
+
 pub struct Application {
     port: u16,
-    server: std::pin::Pin<Box<dyn std::future::Future<Output = hyper::Result<()>> + Send>>,
+    server: Server,
     host: String,
     db: cozo::DbInstance,
+    shutdown: Shutdown,
+    endpoint_hit_writer: EndpointHitWriter,
 }
 
 impl Application {
-    pub async fn build(conf: Conf) -> Self {
+    pub async fn build(conf: Conf) -> anyhow::Result<Self> {
         let address = format!("{}:{}", conf.host, conf.port);
         let listener = std::net::TcpListener::bind(&address).unwrap();
-        tracing::info!("Listening on http://{}", address);
         let host = conf.host.clone();
         let port = listener.local_addr().unwrap().port();
 
-        // let db = &DbInstance::new("sqlite", "testing.db", Default::default()).unwrap();
-        let db = conf.db.db_instance();
+        let db = conf.db.db_instance()?;
         let db = crate::db::start_db(db);
 
+        let shutdown = Shutdown::default();
+
+        tokio::spawn(crate::sessions::purge_expired_sessions(
+            db.clone(),
+            conf.session.purge_period(),
+            shutdown.clone(),
+        ));
+
+        let dev_reload = crate::dev_frontend::DevReload::default();
+        match &conf.frontend.dist_path {
+            Some(dist) => {
+                tracing::info!("Serving frontend from {}", dist.display());
+                tokio::spawn(crate::dev_frontend::watch(
+                    dist.clone(),
+                    dev_reload.clone(),
+                    shutdown.clone(),
+                ));
+            }
+            None => anyhow::ensure!(
+                cfg!(feature = "embed-frontend"),
+                "frontend is not embedded, set frontend.dist_path"
+            ),
+        }
+
         let app_state = AppState {
             users_online: UsersOnline::new(),
+            shutdown: shutdown.clone(),
+            dev_reload,
+            oidc_http: crate::oidc::http_client(),
         };
 
-        return Self {
-            server: Box::pin(run(conf, listener, app_state, db.clone())),
+        let session_keys =
+            crate::session_keys::SessionKeys::load(&conf).context("failed to load session keys")?;
+        let (endpoint_hits, endpoint_hit_writer) = EndpointHitQueue::start(db.clone());
+
+        let make_service = router(conf.clone(), db.clone(), endpoint_hits, session_keys)
+            .with_state(app_state)
+            .into_make_service_with_connect_info::<UserConnectInfo>();
+
+        let server: Server = match &conf.tls {
+            None => {
+                tracing::info!("Listening on http://{}", address);
+                let shutdown = shutdown.clone();
+                Box::pin(async move {
+                    axum::Server::from_tcp(listener)
+                        .map_err(std::io::Error::other)?
+                        .serve(make_service)
+                        .with_graceful_shutdown(async move { shutdown.signaled().await })
+                        .await
+                        .map_err(std::io::Error::other)
+                })
+            }
+            Some(tls_conf) => {
+                let rustls_config = crate::tls::rustls_config(tls_conf).await?;
+                #[cfg(unix)]
+                tokio::spawn(crate::tls::reload_on_sighup(
+                    rustls_config.clone(),
+                    tls_conf.clone(),
+                ));
+
+                tracing::info!("Listening on https://{}", address);
+                let handle = axum_server::Handle::new();
+                tokio::spawn({
+                    let (handle, shutdown) = (handle.clone(), shutdown.clone());
+                    async move {
+                        shutdown.signaled().await;
+                        // deadline is kept by shutdown::drain
+                        handle.graceful_shutdown(None);
+                    }
+                });
+                let https = axum_server::from_tcp_rustls(listener, rustls_config)
+                    .handle(handle)
+                    .serve(make_service);
+
+                match tls_conf.redirect_http_port {
+                    None => Box::pin(https),
+                    Some(http_port) => {
+                        let http_address = format!("{}:{}", conf.host, http_port);
+                        let http_listener = std::net::TcpListener::bind(&http_address)
+                            .with_context(|| format!("failed to bind {http_address}"))?;
+                        tracing::info!("Redirecting http://{} to HTTPS", http_address);
+
+                        let redirect =
+                            crate::tls::serve_redirect(http_listener, port, shutdown.clone());
+                        Box::pin(async move {
+                            futures_util::try_join!(https, redirect)?;
+                            Ok(())
+                        })
+                    }
+                }
+            }
+        };
+
+        let server = Box::pin(crate::shutdown::drain(
+            server,
+            shutdown.clone(),
+            conf.shutdown.drain_timeout(),
+        ));
+
+        Ok(Self {
+            server,
             port,
             host,
             db,
-        };
+            shutdown,
+            endpoint_hit_writer,
+        })
+    }
 
-        pub fn run(
-            conf: Conf,
-            listener: std::net::TcpListener,
-            app_state: AppState,
-            db: cozo::DbInstance,
-        ) -> impl std::future::Future<Output = hyper::Result<()>> {
-            axum::Server::from_tcp(listener).unwrap().serve(
-                router(conf, db)
-                    .with_state(app_state)
-                    .into_make_service_with_connect_info::<UserConnectInfo>(),
-            )
+    // needs to consume to produce 1 server max, and because I don't know better
+    pub fn server(self) -> impl std::future::Future<Output = std::io::Result<()>> + Send {
+        let Self {
+            server,
+            db,
+            endpoint_hit_writer,
+            ..
+        } = self;
+
+        async move {
+            let result = server.await;
+            endpoint_hit_writer.finish().await;
+            // the last handle, storages flush on drop
+            drop(db);
+            tracing::info!("Server stopped");
+            result
         }
     }
 
-    // needs to consume to produce 1 server max, and because I don't know better
-    pub fn server(self) -> impl std::future::Future<Output = hyper::Result<()>> + Send {
-        self.server
+    /// Stops the server once triggered, as SIGTERM and SIGINT do
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.shutdown.clone()
     }
 
     pub fn db(&self) -> cozo::DbInstance {
@@ -359,6 +549,13 @@ impl axum::extract::connect_info::Connected<&hyper::server::conn::AddrStream> fo
     }
 }
 
+// as provided by axum_server when serving TLS
+impl axum::extract::connect_info::Connected<std::net::SocketAddr> for UserConnectInfo {
+    fn connect_info(remote_addr: std::net::SocketAddr) -> Self {
+        Self { remote_addr }
+    }
+}
+
 fn get_origin(h: &hyper::HeaderMap) -> Option<std::net::IpAddr> {
     h.get("origin")
         .map(|v| v.to_str().ok())
@@ -383,7 +580,7 @@ fn get_referer(h: &hyper::HeaderMap) -> Option<std::net::IpAddr> {
         .flatten()
 }
 
-fn get_x_forwarded_for(h: &hyper::HeaderMap) -> Option<std::net::IpAddr> {
+pub(crate) fn get_x_forwarded_for(h: &hyper::HeaderMap) -> Option<std::net::IpAddr> {
     h.get("x-forwarded-for")
         .map(|v| v.to_str().ok())
         .flatten()
@@ -404,3 +601,50 @@ pub fn ip_address(h: &hyper::HeaderMap) -> std::net::IpAddr {
             "127.0.0.1".parse().unwrap()
         })
 }
+
+// keep raw IPs only locally
+pub fn hash_ip(ip: std::net::IpAddr, conf: &Conf) -> String {
+    if conf.env.local() {
+        ip.to_string()
+    } else {
+        interfacing::EndpointHit::hash_ip(ip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::{Env, EnvConf};
+
+    #[test]
+    fn declared_routes_registered() {
+        let mut env_conf = EnvConf::test_default();
+        // ws.dev_reload is registered only then
+        env_conf.frontend.dist_path = Some("dist".into());
+        let conf = Conf::new(Env::Local, env_conf);
+        let registered = registered_routes(&conf);
+        let is_registered = |method: &str, pattern: &str| {
+            registered
+                .iter()
+                .any(|(m, path)| m.as_str() == method && path == pattern)
+        };
+
+        for route in routes().declared() {
+            if route.page.is_some() {
+                // rendered by the frontend behind the fallback
+                assert!(
+                    !registered.iter().any(|(_, path)| *path == route.pattern),
+                    "page {} is shadowed by a route",
+                    route.pattern
+                );
+            } else {
+                assert!(
+                    is_registered(route.method, &route.pattern),
+                    "{} {} has no handler",
+                    route.method,
+                    route.pattern
+                );
+            }
+        }
+    }
+}
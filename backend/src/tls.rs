@@ -0,0 +1,127 @@
+// Native TLS serving
+//
+// HTTP/2 is negotiated through ALPN. Certificates are reloaded on SIGHUP,
+// so renewed ones are picked up without a restart.
+//
+
+use crate::{conf::TlsConf, shutdown::Shutdown};
+use anyhow::Context;
+use axum::{
+    http::{header, StatusCode, Uri},
+    response::{IntoResponse, Redirect},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+
+pub async fn rustls_config(conf: &TlsConf) -> anyhow::Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(&conf.cert_path, &conf.key_path)
+        .await
+        .with_context(|| {
+            format!(
+                "failed to load TLS certificate {:?} and key {:?}",
+                conf.cert_path, conf.key_path
+            )
+        })
+}
+
+#[cfg(unix)]
+pub async fn reload_on_sighup(config: RustlsConfig, conf: TlsConf) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            tracing::error!("failed to listen for SIGHUP: {e}");
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        // a failed reload keeps the current certificates
+        match config
+            .reload_from_pem_file(&conf.cert_path, &conf.key_path)
+            .await
+        {
+            Ok(()) => tracing::info!("TLS certificates reloaded"),
+            Err(e) => tracing::error!("failed to reload TLS certificates: {e}"),
+        }
+    }
+}
+
+/// Router of the plain HTTP listener, redirecting everything to HTTPS
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |h: hyper::HeaderMap, uri: Uri| async move {
+        let host = h.get(header::HOST).and_then(|v| v.to_str().ok());
+
+        match host.and_then(|host| https_url(host, &uri, https_port)) {
+            Some(url) => Redirect::permanent(&url).into_response(),
+            None => StatusCode::BAD_REQUEST.into_response(),
+        }
+    })
+}
+
+fn https_url(host: &str, uri: &Uri, https_port: u16) -> Option<String> {
+    let authority: axum::http::uri::Authority = host.parse().ok()?;
+    let path = uri.path_and_query().map_or("/", |v| v.as_str());
+
+    Some(match https_port {
+        443 => format!("https://{}{path}", authority.host()),
+        port => format!("https://{}:{port}{path}", authority.host()),
+    })
+}
+
+pub async fn serve_redirect(
+    listener: std::net::TcpListener,
+    https_port: u16,
+    shutdown: Shutdown,
+) -> std::io::Result<()> {
+    axum::Server::from_tcp(listener)
+        .map_err(std::io::Error::other)?
+        .serve(redirect_router(https_port).into_make_service())
+        .with_graceful_shutdown(async move { shutdown.signaled().await })
+        .await
+        .map_err(std::io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirect_urls() {
+        let uri: Uri = "/articles?page=2".parse().unwrap();
+
+        assert_eq!(
+            https_url("phantie.site", &uri, 443).as_deref(),
+            Some("https://phantie.site/articles?page=2")
+        );
+        assert_eq!(
+            https_url("localhost:8080", &uri, 8443).as_deref(),
+            Some("https://localhost:8443/articles?page=2")
+        );
+        assert_eq!(
+            https_url("localhost", &"/".parse().unwrap(), 443).as_deref(),
+            Some("https://localhost/")
+        );
+        assert_eq!(https_url("not a host", &uri, 443), None);
+    }
+
+    #[tokio::test]
+    async fn certificates_loaded() {
+        let conf = TlsConf {
+            cert_path: "tests/fixtures/tls_cert.pem".into(),
+            key_path: "tests/fixtures/tls_key.pem".into(),
+            redirect_http_port: None,
+        };
+        let config = rustls_config(&conf).await.unwrap();
+
+        let alpn = &config.get_inner().alpn_protocols;
+        assert!(alpn.contains(&b"h2".to_vec()));
+
+        let missing = TlsConf {
+            cert_path: "tests/fixtures/missing.pem".into(),
+            ..conf
+        };
+        assert!(rustls_config(&missing).await.is_err());
+    }
+}
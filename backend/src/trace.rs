@@ -5,6 +5,7 @@ use tracing::{subscriber::set_global_default, Subscriber};
 use tracing_log::LogTracer;
 use tracing_subscriber::{
     filter,
+    fmt::writer::BoxMakeWriter,
     layer::{Layer, SubscriberExt},
     EnvFilter, Registry,
 };
@@ -13,6 +14,7 @@ pub struct TracingSubscriber {
     crate_level: tracing::Level,
     rust_log_fallback: String,
     pretty: bool,
+    stderr: bool,
 }
 
 impl Default for TracingSubscriber {
@@ -21,6 +23,7 @@ impl Default for TracingSubscriber {
             crate_level: tracing::Level::DEBUG,
             rust_log_fallback: "debug".into(),
             pretty: false,
+            stderr: false,
         }
     }
 }
@@ -48,6 +51,13 @@ impl TracingSubscriber {
         self
     }
 
+    // keeps stdout to command output
+    #[allow(unused)]
+    pub fn stderr(mut self, value: bool) -> Self {
+        self.stderr = value;
+        self
+    }
+
     pub fn set_global_default(self) {
         LogTracer::init().expect("Failed to set logger");
         set_global_default(self.build()).expect("Failed to set subscriber");
@@ -65,12 +75,19 @@ impl TracingSubscriber {
             .with_target("hyper", tracing::Level::INFO)
             .with_default(tracing::Level::TRACE);
 
+        let writer = if self.stderr {
+            BoxMakeWriter::new(std::io::stderr)
+        } else {
+            BoxMakeWriter::new(std::io::stdout)
+        };
+
         // ugly
         if self.pretty {
             Box::new(
                 Registry::default().with(
                     tracing_subscriber::fmt::layer()
                         .pretty()
+                        .with_writer(writer)
                         .with_filter(env_filter)
                         .with_filter(target_filter),
                 ),
@@ -79,6 +96,7 @@ impl TracingSubscriber {
             Box::new(
                 Registry::default().with(
                     tracing_subscriber::fmt::layer()
+                        .with_writer(writer)
                         .with_filter(env_filter)
                         .with_filter(target_filter),
                 ),
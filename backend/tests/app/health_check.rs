@@ -1,4 +1,4 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, spawn_app_with};
 use hyper::StatusCode;
 
 use static_routes::*;
@@ -16,3 +16,65 @@ async fn health_check_works() {
     assert_eq!(StatusCode::OK, response.status());
     assert_eq!(Some(0), response.content_length());
 }
+
+#[tokio::test]
+async fn liveness_and_readiness() {
+    let app = spawn_app().await;
+
+    let live = app
+        .get(routes().api.health.live)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::OK, live.status());
+
+    let ready = app
+        .get(routes().api.health.ready)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::OK, ready.status());
+    let readiness = ready.json::<interfacing::Readiness>().await.unwrap();
+    assert!(readiness.ready);
+    assert_eq!(readiness.checks.len(), 3);
+}
+
+#[tokio::test]
+async fn readiness_failures_not_detailed() {
+    let missing = "/nonexistent/static/root";
+    let app = spawn_app_with(|env_conf| env_conf.static_files.root = missing.into()).await;
+
+    let ready = app
+        .get(routes().api.health.ready)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::SERVICE_UNAVAILABLE, ready.status());
+
+    let body = ready.text().await.unwrap();
+    assert!(!body.contains(missing));
+    let readiness = serde_json::from_str::<interfacing::Readiness>(&body).unwrap();
+    let static_files = readiness
+        .checks
+        .iter()
+        .find(|check| check.name == "static_files")
+        .unwrap();
+    assert!(!static_files.ok);
+}
+
+#[tokio::test]
+async fn version_reports_build() {
+    let app = spawn_app().await;
+
+    let response = app
+        .get(routes().api.version)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let build = response.json::<interfacing::BuildInfo>().await.unwrap();
+    assert_eq!(build.env, "local");
+    assert!(!build.git_sha.is_empty());
+    assert!(build.versions.contains_key("backend"));
+}
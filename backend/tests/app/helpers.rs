@@ -14,13 +14,18 @@ static TRACING: Lazy<()> = Lazy::new(|| {
 });
 
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| ()).await
+}
+
+pub async fn spawn_app_with(configure: impl FnOnce(&mut conf::EnvConf)) -> TestApp {
     Lazy::force(&TRACING);
 
-    let env_conf = conf::EnvConf::test_default();
+    let mut env_conf = conf::EnvConf::test_default();
+    configure(&mut env_conf);
     let env = conf::Env::Local;
     let conf = conf::Conf::new(env, env_conf);
 
-    let application = Application::build(conf).await;
+    let application = Application::build(conf).await.expect("app to build");
 
     let host = application.host();
     let port = application.port();
@@ -28,7 +33,8 @@ pub async fn spawn_app() -> TestApp {
     let address = format!("http://{}:{}", host, port);
 
     let db = application.db();
-    let _ = tokio::spawn(application.server());
+    let shutdown = application.shutdown_handle();
+    let server = tokio::spawn(application.server());
 
     let test_user = TestUser::generate();
     test_user.replace(db.clone()).await.unwrap();
@@ -45,6 +51,8 @@ pub async fn spawn_app() -> TestApp {
         test_user,
         api_client,
         db,
+        shutdown,
+        server,
     }
 }
 
@@ -54,6 +62,8 @@ pub struct TestApp {
     pub test_user: TestUser,
     pub api_client: reqwest::Client,
     pub db: cozo::DbInstance,
+    pub shutdown: backend::shutdown::Shutdown,
+    pub server: tokio::task::JoinHandle<std::io::Result<()>>,
 }
 
 impl TestApp {
@@ -126,6 +136,54 @@ impl TestApp {
         self.get_change_password().await.text().await.unwrap()
     }
 
+    pub async fn add_user(&self) -> TestUser {
+        let user = TestUser::generate();
+        user.replace(self.db.clone()).await.unwrap();
+        user
+    }
+
+    pub async fn log_in(&self, user: &TestUser) -> LoggedIn {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let login = routes().api.login;
+        let response = client
+            .post(login.post().with_base(&self.address).complete())
+            .json(&serde_json::json!({
+                "username": &user.username,
+                "password": &user.password
+            }))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(StatusCode::OK, response.status());
+
+        let cookie = response
+            .headers()
+            .get(hyper::header::SET_COOKIE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .expect("session cookie to be set")
+            .to_owned();
+
+        let mut logged_in = LoggedIn {
+            address: self.address.clone(),
+            client,
+            cookie,
+            csrf_token: String::new(),
+        };
+        let session: interfacing::AdminSession = logged_in
+            .get_admin_session()
+            .await
+            .json()
+            .await
+            .expect("admin session");
+        logged_in.csrf_token = session.csrf_token;
+
+        logged_in
+    }
+
     pub async fn post_logout(&self) -> reqwest::Response {
         self.post(routes().api.admin.logout)
             .send()
@@ -134,6 +192,40 @@ impl TestApp {
     }
 }
 
+/// A logged in client
+///
+/// The session cookie is Secure, so the cookie store of a client talking
+/// plain http wouldn't send it back. It's kept and sent by hand instead.
+pub struct LoggedIn {
+    pub address: String,
+    pub client: reqwest::Client,
+    pub cookie: String,
+    pub csrf_token: String,
+}
+
+impl LoggedIn {
+    pub fn get(&self, static_path: impl Get) -> RequestBuilder {
+        self.client
+            .get(static_path.get().with_base(&self.address).complete())
+            .header(hyper::header::COOKIE, &self.cookie)
+    }
+
+    pub fn post(&self, static_path: impl Post) -> RequestBuilder {
+        self.client
+            .post(static_path.post().with_base(&self.address).complete())
+            .header(hyper::header::COOKIE, &self.cookie)
+            .header("sec-fetch-site", "same-origin")
+            .header(interfacing::CSRF_TOKEN_HEADER, &self.csrf_token)
+    }
+
+    pub async fn get_admin_session(&self) -> reqwest::Response {
+        self.get(routes().api.admin.session)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+}
+
 pub struct TestUser {
     pub username: String,
     pub password: String,
@@ -1,4 +1,4 @@
-use crate::helpers::{assert_is_redirect_to, spawn_app};
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
 #[allow(unused_imports)]
 use hyper::StatusCode;
 
@@ -27,3 +27,75 @@ async fn redirect_to_admin_dashboard_after_login_success() {
     let html_page = app.get_admin_dashboard_html().await;
     assert!(html_page.contains(&format!("Welcome {}", app.test_user.username)));
 }
+
+/// Registers a passkey of a software authenticator for `username`
+fn register_passkey(app: &TestApp, username: &str) {
+    use backend::passkeys;
+    use webauthn_authenticator_rs::{softpasskey::SoftPasskey, WebauthnAuthenticator};
+
+    let conf = backend::conf::Conf::new(
+        backend::conf::Env::Local,
+        backend::conf::EnvConf::test_default(),
+    );
+    let webauthn = passkeys::webauthn(&conf).unwrap();
+    let origin = url::Url::parse(&conf.webauthn.rp_origin).unwrap();
+
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let (challenge, state) = webauthn
+        .start_passkey_registration(passkeys::user_unique_id(username), username, username, None)
+        .unwrap();
+    let credential = authenticator.do_registration(origin, challenge).unwrap();
+    let passkey = webauthn
+        .finish_passkey_registration(&credential, &state)
+        .unwrap();
+
+    let stored = passkeys::to_stored(username, "laptop", &passkey).unwrap();
+    backend::db::q::put_passkey(&app.db, stored).unwrap();
+}
+
+/// Keys and value types of a JSON value, array lengths included
+fn shape(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| (key.clone(), shape(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        Value::Array(array) => array.iter().map(shape).collect(),
+        Value::Null => "null".into(),
+        Value::Bool(_) => "bool".into(),
+        Value::Number(_) => "number".into(),
+        Value::String(_) => "string".into(),
+    }
+}
+
+#[tokio::test]
+async fn passkey_login_start_same_for_all_users() {
+    let app = spawn_app().await;
+    let with_passkey = app.add_user().await;
+    register_passkey(&app, &with_passkey.username);
+
+    let mut responses = vec![];
+    // test_user has no passkeys
+    for username in [
+        with_passkey.username.clone(),
+        app.test_user.username.clone(),
+        "unknown".to_owned(),
+    ] {
+        let response = app
+            .post(routes().api.login.passkey.start)
+            .json(&serde_json::json!({ "username": username }))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap();
+        responses.push((status, shape(&body)));
+    }
+
+    assert_eq!(responses[0].0, StatusCode::OK);
+    assert_eq!(responses[0], responses[1]);
+    assert_eq!(responses[0], responses[2]);
+}
@@ -3,5 +3,8 @@ mod change_password;
 mod health_check;
 mod helpers;
 mod login;
+mod metrics;
+mod sessions;
+mod shutdown;
 
 mod frontend;
@@ -0,0 +1,58 @@
+use crate::helpers::{spawn_app, spawn_app_with};
+use hyper::StatusCode;
+
+use static_routes::*;
+
+#[tokio::test]
+async fn metrics_count_requests() {
+    let app = spawn_app_with(|conf| {
+        conf.metrics.allowed_ips = vec!["127.0.0.1".parse().unwrap()];
+    })
+    .await;
+
+    app.get(routes().api.health_check)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let response = app
+        .get(routes().root.metrics)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(StatusCode::OK, response.status());
+    let text = response.text().await.unwrap();
+    assert!(text.contains("http_requests_total"));
+    assert!(text.contains("ws_connections"));
+}
+
+#[tokio::test]
+async fn metrics_closed_unless_configured() {
+    let app = spawn_app().await;
+
+    let response = app
+        .get(routes().root.metrics)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+}
+
+#[tokio::test]
+async fn spoofed_forwarded_address_rejected() {
+    // the test client connects from 127.0.0.1, which is not a trusted proxy
+    let app = spawn_app_with(|conf| {
+        conf.metrics.allowed_ips = vec!["203.0.113.7".parse().unwrap()];
+    })
+    .await;
+
+    let response = app
+        .get(routes().root.metrics)
+        .header("x-forwarded-for", "203.0.113.7")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+}
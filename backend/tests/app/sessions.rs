@@ -0,0 +1,128 @@
+use crate::helpers::{spawn_app, LoggedIn};
+use hyper::StatusCode;
+
+use static_routes::*;
+
+async fn session_list(logged_in: &LoggedIn) -> Vec<interfacing::SessionInfo> {
+    logged_in
+        .get(routes().api.admin.sessions)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .expect("session list")
+}
+
+async fn current_id(logged_in: &LoggedIn) -> String {
+    session_list(logged_in)
+        .await
+        .into_iter()
+        .find(|info| info.current)
+        .expect("current session to be listed")
+        .id
+}
+
+async fn post_revoke(logged_in: &LoggedIn, id: &str) -> reqwest::Response {
+    logged_in
+        .post(routes().api.admin.sessions.revoke)
+        .json(&interfacing::RevokeSession { id: id.to_owned() })
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn own_session_revoked() {
+    let app = spawn_app().await;
+    let laptop = app.log_in(&app.test_user).await;
+    let phone = app.log_in(&app.test_user).await;
+    let phone_id = current_id(&phone).await;
+
+    let response = post_revoke(&laptop, &phone_id).await;
+
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        phone.get_admin_session().await.status()
+    );
+    assert_eq!(StatusCode::OK, laptop.get_admin_session().await.status());
+    let entries = backend::db::q::find_audit_log_entries(&app.db).unwrap();
+    assert!(entries
+        .iter()
+        .any(|entry| entry.action == "session_revoke" && entry.target_id == phone_id));
+}
+
+#[tokio::test]
+async fn current_session_not_revoked() {
+    let app = spawn_app().await;
+    let laptop = app.log_in(&app.test_user).await;
+
+    let response = post_revoke(&laptop, &current_id(&laptop).await).await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    assert_eq!(StatusCode::OK, laptop.get_admin_session().await.status());
+}
+
+#[tokio::test]
+async fn others_session_not_found() {
+    let app = spawn_app().await;
+    let laptop = app.log_in(&app.test_user).await;
+    let other_user = app.add_user().await;
+    let other = app.log_in(&other_user).await;
+
+    let response = post_revoke(&laptop, &current_id(&other).await).await;
+
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+    assert_eq!(StatusCode::OK, other.get_admin_session().await.status());
+}
+
+#[tokio::test]
+async fn other_sessions_revoked() {
+    let app = spawn_app().await;
+    let laptop = app.log_in(&app.test_user).await;
+    let phone = app.log_in(&app.test_user).await;
+    let tablet = app.log_in(&app.test_user).await;
+
+    let response = laptop
+        .post(routes().api.admin.sessions.revoke_others)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(StatusCode::OK, laptop.get_admin_session().await.status());
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        phone.get_admin_session().await.status()
+    );
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        tablet.get_admin_session().await.status()
+    );
+    let laptop_id = current_id(&laptop).await;
+    assert_eq!(session_list(&laptop).await.len(), 1);
+    let entries = backend::db::q::find_audit_log_entries(&app.db).unwrap();
+    assert!(entries
+        .iter()
+        .any(|entry| entry.action == "session_revoke_others" && entry.target_id == laptop_id));
+}
+
+#[tokio::test]
+async fn last_seen_advanced_by_reads() {
+    let app = spawn_app().await;
+    let laptop = app.log_in(&app.test_user).await;
+    let id = current_id(&laptop).await;
+    // as if the session was last used a while ago
+    let long_ago = "2000-01-01T00:00:00Z";
+    backend::db::q::touch_session(&app.db, &id, long_ago, "9999-12-31T00:00:00Z").unwrap();
+
+    assert_eq!(StatusCode::OK, laptop.get_admin_session().await.status());
+
+    let info = backend::db::q::find_sessions_by_username(&app.db, &app.test_user.username)
+        .unwrap()
+        .into_iter()
+        .find(|info| info.id == id)
+        .expect("session to be stored");
+    assert!(info.last_seen_at.as_str() > long_ago);
+}
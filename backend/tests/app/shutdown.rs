@@ -0,0 +1,28 @@
+use crate::helpers::spawn_app;
+use hyper::StatusCode;
+
+use static_routes::*;
+
+#[tokio::test]
+async fn shutdown_stops_server() {
+    let app = spawn_app().await;
+
+    let response = app
+        .get(routes().api.health_check)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let after_shutdown = app.get(routes().api.health_check);
+    app.shutdown.trigger();
+
+    let result = tokio::time::timeout(std::time::Duration::from_secs(10), app.server)
+        .await
+        .expect("server to stop before the drain deadline")
+        .expect("server task not to panic");
+    assert!(result.is_ok());
+
+    // no longer accepting connections
+    assert!(after_shutdown.send().await.is_err());
+}
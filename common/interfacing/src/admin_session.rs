@@ -1,6 +1,11 @@
 use crate::imports::*;
 
+/// Header carrying the session's CSRF token on cookie authenticated mutations
+pub static CSRF_TOKEN_HEADER: &str = "x-csrf-token";
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct AdminSession {
     pub username: String,
+    pub csrf_token: String,
 }
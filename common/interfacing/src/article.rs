@@ -1,14 +1,19 @@
 use crate::imports::*;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct Article {
     pub title: String,
     pub public_id: String,
     pub markdown: String,
     pub draft: bool,
+    // lowercase, each published article is also in the feed of its tags
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct ArticleWithId {
     pub id: String,
     pub body: Article,
@@ -22,6 +27,18 @@ impl Article {
     pub fn body_mut(&mut self) -> &mut Article {
         self
     }
+
+    /// Trimmed, lowercase, sorted and deduplicated, empty ones dropped
+    pub fn normalized_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let mut tags: Vec<String> = tags
+            .into_iter()
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
 }
 
 impl ArticleWithId {
@@ -0,0 +1,44 @@
+use crate::imports::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct AuditLogEntry {
+    pub actor: String,
+    pub action: String,
+    pub target_id: String,
+    pub timestamp: String,
+    pub hashed_ip: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl AuditLogEntry {
+    /// None for a malformed row, which is still listed with its raw timestamp
+    pub fn timestamp(&self) -> Option<std::time::SystemTime> {
+        humantime::parse_rfc3339(&self.timestamp).ok()
+    }
+}
+
+// fields left empty match any entry
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct AuditLogFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target_id: Option<String>,
+}
+
+impl AuditLogFilter {
+    pub fn matches(&self, entry: &AuditLogEntry) -> bool {
+        fn field_matches(filter: &Option<String>, value: &str) -> bool {
+            match filter.as_deref() {
+                None | Some("") => true,
+                Some(filter) => value == filter,
+            }
+        }
+
+        field_matches(&self.actor, &entry.actor)
+            && field_matches(&self.action, &entry.action)
+            && field_matches(&self.target_id, &entry.target_id)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::imports::*;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct EndpointHit {
     pub hashed_ip: String,
     pub endpoint: String,
@@ -15,8 +16,7 @@ impl EndpointHit {
     }
 
     pub fn formatted_now() -> String {
-        let system_time = std::time::SystemTime::now();
-        humantime::format_rfc3339(system_time).to_string()
+        crate::formatted_now()
     }
 
     pub fn hash_ip(ip: std::net::IpAddr) -> String {
@@ -30,6 +30,7 @@ impl EndpointHit {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct FrontendEndpointHit {
     pub endpoint: String,
     pub status: u16,
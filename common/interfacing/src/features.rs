@@ -0,0 +1,39 @@
+use crate::imports::*;
+use std::collections::BTreeMap;
+
+// flags the frontend and backend both check
+pub static SNAKE_GAME_FEATURE: &str = "snake_game";
+pub static USERS_ONLINE_FEATURE: &str = "users_online";
+// reported by the backend when OIDC is configured, not configurable itself
+pub static OIDC_LOGIN_FEATURE: &str = "oidc_login";
+
+/// Value of a feature flag
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum FeatureFlag {
+    Enabled(bool),
+    // enabled for this percentage of visitors
+    Percentage(u8),
+}
+
+/// Feature flags evaluated for the requesting visitor
+pub type Features = BTreeMap<String, bool>;
+
+/// Feature flag as listed on the admin dashboard
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct FeatureFlagInfo {
+    pub name: String,
+    pub configured: FeatureFlag,
+    // takes precedence over the configured value
+    pub overridden: Option<FeatureFlag>,
+}
+
+/// Sets the runtime override of a flag, or clears it when `value` is None
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct FeatureFlagOverride {
+    pub name: String,
+    pub value: Option<FeatureFlag>,
+}
@@ -0,0 +1,39 @@
+use crate::imports::*;
+use std::collections::BTreeMap;
+
+/// Whether the backend is able to serve requests, by each of the checks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Readiness {
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReadinessCheck {
+    pub name: String,
+    pub ok: bool,
+}
+
+impl Readiness {
+    pub fn new(checks: Vec<ReadinessCheck>) -> Self {
+        Self {
+            ready: checks.iter().all(|check| check.ok),
+            checks,
+        }
+    }
+}
+
+/// Build of the running backend
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct BuildInfo {
+    // "unknown" unless GIT_SHA was set when building
+    pub git_sha: String,
+    // RFC 3339, of the executable, or "unknown"
+    pub build_time: String,
+    pub env: String,
+    // by crate name
+    pub versions: BTreeMap<String, String>,
+}
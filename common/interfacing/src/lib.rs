@@ -2,12 +2,38 @@ mod imports;
 
 mod admin_session;
 mod article;
+mod audit_log;
 mod endpoint_hits;
+mod features;
+mod health;
 mod login_form;
+mod markdown;
+mod media;
+mod passkeys;
+mod prefetched;
 mod password_change_form;
+mod sessions;
+mod static_articles;
 
-pub use admin_session::AdminSession;
+pub use admin_session::{AdminSession, CSRF_TOKEN_HEADER};
 pub use article::{Article, ArticleWithId};
+pub use audit_log::{AuditLogEntry, AuditLogFilter};
 pub use endpoint_hits::{EndpointHit, FrontendEndpointHit};
+pub use features::{
+    FeatureFlag, FeatureFlagInfo, FeatureFlagOverride, Features, OIDC_LOGIN_FEATURE,
+    SNAKE_GAME_FEATURE, USERS_ONLINE_FEATURE,
+};
+pub use health::{BuildInfo, Readiness, ReadinessCheck};
 pub use login_form::LoginForm;
+pub use markdown::{markdown_excerpt, markdown_first_image, markdown_to_html};
+pub use media::MediaFile;
+pub use passkeys::{PasskeyLoginStart, PasskeyRegistrationStart};
 pub use password_change_form::PasswordChangeForm;
+pub use prefetched::{Prefetched, PREFETCHED_ELEMENT_ID};
+pub use sessions::{RevokeSession, SessionInfo};
+pub use static_articles::{static_articles, StaticArticle, StaticArticles};
+
+/// Current time in RFC 3339, as timestamps are stored and compared
+pub fn formatted_now() -> String {
+    humantime::format_rfc3339(std::time::SystemTime::now()).to_string()
+}
@@ -1,8 +1,10 @@
 use crate::imports::*;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct LoginForm {
     pub username: String,
     #[serde(serialize_with = "expose_secret_string")]
+    #[cfg_attr(feature = "openapi", schemars(with = "String"))]
     pub password: SecretString,
 }
@@ -0,0 +1,100 @@
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+
+fn parser(markdown: &str) -> Parser<'_, '_> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    Parser::new_ext(markdown, options)
+}
+
+/// Renders article markdown, shared so the frontend and feeds agree
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, parser(markdown));
+    html_output
+}
+
+/// Plain text of the leading paragraphs, cut at a word within max_chars
+pub fn markdown_excerpt(markdown: &str, max_chars: usize) -> String {
+    let mut text = String::new();
+    // headings, code blocks and image descriptions don't read as prose
+    let mut skipped = 0;
+
+    for event in parser(markdown) {
+        match event {
+            Event::Start(Tag::Heading(..) | Tag::CodeBlock(_) | Tag::Image(..)) => skipped += 1,
+            Event::End(Tag::Heading(..) | Tag::CodeBlock(_) | Tag::Image(..)) => skipped -= 1,
+            _ if skipped > 0 => (),
+            Event::Text(part) | Event::Code(part) => text.push_str(&part),
+            Event::End(Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..)) => (),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => (),
+        }
+        if text.chars().count() > max_chars {
+            break;
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(' ') {
+        Some(end) => &cut[..end],
+        None => &cut,
+    };
+    format!(
+        "{}…",
+        cut.trim_end_matches(|c: char| c.is_ascii_punctuation())
+    )
+}
+
+/// Link of the first image, previews show it
+pub fn markdown_first_image(markdown: &str) -> Option<String> {
+    parser(markdown).find_map(|event| match event {
+        Event::Start(Tag::Image(_, url, _)) => Some(url.to_string()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excerpt_within_limit() {
+        assert_eq!(markdown_excerpt("one two three", 13), "one two three");
+        assert_eq!(markdown_excerpt("", 13), "");
+    }
+
+    #[test]
+    fn excerpt_cut_at_word() {
+        assert_eq!(markdown_excerpt("one two three", 12), "one two…");
+        assert_eq!(markdown_excerpt("one two, three", 12), "one two…");
+        // a single word longer than the limit
+        assert_eq!(markdown_excerpt("unbreakable", 6), "unbrea…");
+    }
+
+    #[test]
+    fn excerpt_stripped_of_syntax() {
+        let markdown = "# Title\n\nSome *emphasis*, **strong** and ~~struck~~ `code` with [a link](https://example.com).\n\n```\nfn main() {}\n```\n\n![alt text](cat.png) Last\nline";
+
+        assert_eq!(
+            markdown_excerpt(markdown, 200),
+            "Some emphasis, strong and struck code with a link. Last line"
+        );
+    }
+
+    #[test]
+    fn first_image() {
+        assert_eq!(
+            markdown_first_image("text ![a](/api/media/a.png) ![b](b.png)"),
+            Some("/api/media/a.png".into())
+        );
+        assert_eq!(
+            markdown_first_image("[a link](a.png) and `![b](b.png)`"),
+            None
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::imports::*;
+
+/// Uploaded file as listed in the admin media library
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct MediaFile {
+    // content hash with an extension of the sniffed type
+    pub id: String,
+    // as uploaded, for display only
+    pub name: String,
+    pub mime: String,
+    pub size: u64,
+    pub uploaded_by: String,
+    pub uploaded_at: String,
+}
+
+impl MediaFile {
+    pub fn url(&self) -> String {
+        use static_routes::{routes, Get, WithParams};
+
+        routes()
+            .api
+            .media
+            .with(self.id.as_str())
+            .get()
+            .complete()
+            .to_owned()
+    }
+
+    pub fn image(&self) -> bool {
+        self.mime.starts_with("image/")
+    }
+
+    /// Markdown embedding images and linking other files
+    pub fn markdown_link(&self) -> String {
+        let name = self.name.replace(['[', ']'], "");
+
+        if self.image() {
+            format!("![{}]({})", name, self.url())
+        } else {
+            format!("[{}]({})", name, self.url())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linked_by_id() {
+        let file = MediaFile {
+            id: "abc.png".into(),
+            name: "[cat].png".into(),
+            mime: "image/png".into(),
+            size: 3,
+            uploaded_by: "admin".into(),
+            uploaded_at: "2023-01-01T00:00:00Z".into(),
+        };
+
+        assert_eq!(file.url(), "/api/media/abc.png");
+        assert_eq!(file.markdown_link(), "![cat.png](/api/media/abc.png)");
+    }
+}
@@ -0,0 +1,14 @@
+use crate::imports::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasskeyRegistrationStart {
+    // to tell passkeys apart, like "laptop"
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasskeyLoginStart {
+    pub username: String,
+}
@@ -1,11 +1,15 @@
 use crate::imports::*;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct PasswordChangeForm {
     #[serde(serialize_with = "expose_secret_string")]
+    #[cfg_attr(feature = "openapi", schemars(with = "String"))]
     pub current_password: SecretString,
     #[serde(serialize_with = "expose_secret_string")]
+    #[cfg_attr(feature = "openapi", schemars(with = "String"))]
     pub new_password: SecretString,
     #[serde(serialize_with = "expose_secret_string")]
+    #[cfg_attr(feature = "openapi", schemars(with = "String"))]
     pub new_password_check: SecretString,
 }
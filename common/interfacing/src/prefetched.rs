@@ -0,0 +1,14 @@
+use crate::imports::*;
+use crate::{ArticleWithId, Features};
+
+// id of the script element carrying it on server rendered pages
+pub static PREFETCHED_ELEMENT_ID: &str = "prefetched";
+
+/// Data a page was rendered with on the server, for the frontend to hydrate with
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Prefetched {
+    // published ones, for the article list
+    pub articles: Option<Vec<ArticleWithId>>,
+    pub article: Option<ArticleWithId>,
+    pub features: Option<Features>,
+}
@@ -0,0 +1,21 @@
+use crate::imports::*;
+
+/// Session of the logged in user as listed on the admin dashboard
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SessionInfo {
+    pub id: String,
+    // the one the listing was requested with
+    pub current: bool,
+    pub created_at: String,
+    pub last_seen_at: String,
+    pub expires_at: Option<String>,
+    pub user_agent_class: Option<String>,
+    pub hashed_ip: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct RevokeSession {
+    pub id: String,
+}
@@ -1,3 +1,6 @@
+// Articles built into the frontend, also listed in the backend's sitemap
+//
+
 pub fn static_articles() -> StaticArticles {
     StaticArticles {
         md_article_editor: StaticArticle {
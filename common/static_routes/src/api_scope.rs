@@ -1,191 +1,60 @@
-#[allow(unused_imports)]
-use crate::primitives::{Get, Post, Url};
-use macros::*;
-
-#[derive(Default)]
-pub struct Routes {
-    pub health_check: HealthCheck,
-    pub login: Login,
-    pub admin: Admin,
-    pub articles: Articles,
-    pub endpoint_hits: EndpointHits,
-}
-
-#[derive(Default)]
-pub struct EndpointHits {
-    pub frontend: EndpointHitsFrontend,
-    pub github: EndpointHitsGithub,
-}
-
-#[derive(Default, Post)]
-pub struct EndpointHitsFrontend;
-
-impl Url for EndpointHitsFrontend {
-    fn postfix(&self) -> &str {
-        "/endpoint_hits/frontend"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default)]
-pub struct EndpointHitsGithub {
-    pub profile: EndpointHitsGithubProfile,
-    pub wsite: EndpointHitsGithubWsite,
-}
-
-#[derive(Default, Get)]
-pub struct EndpointHitsGithubProfile;
-
-impl Url for EndpointHitsGithubProfile {
-    fn postfix(&self) -> &str {
-        "/endpoint_hits/github"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct EndpointHitsGithubWsite;
-
-impl Url for EndpointHitsGithubWsite {
-    fn postfix(&self) -> &str {
-        "/endpoint_hits/github/wsite"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default)]
-pub struct Admin {
-    pub password: AdminPassword,
-    pub logout: AdminLogout,
-    pub session: AdminSession,
-    pub articles: AdminArticles,
-    pub endpoint_hits: AdminEndpointHits,
-}
-
-#[derive(Default, Get)]
-pub struct HealthCheck;
-
-impl Url for HealthCheck {
-    fn postfix(&self) -> &str {
-        "/health_check"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Post)]
-pub struct Login;
-
-impl Url for Login {
-    fn postfix(&self) -> &str {
-        "/login"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Post)]
-pub struct AdminPassword;
-
-impl Url for AdminPassword {
-    fn postfix(&self) -> &str {
-        "/admin/password"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Post)]
-pub struct AdminLogout;
-
-impl Url for AdminLogout {
-    fn postfix(&self) -> &str {
-        "/admin/logout"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct AdminSession;
-
-impl Url for AdminSession {
-    fn postfix(&self) -> &str {
-        "/admin/session"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct Articles;
-
-impl Url for Articles {
-    fn postfix(&self) -> &str {
-        "/articles"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Post)]
-pub struct AdminArticles;
-
-impl Url for AdminArticles {
-    fn postfix(&self) -> &str {
-        "/admin/articles"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct AdminEndpointHits {
-    pub grouped: AdminEndpointHitsGrouped,
-}
-
-impl Url for AdminEndpointHits {
-    fn postfix(&self) -> &str {
-        "/admin/endpoint_hits"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct AdminEndpointHitsGrouped;
-
-impl Url for AdminEndpointHitsGrouped {
-    fn postfix(&self) -> &str {
-        "/admin/endpoint_hits/grouped"
-    }
-
-    fn prefix(&self) -> &str {
-        "/api"
-    }
+routes! {
+    prefix: "/api";
+
+    health_check: HealthCheck [GET] "/health_check",
+    health: Health {
+        live: HealthLive [GET] "/health/live",
+        ready: HealthReady [GET] "/health/ready",
+    },
+    version: Version [GET] "/version",
+    openapi: OpenApi [GET] "/openapi.json",
+    login: Login [POST] "/login" {
+        passkey: LoginPasskey {
+            start: LoginPasskeyStart [POST] "/login/passkey/start",
+            finish: LoginPasskeyFinish [POST] "/login/passkey/finish",
+        },
+        oidc: LoginOidc [GET] "/login/oidc" {
+            callback: LoginOidcCallback [GET] "/login/oidc/callback",
+        },
+    },
+    admin: Admin {
+        password: AdminPassword [POST] "/admin/password",
+        logout: AdminLogout [POST] "/admin/logout",
+        session: AdminSession [GET] "/admin/session",
+        articles: AdminArticles [POST, PUT] "/admin/articles",
+        endpoint_hits: AdminEndpointHits [GET] "/admin/endpoint_hits" {
+            grouped: AdminEndpointHitsGrouped [GET] "/admin/endpoint_hits/grouped",
+        },
+        audit_log: AdminAuditLog [GET] "/admin/audit_log",
+        sessions: AdminSessions [GET] "/admin/sessions" {
+            revoke: AdminSessionsRevoke [POST] "/admin/sessions/revoke",
+            revoke_others: AdminSessionsRevokeOthers [POST] "/admin/sessions/revoke_others",
+        },
+        passkeys: AdminPasskeys {
+            register: AdminPasskeysRegister {
+                start: AdminPasskeysRegisterStart [POST] "/admin/passkeys/register/start",
+                finish: AdminPasskeysRegisterFinish [POST] "/admin/passkeys/register/finish",
+            },
+        },
+        features: AdminFeatures [GET] "/admin/features" {
+            set: AdminFeaturesSet [POST] "/admin/features/set",
+        },
+        media: AdminMedia [GET] "/admin/media" {
+            upload: AdminMediaUpload [POST] "/admin/media/upload",
+        },
+    },
+    articles: Articles [GET] "/articles" {
+        // deleted by id, under the same pattern
+        by_public_id: ArticlesByPublicId [GET, DELETE] "/articles/:public_id" (public_id: String),
+    },
+    endpoint_hits: EndpointHits {
+        frontend: EndpointHitsFrontend [POST] "/endpoint_hits/frontend",
+        github: EndpointHitsGithub {
+            profile: EndpointHitsGithubProfile [GET] "/endpoint_hits/github",
+            wsite: EndpointHitsGithubWsite [GET] "/endpoint_hits/github/wsite",
+        },
+    },
+    features: Features [GET] "/features",
+    media: Media [GET] "/media/:id" (id: String),
+    static_files: StaticFiles [GET] "/static/*path" (path: String),
 }
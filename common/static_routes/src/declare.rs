@@ -0,0 +1,229 @@
+// Declared routes
+//
+// `routes!` turns a tree of routes into the structs of a scope. Nodes with a
+// path get a `Url` impl and a trait per method, nodes with children get a
+// field per child. Methods of routes with parameters are on them filled in. Pages are routes rendered by the frontend, named after
+// their `Route` variant. Every scope lists what it declares, so the backend
+// router is checked against it, and checks that nothing is declared twice.
+//
+// With `pages: name;`, the scope also exports the macro `name!`, which defines
+// the frontend's `Route` enum, a variant per page with its path and path
+// parameters. Attributes of a page are put on its variant, like #[not_found].
+// Routable takes only literal paths, hence a macro expanded in the frontend.
+//
+//     routes! {
+//         prefix: "/api";
+//         health_check: HealthCheck [GET] "/health_check",
+//         articles: Articles [GET] "/articles" {
+//             by_public_id: ArticlesByPublicId [GET, DELETE] "/articles/:public_id" (public_id: String),
+//         },
+//         admin: Admin {
+//             dashboard: AdminDashboard page AdminDashboard "/admin/dashboard",
+//         },
+//     }
+//
+
+use crate::primitives::{RelativePath, Url};
+
+/// A route as declared in a scope, once per method
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeclaredRoute {
+    pub method: &'static str,
+    // complete, with its :params and *params
+    pub pattern: String,
+    // the frontend `Route` variant rendering it, served by the fallback
+    pub page: Option<&'static str>,
+}
+
+impl DeclaredRoute {
+    pub(crate) fn of<U: Url>(
+        route: &U,
+        methods: &[&'static str],
+        page: Option<&'static str>,
+    ) -> Vec<Self> {
+        let pattern = RelativePath::from(route).complete().to_owned();
+        methods
+            .iter()
+            .map(|method| Self {
+                method,
+                pattern: pattern.clone(),
+                page,
+            })
+            .collect()
+    }
+}
+
+pub(crate) trait Declare {
+    /// Adds the routes of this node and of its children
+    fn declare(&self, routes: &mut Vec<DeclaredRoute>);
+}
+
+macro_rules! routes {
+    (prefix: $prefix:literal; pages: $pages:ident; $($entries:tt)*) => {
+        routes!(prefix: $prefix; $($entries)*);
+        routes!(@pages $pages [] $($entries)*);
+    };
+
+    (prefix: $prefix:literal; $($entries:tt)*) => {
+        routes!(@group $prefix, Routes, (), [], $($entries)*);
+
+        #[cfg(test)]
+        mod declared {
+            #[test]
+            fn declared_once() {
+                let mut routes = vec![];
+                $crate::declare::Declare::declare(&super::Routes::default(), &mut routes);
+
+                for (i, route) in routes.iter().enumerate() {
+                    assert!(
+                        !routes[..i]
+                            .iter()
+                            .any(|other| other.method == route.method && other.pattern == route.pattern),
+                        "{} {} is declared twice",
+                        route.method,
+                        route.pattern
+                    );
+                    assert!(
+                        route.page.is_none()
+                            || !routes[..i].iter().any(|other| other.page == route.page),
+                        "{:?} renders two pages",
+                        route.page
+                    );
+                }
+            }
+        }
+    };
+
+    // every page is collected, the macro defining the frontend `Route`
+    (@pages $pages:ident [$($variants:tt)*] $(,)?) => {
+        #[macro_export]
+        macro_rules! $pages {
+            () => {
+                #[derive(Clone, Debug, PartialEq, Routable)]
+                pub enum Route {
+                    $($variants)*
+                }
+            };
+        }
+    };
+
+    (@pages $pages:ident [$($variants:tt)*] , $($rest:tt)*) => {
+        routes!(@pages $pages [$($variants)*] $($rest)*);
+    };
+
+    // a page, its children flattened in front of the rest
+    (@pages $pages:ident [$($variants:tt)*]
+        $(#[$attr:meta])* $field:ident : $ty:ident page $variant:ident $path:literal
+        $(($param:ident : $params:ty))? $({ $($children:tt)* })? $(, $($rest:tt)*)?) => {
+        routes!(@pages $pages [
+            $($variants)*
+            $(#[$attr])*
+            #[at($path)]
+            $variant $({ $param: $params })?,
+        ] $($($children)*)? , $($($rest)*)?);
+    };
+
+    // not a page, its children may be
+    (@pages $pages:ident [$($variants:tt)*]
+        $(#[$attr:meta])* $field:ident : $ty:ident
+        $([$($method:ident),+] $path:literal $(($param:ident : $params:ty))?)?
+        $({ $($children:tt)* })? $(, $($rest:tt)*)?) => {
+        routes!(@pages $pages [$($variants)*] $($($children)*)? , $($($rest)*)?);
+    };
+
+    // every entry is munched, the struct of the group
+    (@group $prefix:literal, $name:ident, ($([$($method:ident),+], $page:expr)?),
+        [$($field:ident : $ty:ident,)*], $(,)?) => {
+        #[derive(Default)]
+        pub struct $name {
+            $(pub $field: $ty,)*
+        }
+
+        impl $crate::declare::Declare for $name {
+            fn declare(&self, routes: &mut Vec<$crate::DeclaredRoute>) {
+                $(routes.extend($crate::DeclaredRoute::of(self, &[$(stringify!($method)),+], $page));)?
+                $($crate::declare::Declare::declare(&self.$field, routes);)*
+            }
+        }
+    };
+
+    // a route with children
+    (@group $prefix:literal, $name:ident, $own:tt, [$($fields:tt)*],
+        $(#[$attr:meta])* $field:ident : $ty:ident [$($method:ident),+] $path:literal
+        $(($param:ident : $params:ty))?
+        { $($children:tt)* } $(, $($rest:tt)*)?) => {
+        routes!(@route $prefix, $ty, [$($method),+], $path $(, $params)?);
+        routes!(@group $prefix, $ty, ([$($method),+], None), [], $($children)*);
+        routes!(@group $prefix, $name, $own, [$($fields)* $field: $ty,], $($($rest)*)?);
+    };
+
+    // a route
+    (@group $prefix:literal, $name:ident, $own:tt, [$($fields:tt)*],
+        $(#[$attr:meta])* $field:ident : $ty:ident [$($method:ident),+] $path:literal
+        $(($param:ident : $params:ty))?
+        $(, $($rest:tt)*)?) => {
+        routes!(@route $prefix, $ty, [$($method),+], $path $(, $params)?);
+        routes!(@group $prefix, $ty, ([$($method),+], None), [],);
+        routes!(@group $prefix, $name, $own, [$($fields)* $field: $ty,], $($($rest)*)?);
+    };
+
+    // a page with children
+    (@group $prefix:literal, $name:ident, $own:tt, [$($fields:tt)*],
+        $(#[$attr:meta])* $field:ident : $ty:ident page $variant:ident $path:literal
+        $(($param:ident : $params:ty))?
+        { $($children:tt)* } $(, $($rest:tt)*)?) => {
+        routes!(@route $prefix, $ty, [GET], $path $(, $params)?);
+        routes!(@group $prefix, $ty, ([GET], Some(stringify!($variant))), [], $($children)*);
+        routes!(@group $prefix, $name, $own, [$($fields)* $field: $ty,], $($($rest)*)?);
+    };
+
+    // a page
+    (@group $prefix:literal, $name:ident, $own:tt, [$($fields:tt)*],
+        $(#[$attr:meta])* $field:ident : $ty:ident page $variant:ident $path:literal
+        $(($param:ident : $params:ty))?
+        $(, $($rest:tt)*)?) => {
+        routes!(@route $prefix, $ty, [GET], $path $(, $params)?);
+        routes!(@group $prefix, $ty, ([GET], Some(stringify!($variant))), [],);
+        routes!(@group $prefix, $name, $own, [$($fields)* $field: $ty,], $($($rest)*)?);
+    };
+
+    // only children
+    (@group $prefix:literal, $name:ident, $own:tt, [$($fields:tt)*],
+        $(#[$attr:meta])* $field:ident : $ty:ident { $($children:tt)* } $(, $($rest:tt)*)?) => {
+        routes!(@group $prefix, $ty, (), [], $($children)*);
+        routes!(@group $prefix, $name, $own, [$($fields)* $field: $ty,], $($($rest)*)?);
+    };
+
+    (@route $prefix:literal, $ty:ident, [$($method:ident),+], $path:literal) => {
+        routes!(@url $prefix, $ty, $path);
+        $(routes!(@method $ty, $method);)+
+    };
+
+    // requested only once filled in, the route itself is the pattern to route by
+    (@route $prefix:literal, $ty:ident, [$($method:ident),+], $path:literal, $params:ty) => {
+        routes!(@url $prefix, $ty, $path);
+        $(routes!(@method $crate::Filled<$ty>, $method);)+
+
+        impl $crate::WithParams for $ty {
+            type Params = $params;
+        }
+    };
+
+    (@url $prefix:literal, $ty:ident, $path:literal) => {
+        impl $crate::Url for $ty {
+            fn postfix(&self) -> &str {
+                $path
+            }
+
+            fn prefix(&self) -> &str {
+                $prefix
+            }
+        }
+    };
+
+    (@method $ty:ty, GET) => { impl $crate::Get for $ty {} };
+    (@method $ty:ty, POST) => { impl $crate::Post for $ty {} };
+    (@method $ty:ty, PUT) => { impl $crate::Put for $ty {} };
+    (@method $ty:ty, DELETE) => { impl $crate::Delete for $ty {} };
+    (@method $ty:ty, PATCH) => { impl $crate::Patch for $ty {} };
+}
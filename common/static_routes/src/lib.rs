@@ -1,20 +1,39 @@
+#[macro_use]
+mod declare;
+
 mod api_scope;
 mod primitives;
 mod root_scope;
+mod ws_scope;
 
-pub use primitives::{Get, Post, RelativePath, Url};
+pub use declare::DeclaredRoute;
+pub use primitives::{
+    Delete, Filled, Get, Patch, PathParams, Post, Put, RelativePath, Url, WithParams,
+};
 
 #[allow(dead_code)]
 #[derive(Default)]
 pub struct Routes {
     pub api: api_scope::Routes,
     pub root: root_scope::Routes,
+    pub ws: ws_scope::Routes,
 }
 
 impl Routes {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Every route of every scope, pages included
+    pub fn declared(&self) -> Vec<DeclaredRoute> {
+        use declare::Declare;
+
+        let mut routes = vec![];
+        self.api.declare(&mut routes);
+        self.root.declare(&mut routes);
+        self.ws.declare(&mut routes);
+        routes
+    }
 }
 
 pub fn routes() -> Routes {
@@ -32,7 +51,7 @@ mod tests {
     static zeros_with_port: &str = "http://0.0.0.0:8000";
     static https: &str = "https://api-qwerty.digitalocean.com";
 
-    static hosts: &[&'static str] = &[
+    static hosts: &[&str] = &[
         localhost_dns,
         localhost,
         localhost_with_port,
@@ -55,6 +74,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_health() {
+        let routes = routes().api;
+
+        assert_eq!(routes.health.live.get().complete(), "/api/health/live");
+        assert_eq!(routes.health.ready.get().complete(), "/api/health/ready");
+        assert_eq!(routes.version.get().complete(), "/api/version");
+        assert_eq!(routes.openapi.get().complete(), "/api/openapi.json");
+    }
+
+    #[test]
+    fn test_path_params() {
+        let routes = routes().api;
+        let route = &routes.articles.by_public_id;
+
+        assert_eq!(route.pattern().postfix(), "/articles/:public_id");
+        assert_eq!(route.pattern().complete(), "/api/articles/:public_id");
+        assert_eq!(route.with("intro").get().complete(), "/api/articles/intro");
+        assert_eq!(
+            route.with("a b/c").delete().complete(),
+            "/api/articles/a%20b%2Fc"
+        );
+        assert_eq!(
+            route.with("intro").get().with_base(https).complete(),
+            format!("{}/api/articles/intro", https)
+        );
+
+        assert_eq!(
+            routes.static_files.with("images/a b.png").get().complete(),
+            "/api/static/images/a%20b.png"
+        );
+        assert_eq!(
+            routes.admin.articles.put().complete(),
+            "/api/admin/articles"
+        );
+    }
+
+    #[test]
+    fn test_declared() {
+        let declared = routes().declared();
+        let find = |method: &str, pattern: &str| {
+            declared
+                .iter()
+                .find(|route| route.method == method && route.pattern == pattern)
+        };
+
+        assert!(find("GET", "/api/health_check").is_some());
+        assert!(find("PUT", "/api/admin/articles").is_some());
+        assert!(find("DELETE", "/api/articles/:public_id").is_some());
+        assert_eq!(find("GET", "/api/login"), None);
+        assert_eq!(find("GET", "/metrics").unwrap().page, None);
+        assert!(find("GET", "/ws/users_online").is_some());
+        assert_eq!(
+            find("GET", "/admin/articles/:public_id/edit").unwrap().page,
+            Some("EditArticle")
+        );
+
+        let root = routes().root;
+        assert_eq!(
+            root.articles.by_public_id.with("intro").get().complete(),
+            "/articles/intro"
+        );
+    }
+
     #[test]
     fn test_home() {
         let route = routes().root.home.get();
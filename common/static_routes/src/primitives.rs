@@ -83,3 +83,124 @@ pub trait Post: Url {
         self.into()
     }
 }
+
+pub trait Put: Url {
+    fn put(&self) -> RelativePath {
+        self.into()
+    }
+}
+
+pub trait Delete: Url {
+    fn delete(&self) -> RelativePath {
+        self.into()
+    }
+}
+
+pub trait Patch: Url {
+    fn patch(&self) -> RelativePath {
+        self.into()
+    }
+}
+
+/// Route with parameters in its postfix, as `:name` segments and a trailing `*name`
+///
+/// `pattern` gives what the backend routes by, and `with` the route to
+/// request, parameters filled in. Only the latter has the methods, so a
+/// pattern is never requested:
+///
+/// ```compile_fail
+/// use static_routes::*;
+///
+/// routes().api.articles.by_public_id.get();
+/// ```
+pub trait WithParams: Url + Sized {
+    type Params: PathParams;
+
+    fn pattern(&self) -> RelativePath {
+        self.into()
+    }
+
+    fn with(&self, params: impl Into<Self::Params>) -> Filled<Self> {
+        Filled::new(self, params.into())
+    }
+}
+
+/// Values of a route's parameters, in the order of its segments
+pub trait PathParams {
+    fn values(&self) -> Vec<&str>;
+}
+
+impl PathParams for String {
+    fn values(&self) -> Vec<&str> {
+        vec![self]
+    }
+}
+
+impl PathParams for (String, String) {
+    fn values(&self) -> Vec<&str> {
+        vec![&self.0, &self.1]
+    }
+}
+
+/// Route with its parameters filled in, with the methods of the route
+pub struct Filled<R> {
+    prefix: String,
+    postfix: String,
+    route: std::marker::PhantomData<R>,
+}
+
+impl<R: WithParams> Filled<R> {
+    fn new(route: &R, params: R::Params) -> Self {
+        let mut values = params.values().into_iter();
+        let mut value = || values.next().expect("a value for each parameter");
+
+        let postfix = route
+            .postfix()
+            .split('/')
+            .map(|segment| {
+                if segment.starts_with(':') {
+                    percent_encode(value())
+                } else if segment.starts_with('*') {
+                    // spans segments
+                    value()
+                        .split('/')
+                        .map(percent_encode)
+                        .collect::<Vec<_>>()
+                        .join("/")
+                } else {
+                    segment.to_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Self {
+            prefix: route.prefix().to_owned(),
+            postfix,
+            route: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<R> Url for Filled<R> {
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn postfix(&self) -> &str {
+        &self.postfix
+    }
+}
+
+// all but the unreserved characters of RFC 3986
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
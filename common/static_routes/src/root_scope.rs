@@ -1,73 +1,39 @@
-#[allow(unused_imports)]
-use crate::primitives::{Get, Post, Url};
-use macros::*;
-
-#[derive(Default)]
-pub struct Routes {
-    pub home: Home,
-    pub login: Login,
-    pub admin: Admin,
-    pub articles: Articles,
-}
-
-#[derive(Default)]
-pub struct Admin {
-    pub password: AdminPassword,
-    pub dashboard: AdminDashboard,
-    pub articles: AdminArticles,
-}
-
-#[derive(Default, Get)]
-pub struct Home;
-
-impl Url for Home {
-    fn postfix(&self) -> &str {
-        "/"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct Login;
-
-impl Url for Login {
-    fn postfix(&self) -> &str {
-        "/login"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct AdminPassword;
-
-impl Url for AdminPassword {
-    fn postfix(&self) -> &str {
-        "/admin/password"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct AdminDashboard;
-
-impl Url for AdminDashboard {
-    fn postfix(&self) -> &str {
-        "/admin/dashboard"
-    }
-}
-
-#[derive(Default, Get)]
-pub struct AdminArticles;
-
-impl Url for AdminArticles {
-    fn postfix(&self) -> &str {
-        "/admin/articles"
-    }
-}
-
-// article listing
-#[derive(Default, Get)]
-pub struct Articles;
-
-impl Url for Articles {
-    fn postfix(&self) -> &str {
-        "/articles"
-    }
+routes! {
+    prefix: "";
+    pages: frontend_route;
+
+    home: Home page Home "/",
+    login: Login page Login "/login",
+    markdown_preview: MarkdownPreview page MarkdownPreview "/md",
+    // article listing
+    articles: Articles page ArticleList "/articles" {
+        by_public_id: ArticlesByPublicId page ArticleViewer "/articles/:public_id" (public_id: String),
+    },
+    admin: Admin {
+        password: AdminPassword page PasswordChange "/admin/password",
+        dashboard: AdminDashboard page AdminDashboard "/admin/dashboard",
+        articles: AdminArticles page CreateArticle "/admin/articles" {
+            edit: AdminArticlesEdit page EditArticle "/admin/articles/:public_id/edit" (public_id: String),
+        },
+        audit_log: AdminAuditLog page AuditLog "/admin/audit_log",
+        sessions: AdminSessions page Sessions "/admin/sessions",
+        features: AdminFeatures page Features "/admin/features",
+        media: AdminMedia page Media "/admin/media",
+    },
+    snake: Snake page Snake "/snake",
+    referral: Referral page Ref "/i/",
+    #[not_found]
+    not_found: NotFound page NotFound "/404",
+    unauthorized: Unauthorized page Unauthorized "/401",
+    // of published articles, served by the backend outside of /api
+    feeds: Feeds {
+        rss: RssFeed [GET] "/feed.xml",
+        atom: AtomFeed [GET] "/atom.xml",
+        json: JsonFeed [GET] "/feed.json",
+    },
+    // for crawlers, served by the backend outside of /api
+    sitemap: Sitemap [GET] "/sitemap.xml",
+    robots: Robots [GET] "/robots.txt",
+    // for Prometheus, served by the backend outside of /api
+    metrics: Metrics [GET] "/metrics",
 }
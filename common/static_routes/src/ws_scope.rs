@@ -0,0 +1,7 @@
+routes! {
+    prefix: "/ws";
+
+    users_online: UsersOnline [GET] "/users_online",
+    // only served when the backend serves a frontend dist, see dev_frontend
+    dev_reload: DevReload [GET] "/dev_reload",
+}
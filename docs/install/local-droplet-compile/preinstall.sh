@@ -8,4 +8,4 @@ apt-get install pkg-config libssl-dev -y &&
 rustup target add wasm32-unknown-unknown &&
 tar -xzf ~/wsite/frontend/trunk-x86_64-unknown-linux-gnu.tar.gz -C ~/wsite/frontend/ &&
 cd /root/wsite/frontend/ && ~/wsite/frontend/trunk build --release &&
-cd /root/wsite/backend/ && cargo build --release
+cd /root/wsite/backend/ && GIT_SHA=$(git rev-parse HEAD) cargo build --release
@@ -1,23 +1,114 @@
+use crate::components::{Prefetched, PrefetchedCtx};
 use crate::router::Route;
 use crate::switch::switch;
 
 use yew::prelude::*;
 use yew_router::prelude::{BrowserRouter, Switch};
 
+#[derive(Properties, PartialEq, Default)]
+pub struct AppProps {
+    // present when the page was rendered on the server
+    #[prop_or_default]
+    pub prefetched: interfacing::Prefetched,
+}
+
 #[function_component(App)]
-pub fn app() -> Html {
+pub fn app(props: &AppProps) -> Html {
+    let prefetched = use_memo(
+        |prefetched| Prefetched::from(prefetched.clone()),
+        props.prefetched.clone(),
+    );
+
+    html! {
+        <ContextProvider<PrefetchedCtx> context={prefetched}>
+            <BrowserRouter>
+                <Content/>
+            </BrowserRouter>
+        </ContextProvider<PrefetchedCtx>>
+    }
+}
+
+#[function_component(Content)]
+fn content() -> Html {
     use crate::components::theme::theme_ctx::WithTheme;
     use crate::components::theme::toggle::ThemeToggle;
-    use crate::components::WithOnline;
+    use crate::components::{WithFeatures, WithOnline};
 
     html! {
         <WithTheme>
             <ThemeToggle/>
-            <WithOnline>
-                <BrowserRouter>
+            <WithFeatures>
+                <WithOnline>
                     <Switch<Route> render={switch} />
-                </BrowserRouter>
-            </WithOnline>
+                </WithOnline>
+            </WithFeatures>
         </WithTheme>
     }
 }
+
+#[cfg(feature = "ssr")]
+pub use server::{render, ServerApp, ServerAppProps, ServerRendered};
+
+#[cfg(feature = "ssr")]
+mod server {
+    use super::*;
+    use stylist::manager::{render_static, StyleManager};
+    use stylist::yew::ManagerProvider;
+    use yew_router::history::{AnyHistory, History, MemoryHistory};
+    use yew_router::Router;
+
+    #[derive(Properties, PartialEq)]
+    pub struct ServerAppProps {
+        pub path: AttrValue,
+        pub prefetched: interfacing::Prefetched,
+        // collects styles of the page for its head
+        pub manager: StyleManager,
+    }
+
+    /// App as rendered on the server for a path, hydrated by [App](super::App)
+    #[function_component(ServerApp)]
+    pub fn server_app(props: &ServerAppProps) -> Html {
+        let history = AnyHistory::from(MemoryHistory::new());
+        history.push(props.path.to_string());
+        let prefetched = PrefetchedCtx::new(Prefetched::from(props.prefetched.clone()));
+
+        html! {
+            <ManagerProvider manager={props.manager.clone()}>
+                <ContextProvider<PrefetchedCtx> context={prefetched}>
+                    <Router {history}>
+                        <Content/>
+                    </Router>
+                </ContextProvider<PrefetchedCtx>>
+            </ManagerProvider>
+        }
+    }
+
+    /// Page as rendered on the server, with styles of its head
+    pub struct ServerRendered {
+        pub body: String,
+        pub styles: String,
+    }
+
+    pub async fn render(path: String, prefetched: interfacing::Prefetched) -> ServerRendered {
+        let (writer, reader) = render_static();
+
+        let body = yew::ServerRenderer::<ServerApp>::with_props(move || ServerAppProps {
+            path: path.into(),
+            prefetched,
+            manager: StyleManager::builder()
+                .writer(writer)
+                .build()
+                .expect("style manager to build"),
+        })
+        .render()
+        .await;
+
+        let mut styles = String::new();
+        reader
+            .read_style_data()
+            .write_static_markup(&mut styles)
+            .expect("styles to be written");
+
+        ServerRendered { body, styles }
+    }
+}
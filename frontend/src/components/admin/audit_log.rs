@@ -0,0 +1,197 @@
+#![allow(non_upper_case_globals)]
+
+use crate::components::imports::*;
+
+#[derive(Default, Clone)]
+pub struct Refs {
+    actor_ref: NodeRef,
+    action_ref: NodeRef,
+    target_id_ref: NodeRef,
+}
+
+pub struct AuditLog {
+    refs: Refs,
+    entries: Option<Vec<interfacing::AuditLogEntry>>,
+}
+
+pub enum Msg {
+    EntriesLoaded(Vec<interfacing::AuditLogEntry>),
+    Filter(interfacing::AuditLogFilter),
+    Nothing,
+}
+
+impl Component for AuditLog {
+    type Message = Msg;
+    type Properties = ();
+
+    #[allow(unused_variables)]
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            refs: Refs::default(),
+            entries: None,
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let Refs {
+            actor_ref,
+            action_ref,
+            target_id_ref,
+        } = self.refs.clone();
+
+        let onsubmit = {
+            let Refs {
+                actor_ref,
+                action_ref,
+                target_id_ref,
+            } = self.refs.clone();
+
+            ctx.link().callback(move |event: SubmitEvent| {
+                event.prevent_default();
+
+                let value = |node_ref: &NodeRef| {
+                    let value = node_ref.cast::<HtmlInputElement>().unwrap().value();
+                    (!value.is_empty()).then_some(value)
+                };
+
+                Msg::Filter(interfacing::AuditLogFilter {
+                    actor: value(&actor_ref),
+                    action: value(&action_ref),
+                    target_id: value(&target_id_ref),
+                })
+            })
+        };
+
+        let table_style = css!(
+            "
+                border-collapse: collapse;
+                margin-top: 20px;
+
+                td, th {
+                    border: 1px solid;
+                    padding: 5px 10px;
+                    text-align: left;
+                    vertical-align: top;
+                }
+            "
+        );
+
+        let entries = match &self.entries {
+            None => html! { "Loading..." },
+            Some(entries) => {
+                let rows = entries
+                    .iter()
+                    .map(|entry| {
+                        html! {
+                            <tr>
+                                <td>{ &entry.timestamp }</td>
+                                <td>{ &entry.actor }</td>
+                                <td>{ &entry.action }</td>
+                                <td>{ &entry.target_id }</td>
+                                <td>{ &entry.hashed_ip }</td>
+                                <td>{ entry.before.clone().unwrap_or_default() }</td>
+                                <td>{ entry.after.clone().unwrap_or_default() }</td>
+                            </tr>
+                        }
+                    })
+                    .collect::<Html>();
+
+                html! {
+                    <table class={table_style}>
+                        <tr>
+                            <th>{ "Time" }</th>
+                            <th>{ "Actor" }</th>
+                            <th>{ "Action" }</th>
+                            <th>{ "Target" }</th>
+                            <th>{ "IP hash" }</th>
+                            <th>{ "Before" }</th>
+                            <th>{ "After" }</th>
+                        </tr>
+                        { rows }
+                    </table>
+                }
+            }
+        };
+
+        html! {
+            <DefaultStyling>
+                <PageTitle title={"Audit log"}/>
+
+                <h1>{ "Audit log" }</h1>
+
+                <form {onsubmit}>
+                    <label>{ "Actor " }
+                        <input ref={actor_ref} type="text" name="actor"/>
+                    </label>
+                    <label>{ " Action " }
+                        <input ref={action_ref} type="text" name="action"/>
+                    </label>
+                    <label>{ " Target " }
+                        <input ref={target_id_ref} type="text" name="target_id"/>
+                    </label>
+                    <button type="submit">{ "Filter" }</button>
+                </form>
+
+                { entries }
+            </DefaultStyling>
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Self::Message::EntriesLoaded(entries) => {
+                self.entries = Some(entries);
+                true
+            }
+            Self::Message::Filter(filter) => {
+                ctx.link().send_future(load_entries(filter));
+                false
+            }
+            Self::Message::Nothing => false,
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            ctx.link()
+                .send_future(load_entries(interfacing::AuditLogFilter::default()));
+        }
+    }
+}
+
+async fn load_entries(filter: interfacing::AuditLogFilter) -> Msg {
+    match fetch_audit_log(&filter).await {
+        Ok(entries) => Msg::EntriesLoaded(entries),
+        Err(_) => Msg::Nothing,
+    }
+}
+
+async fn fetch_audit_log(
+    filter: &interfacing::AuditLogFilter,
+) -> Result<Vec<interfacing::AuditLogEntry>, ()> {
+    let params = [
+        ("actor", &filter.actor),
+        ("action", &filter.action),
+        ("target_id", &filter.target_id),
+    ];
+
+    let result = Request::static_get(routes().api.admin.audit_log)
+        .query(
+            params
+                .into_iter()
+                .filter_map(|(name, value)| value.as_ref().map(|value| (name, value))),
+        )
+        .send()
+        .await;
+
+    match result {
+        Err(_) => Err(()),
+        Ok(response) => match response.status() {
+            200 => Ok(response
+                .json::<Vec<interfacing::AuditLogEntry>>()
+                .await
+                .unwrap()),
+            _ => Err(()),
+        },
+    }
+}
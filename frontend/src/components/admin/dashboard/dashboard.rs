@@ -1,6 +1,6 @@
 #![allow(non_upper_case_globals)]
 
-use crate::components::admin::dashboard::{Logout, WelcomeMessage};
+use crate::components::admin::dashboard::{Logout, RegisterPasskey, WelcomeMessage};
 use crate::components::imports::*;
 
 pub struct Dashboard;
@@ -57,6 +57,21 @@ impl Component for Dashboard {
                     <li>
                         <Link<Route> to={ Route::PasswordChange }>{ "Change password" }</Link<Route>>
                     </li>
+                    <li>
+                        <Link<Route> to={ Route::AuditLog }>{ "Audit log" }</Link<Route>>
+                    </li>
+                    <li>
+                        <Link<Route> to={ Route::Sessions }>{ "Sessions" }</Link<Route>>
+                    </li>
+                    <li>
+                        <Link<Route> to={ Route::Features }>{ "Feature flags" }</Link<Route>>
+                    </li>
+                    <li>
+                        <Link<Route> to={ Route::Media }>{ "Media" }</Link<Route>>
+                    </li>
+                    <li>
+                        <RegisterPasskey/>
+                    </li>
                     <br/>
                     <li>
                         <Logout/>
@@ -1,7 +1,9 @@
 mod dashboard;
 mod logout;
+mod passkey;
 mod welcome;
 use logout::Logout;
+use passkey::RegisterPasskey;
 use welcome::WelcomeMessage;
 
 pub use dashboard::Dashboard;
@@ -0,0 +1,59 @@
+use crate::components::imports::*;
+use crate::components::passkeys::register_passkey;
+
+#[derive(Default)]
+pub struct RegisterPasskey {
+    name_ref: NodeRef,
+    status: Option<&'static str>,
+}
+
+pub enum Msg {
+    Registered,
+    Failed,
+}
+
+impl Component for RegisterPasskey {
+    type Message = Msg;
+    type Properties = ();
+
+    #[allow(unused_variables)]
+    fn create(ctx: &Context<Self>) -> Self {
+        Self::default()
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let onsubmit = {
+            let name_ref = self.name_ref.clone();
+
+            ctx.link().callback_future(move |event: SubmitEvent| {
+                event.prevent_default();
+
+                let name = name_ref.cast::<HtmlInputElement>().unwrap().value();
+
+                async move {
+                    match register_passkey(name).await {
+                        Ok(()) => Msg::Registered,
+                        Err(()) => Msg::Failed,
+                    }
+                }
+            })
+        };
+
+        html! {
+            <form {onsubmit}>
+                <input ref={ self.name_ref.clone() } type="text" placeholder="passkey name" required={true}/>
+                <button type="submit">{ "Register passkey" }</button>
+                { self.status.map(|status| html! { <span>{ " " }{ status }</span> }).unwrap_or_default() }
+            </form>
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        self.status = Some(match msg {
+            Self::Message::Registered => "registered",
+            Self::Message::Failed => "failed",
+        });
+        true
+    }
+}
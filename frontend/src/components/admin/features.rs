@@ -0,0 +1,170 @@
+#![allow(non_upper_case_globals)]
+
+use crate::components::imports::*;
+use interfacing::FeatureFlag;
+
+pub struct Features {
+    flags: Option<Vec<interfacing::FeatureFlagInfo>>,
+}
+
+pub enum Msg {
+    FlagsLoaded(Vec<interfacing::FeatureFlagInfo>),
+    Set(String, Option<FeatureFlag>),
+    Updated,
+    Nothing,
+}
+
+fn display(flag: &FeatureFlag) -> String {
+    match flag {
+        FeatureFlag::Enabled(true) => "on".into(),
+        FeatureFlag::Enabled(false) => "off".into(),
+        FeatureFlag::Percentage(percentage) => format!("{percentage}%"),
+    }
+}
+
+impl Component for Features {
+    type Message = Msg;
+    type Properties = ();
+
+    #[allow(unused_variables)]
+    fn create(ctx: &Context<Self>) -> Self {
+        Self { flags: None }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let table_style = css!(
+            "
+                border-collapse: collapse;
+                margin-top: 20px;
+
+                td, th {
+                    border: 1px solid;
+                    padding: 5px 10px;
+                    text-align: left;
+                    vertical-align: top;
+                }
+
+                button {
+                    margin-right: 5px;
+                }
+            "
+        );
+
+        let flags = match &self.flags {
+            None => html! { "Loading..." },
+            Some(flags) => {
+                let rows = flags
+                    .iter()
+                    .map(|flag| {
+                        let set = |value: Option<FeatureFlag>| {
+                            let name = flag.name.clone();
+                            ctx.link()
+                                .callback(move |_| Msg::Set(name.clone(), value))
+                        };
+
+                        html! {
+                            <tr>
+                                <td>{ &flag.name }</td>
+                                <td>{ display(&flag.configured) }</td>
+                                <td>{ flag.overridden.as_ref().map(display).unwrap_or_default() }</td>
+                                <td>
+                                    <button onclick={ set(Some(FeatureFlag::Enabled(true))) }>{ "On" }</button>
+                                    <button onclick={ set(Some(FeatureFlag::Enabled(false))) }>{ "Off" }</button>
+                                    <button onclick={ set(None) } disabled={ flag.overridden.is_none() }>{ "Reset" }</button>
+                                </td>
+                            </tr>
+                        }
+                    })
+                    .collect::<Html>();
+
+                html! {
+                    <table class={table_style}>
+                        <tr>
+                            <th>{ "Flag" }</th>
+                            <th>{ "Configured" }</th>
+                            <th>{ "Override" }</th>
+                            <th></th>
+                        </tr>
+                        { rows }
+                    </table>
+                }
+            }
+        };
+
+        html! {
+            <DefaultStyling>
+                <PageTitle title={"Feature flags"}/>
+
+                <h1>{ "Feature flags" }</h1>
+
+                { flags }
+            </DefaultStyling>
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Self::Message::FlagsLoaded(flags) => {
+                self.flags = Some(flags);
+                true
+            }
+            Self::Message::Set(name, value) => {
+                ctx.link().send_future(async move {
+                    match set_flag(name, value).await {
+                        Ok(()) => Msg::Updated,
+                        Err(()) => Msg::Nothing,
+                    }
+                });
+                false
+            }
+            Self::Message::Updated => {
+                ctx.link().send_future(load_flags());
+                false
+            }
+            Self::Message::Nothing => false,
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            ctx.link().send_future(load_flags());
+        }
+    }
+}
+
+async fn load_flags() -> Msg {
+    match fetch_flags().await {
+        Ok(flags) => Msg::FlagsLoaded(flags),
+        Err(_) => Msg::Nothing,
+    }
+}
+
+async fn fetch_flags() -> Result<Vec<interfacing::FeatureFlagInfo>, ()> {
+    let result = Request::static_get(routes().api.admin.features)
+        .send()
+        .await;
+
+    match result {
+        Err(_) => Err(()),
+        Ok(response) => match response.status() {
+            200 => response
+                .json::<Vec<interfacing::FeatureFlagInfo>>()
+                .await
+                .map_err(|_| ()),
+            _ => Err(()),
+        },
+    }
+}
+
+async fn set_flag(name: String, value: Option<FeatureFlag>) -> Result<(), ()> {
+    let result = Request::static_post(routes().api.admin.features.set)
+        .json(&interfacing::FeatureFlagOverride { name, value })
+        .unwrap()
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status() == 200 => Ok(()),
+        _ => Err(()),
+    }
+}
@@ -0,0 +1,204 @@
+#![allow(non_upper_case_globals)]
+
+use crate::components::imports::*;
+
+pub struct Media {
+    files: Option<Vec<interfacing::MediaFile>>,
+    input_ref: NodeRef,
+}
+
+pub enum Msg {
+    FilesLoaded(Vec<interfacing::MediaFile>),
+    Upload,
+    Uploaded,
+    Nothing,
+}
+
+impl Component for Media {
+    type Message = Msg;
+    type Properties = ();
+
+    #[allow(unused_variables)]
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            files: None,
+            input_ref: NodeRef::default(),
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let table_style = css!(
+            "
+                border-collapse: collapse;
+                margin-top: 20px;
+
+                td, th {
+                    border: 1px solid;
+                    padding: 5px 10px;
+                    text-align: left;
+                    vertical-align: top;
+                }
+
+                img {
+                    max-width: 120px;
+                    max-height: 80px;
+                }
+
+                code {
+                    user-select: all;
+                }
+            "
+        );
+
+        let files = match &self.files {
+            None => html! { "Loading..." },
+            Some(files) if files.is_empty() => html! { <p>{ "Nothing uploaded yet" }</p> },
+            Some(files) => {
+                let rows = files
+                    .iter()
+                    .map(|file| {
+                        let preview = if file.image() {
+                            html! { <img src={file.url()} alt={file.name.clone()}/> }
+                        } else {
+                            html! { <a href={file.url()}>{ &file.mime }</a> }
+                        };
+
+                        html! {
+                            <tr>
+                                <td>{ preview }</td>
+                                <td>{ &file.name }</td>
+                                <td>{ format_size(file.size) }</td>
+                                <td>{ &file.uploaded_at }</td>
+                                <td>{ &file.uploaded_by }</td>
+                                <td><code>{ file.markdown_link() }</code></td>
+                            </tr>
+                        }
+                    })
+                    .collect::<Html>();
+
+                html! {
+                    <table class={table_style}>
+                        <tr>
+                            <th></th>
+                            <th>{ "Name" }</th>
+                            <th>{ "Size" }</th>
+                            <th>{ "Uploaded at" }</th>
+                            <th>{ "By" }</th>
+                            <th>{ "Markdown" }</th>
+                        </tr>
+                        { rows }
+                    </table>
+                }
+            }
+        };
+
+        let onchange = ctx.link().callback(|_| Msg::Upload);
+
+        html! {
+            <DefaultStyling>
+                <PageTitle title={"Media"}/>
+
+                <h1>{ "Media" }</h1>
+
+                <label>
+                    { "Upload: " }
+                    <input type="file" ref={self.input_ref.clone()} {onchange}/>
+                </label>
+
+                { files }
+            </DefaultStyling>
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Self::Message::FilesLoaded(files) => {
+                self.files = Some(files);
+                true
+            }
+            Self::Message::Upload => {
+                let input = self.input_ref.cast::<HtmlInputElement>().unwrap();
+                let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                    return false;
+                };
+                input.set_value("");
+
+                ctx.link().send_future(async move {
+                    match upload_media(file).await {
+                        Ok(_) => Msg::Uploaded,
+                        Err(()) => {
+                            let window = web_sys::window().unwrap();
+                            window.alert_with_message("Upload failed").unwrap();
+                            Msg::Nothing
+                        }
+                    }
+                });
+                false
+            }
+            Self::Message::Uploaded => {
+                ctx.link().send_future(load_files());
+                false
+            }
+            Self::Message::Nothing => false,
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            ctx.link().send_future(load_files());
+        }
+    }
+}
+
+fn format_size(size: u64) -> String {
+    match size {
+        size if size < 1024 => format!("{size} B"),
+        size if size < 1024 * 1024 => format!("{:.1} KiB", size as f64 / 1024.0),
+        size => format!("{:.1} MiB", size as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+async fn load_files() -> Msg {
+    match fetch_files().await {
+        Ok(files) => Msg::FilesLoaded(files),
+        Err(_) => Msg::Nothing,
+    }
+}
+
+async fn fetch_files() -> Result<Vec<interfacing::MediaFile>, ()> {
+    let result = Request::static_get(routes().api.admin.media).send().await;
+
+    match result {
+        Err(_) => Err(()),
+        Ok(response) => match response.status() {
+            200 => response
+                .json::<Vec<interfacing::MediaFile>>()
+                .await
+                .map_err(|_| ()),
+            _ => Err(()),
+        },
+    }
+}
+
+/// Uploads a picked file, also used by the article editor
+pub async fn upload_media(file: web_sys::File) -> Result<interfacing::MediaFile, ()> {
+    let url = format!(
+        "{}?name={}",
+        routes().api.admin.media.upload.post().complete(),
+        String::from(js_sys::encode_uri_component(&file.name()))
+    );
+
+    let result = Request::post(&url)
+        .with_csrf_token()
+        .body(file)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status() == 200 => response
+            .json::<interfacing::MediaFile>()
+            .await
+            .map_err(|_| ()),
+        _ => Err(()),
+    }
+}
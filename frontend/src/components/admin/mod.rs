@@ -1,7 +1,15 @@
+mod audit_log;
 mod dashboard;
+mod features;
+mod media;
 mod password;
+mod sessions;
 mod with_session_ctx;
 
+pub use audit_log::AuditLog;
 pub use dashboard::Dashboard;
+pub use features::Features;
+pub use media::{upload_media, Media};
 pub use password::PasswordChange;
+pub use sessions::Sessions;
 pub use with_session_ctx::{SessionCtx, SessionCtxSub, WithSession};
@@ -0,0 +1,184 @@
+#![allow(non_upper_case_globals)]
+
+use crate::components::imports::*;
+
+pub struct Sessions {
+    sessions: Option<Vec<interfacing::SessionInfo>>,
+}
+
+pub enum Msg {
+    SessionsLoaded(Vec<interfacing::SessionInfo>),
+    Revoke(String),
+    RevokeOthers,
+    Revoked,
+    Nothing,
+}
+
+impl Component for Sessions {
+    type Message = Msg;
+    type Properties = ();
+
+    #[allow(unused_variables)]
+    fn create(ctx: &Context<Self>) -> Self {
+        Self { sessions: None }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let table_style = css!(
+            "
+                border-collapse: collapse;
+                margin-top: 20px;
+
+                td, th {
+                    border: 1px solid;
+                    padding: 5px 10px;
+                    text-align: left;
+                    vertical-align: top;
+                }
+            "
+        );
+
+        let sessions = match &self.sessions {
+            None => html! { "Loading..." },
+            Some(sessions) => {
+                let rows = sessions
+                    .iter()
+                    .map(|session| {
+                        let action = if session.current {
+                            html! { "Current" }
+                        } else {
+                            let id = session.id.clone();
+                            let onclick = ctx.link().callback(move |_| Msg::Revoke(id.clone()));
+                            html! { <button {onclick}>{ "Revoke" }</button> }
+                        };
+
+                        html! {
+                            <tr>
+                                <td>{ session.user_agent_class.clone().unwrap_or_default() }</td>
+                                <td>{ session.hashed_ip.clone().unwrap_or_default() }</td>
+                                <td>{ &session.created_at }</td>
+                                <td>{ &session.last_seen_at }</td>
+                                <td>{ session.expires_at.clone().unwrap_or_default() }</td>
+                                <td>{ action }</td>
+                            </tr>
+                        }
+                    })
+                    .collect::<Html>();
+
+                html! {
+                    <table class={table_style}>
+                        <tr>
+                            <th>{ "Browser" }</th>
+                            <th>{ "IP hash" }</th>
+                            <th>{ "Created" }</th>
+                            <th>{ "Last seen" }</th>
+                            <th>{ "Expires" }</th>
+                            <th></th>
+                        </tr>
+                        { rows }
+                    </table>
+                }
+            }
+        };
+
+        let onclick = ctx.link().callback(|_| Msg::RevokeOthers);
+
+        html! {
+            <DefaultStyling>
+                <PageTitle title={"Sessions"}/>
+
+                <h1>{ "Sessions" }</h1>
+
+                <button {onclick}>{ "Log out everywhere else" }</button>
+
+                { sessions }
+            </DefaultStyling>
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Self::Message::SessionsLoaded(sessions) => {
+                self.sessions = Some(sessions);
+                true
+            }
+            Self::Message::Revoke(id) => {
+                ctx.link().send_future(async move {
+                    match revoke_session(id).await {
+                        Ok(()) => Msg::Revoked,
+                        Err(()) => Msg::Nothing,
+                    }
+                });
+                false
+            }
+            Self::Message::RevokeOthers => {
+                ctx.link().send_future(async move {
+                    match revoke_other_sessions().await {
+                        Ok(()) => Msg::Revoked,
+                        Err(()) => Msg::Nothing,
+                    }
+                });
+                false
+            }
+            Self::Message::Revoked => {
+                ctx.link().send_future(load_sessions());
+                false
+            }
+            Self::Message::Nothing => false,
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            ctx.link().send_future(load_sessions());
+        }
+    }
+}
+
+async fn load_sessions() -> Msg {
+    match fetch_sessions().await {
+        Ok(sessions) => Msg::SessionsLoaded(sessions),
+        Err(_) => Msg::Nothing,
+    }
+}
+
+async fn fetch_sessions() -> Result<Vec<interfacing::SessionInfo>, ()> {
+    let result = Request::static_get(routes().api.admin.sessions)
+        .send()
+        .await;
+
+    match result {
+        Err(_) => Err(()),
+        Ok(response) => match response.status() {
+            200 => Ok(response
+                .json::<Vec<interfacing::SessionInfo>>()
+                .await
+                .unwrap()),
+            _ => Err(()),
+        },
+    }
+}
+
+async fn revoke_session(id: String) -> Result<(), ()> {
+    let result = Request::static_post(routes().api.admin.sessions.revoke)
+        .json(&interfacing::RevokeSession { id })
+        .unwrap()
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status() == 200 => Ok(()),
+        _ => Err(()),
+    }
+}
+
+async fn revoke_other_sessions() -> Result<(), ()> {
+    let result = Request::static_post(routes().api.admin.sessions.revoke_others)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status() == 200 => Ok(()),
+        _ => Err(()),
+    }
+}
@@ -68,7 +68,7 @@ impl Component for EditArticle {
 
 async fn fetch_article(public_id: &str) -> Result<Article, ()> {
     // duplicate
-    let result = Request::get(&format!("/api/articles/{}", public_id))
+    let result = Request::static_get(routes().api.articles.by_public_id.with(public_id))
         .send()
         .await;
 
@@ -1,5 +1,6 @@
 #![allow(non_upper_case_globals)]
 
+use crate::components::admin::upload_media;
 use crate::components::imports::*;
 use crate::components::MarkdownPreview;
 
@@ -36,21 +37,28 @@ pub struct ArticleEditor {
     refs: Refs,
     mode: ArticleEditorMode,
     current_article_state: Article,
+    // as typed, normalized into the article on input
+    tags_input: String,
 }
 
 #[derive(Default, Clone)]
 pub struct Refs {
     title_ref: NodeRef,
     public_id_ref: NodeRef,
+    tags_ref: NodeRef,
     draft_ref: NodeRef,
+    media_ref: NodeRef,
 }
 
 pub enum Msg {
     ThemeContextUpdate(ThemeCtx),
     TitleChanged(String),
     PublicIDChanged(String),
+    TagsChanged(String),
     MarkdownChanged(AttrValue),
     DraftStateChanged(bool),
+    UploadMedia,
+    MediaUploaded(interfacing::MediaFile),
     NewArticleVersion(interfacing::ArticleWithId),
     Nothing,
 }
@@ -78,6 +86,7 @@ impl Component for ArticleEditor {
             theme_ctx: ThemeCtxSub::subscribe(ctx, Self::Message::ThemeContextUpdate),
             refs: Refs::default(),
             mode: ctx.props().mode.clone(),
+            tags_input: initial_article.body().tags.join(", "),
             current_article_state: initial_article,
         }
     }
@@ -263,6 +272,15 @@ impl Component for ArticleEditor {
             })
         };
 
+        let tags_oninput = {
+            let input_node_ref = self.refs.tags_ref.clone();
+            ctx.link().callback(move |_| {
+                let input_field = input_node_ref.cast::<HtmlInputElement>().unwrap();
+                let value = input_field.value();
+                Self::Message::TagsChanged(value)
+            })
+        };
+
         let draft_oninput = {
             let input_node_ref = self.refs.draft_ref.clone();
             ctx.link().callback(move |_| {
@@ -272,6 +290,8 @@ impl Component for ArticleEditor {
             })
         };
 
+        let media_onchange = ctx.link().callback(|_| Self::Message::UploadMedia);
+
         let title = match &self.mode {
             ArticleEditorMode::Create => html! {
                 <PageTitle title={format!("New: {}", self.current_article_state.body().title)}/>
@@ -309,6 +329,14 @@ impl Component for ArticleEditor {
                                 value={ self.current_article_state.body().public_id.clone() }
                             />
                         </div>
+                        <div class={metadatum_classes.clone()}>
+                            <label for="tags_input">{ "Tags" }</label>
+                            <input oninput={tags_oninput} name="tags_input"
+                                placeholder="comma separated"
+                                ref={self.refs.tags_ref.clone()}
+                                value={ self.tags_input.clone() }
+                            />
+                        </div>
 
                         <div class={checkbox_classes.clone()}>
                             <label for="draft_input">{ "Draft" }</label>
@@ -318,6 +346,14 @@ impl Component for ArticleEditor {
                             />
                         </div>
 
+                        <label class={action_classes.clone()}>
+                            { "Insert file" }
+                            <input type="file" hidden={true} onchange={media_onchange}
+                                ref={self.refs.media_ref.clone()}
+                            />
+                        </label>
+                        <br/>
+
                         { actions_block }
                     </div>
                 </div>
@@ -325,7 +361,7 @@ impl Component for ArticleEditor {
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Self::Message::ThemeContextUpdate(theme_ctx) => {
                 console::log!("WithTheme context updated from ArticleEditor");
@@ -342,6 +378,13 @@ impl Component for ArticleEditor {
                 self.current_article_state.body_mut().public_id = value;
                 true
             }
+            Self::Message::TagsChanged(value) => {
+                console::log!(format!("tags changed from ArticleEditor"));
+                self.current_article_state.body_mut().tags =
+                    interfacing::Article::normalized_tags(value.split(','));
+                self.tags_input = value;
+                true
+            }
             Self::Message::MarkdownChanged(value) => {
                 console::log!(format!("markdown changed from ArticleEditor"));
                 self.current_article_state.body_mut().markdown = value.to_string();
@@ -352,6 +395,35 @@ impl Component for ArticleEditor {
                 self.current_article_state.body_mut().draft = value;
                 true
             }
+            Self::Message::UploadMedia => {
+                let input = self.refs.media_ref.cast::<HtmlInputElement>().unwrap();
+                let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                    return false;
+                };
+                input.set_value("");
+
+                ctx.link().send_future(async move {
+                    match upload_media(file).await {
+                        Ok(file) => Msg::MediaUploaded(file),
+                        Err(()) => {
+                            let window = web_sys::window().unwrap();
+                            window.alert_with_message("Upload failed").unwrap();
+                            Msg::Nothing
+                        }
+                    }
+                });
+                false
+            }
+            Self::Message::MediaUploaded(file) => {
+                console::log!(format!("media uploaded from ArticleEditor"));
+                let markdown = &mut self.current_article_state.body_mut().markdown;
+                if !markdown.is_empty() && !markdown.ends_with('\n') {
+                    markdown.push('\n');
+                }
+                markdown.push_str(&file.markdown_link());
+                markdown.push('\n');
+                true
+            }
             Self::Message::NewArticleVersion(_value) => {
                 console::log!("new article version saved from ArticleEditor");
                 false
@@ -377,7 +449,7 @@ async fn request_article_create(
 }
 
 async fn request_article_update(article: &interfacing::ArticleWithId) -> request::SendResult {
-    Request::put("/api/admin/articles")
+    Request::static_put(routes().api.admin.articles)
         .json(&article)
         .unwrap()
         .send()
@@ -2,7 +2,7 @@
 
 use crate::components::imports::*;
 
-use crate::static_articles::{StaticArticle, static_articles};
+use interfacing::{StaticArticle, static_articles};
 
 enum Article {
     Dynamic(interfacing::ArticleWithId),
@@ -27,6 +27,8 @@ impl Article {
 
 pub struct ArticleList {
     articles: Option<Vec<Article>>,
+    // rendered on the server, drafts are not there
+    prefetched: bool,
     theme_ctx: ThemeCtxSub,
     session_ctx: SessionCtxSub,
 }
@@ -44,8 +46,11 @@ impl Component for ArticleList {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
+        let articles = prefetched(ctx).and_then(|prefetched| prefetched.take_articles());
+
         Self {
-            articles: None,
+            prefetched: articles.is_some(),
+            articles: articles.map(with_static_articles),
             theme_ctx: ThemeCtxSub::subscribe(ctx, Self::Message::ThemeContextUpdate),
             session_ctx: SessionCtxSub::subscribe(ctx, Msg::SessionContextUpdate),
         }
@@ -216,25 +221,15 @@ impl Component for ArticleList {
     }
 
     fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
-        if first_render {
-            ctx.link().send_future(async {
-                match fetch_article_list().await {
-                    Ok(articles) => Self::Message::ArticlesLoaded(articles),
-                    Err(_) => Self::Message::Nothing,
-                }
-            });
+        if first_render && !self.prefetched {
+            load_articles(ctx);
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Self::Message::ArticlesLoaded(dyn_articles) => {
-                let mut articles = vec![];
-
-                articles.extend(static_articles().into_iter().map(Article::Static));
-                articles.extend(dyn_articles.into_iter().map(Article::Dynamic));
-
-                self.articles = Some(articles);
+                self.articles = Some(with_static_articles(dyn_articles));
                 true
             }
             Self::Message::ThemeContextUpdate(theme_ctx) => {
@@ -244,6 +239,9 @@ impl Component for ArticleList {
             }
             Self::Message::SessionContextUpdate(session_ctx) => {
                 console::log!("WithSession context updated from WelcomeMessage");
+                if self.prefetched && session_ctx.is_some() {
+                    load_articles(ctx);
+                }
                 self.session_ctx.set(session_ctx);
                 true
             }
@@ -253,6 +251,24 @@ impl Component for ArticleList {
     }
 }
 
+fn with_static_articles(dyn_articles: Vec<interfacing::ArticleWithId>) -> Vec<Article> {
+    let mut articles = vec![];
+
+    articles.extend(static_articles().into_iter().map(Article::Static));
+    articles.extend(dyn_articles.into_iter().map(Article::Dynamic));
+
+    articles
+}
+
+fn load_articles(ctx: &Context<ArticleList>) {
+    ctx.link().send_future(async {
+        match fetch_article_list().await {
+            Ok(articles) => Msg::ArticlesLoaded(articles),
+            Err(_) => Msg::Nothing,
+        }
+    });
+}
+
 async fn fetch_article_list() -> Result<Vec<interfacing::ArticleWithId>, ()> {
     let result = Request::static_get(routes().api.articles).send().await;
 
@@ -266,7 +282,7 @@ async fn fetch_article_list() -> Result<Vec<interfacing::ArticleWithId>, ()> {
 }
 
 async fn delete_article(id: &str) -> Result<(), ()> {
-    let result = Request::delete(&format!("/api/articles/{}", id))
+    let result = Request::static_delete(routes().api.articles.by_public_id.with(id))
         .send()
         .await;
 
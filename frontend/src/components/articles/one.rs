@@ -22,11 +22,12 @@ impl Component for ArticleViewer {
     type Message = Msg;
     type Properties = Props;
 
-    #[allow(unused_variables)]
     fn create(ctx: &Context<Self>) -> Self {
+        let public_id = ctx.props().public_id.clone();
+
         Self {
-            article: None,
-            public_id: ctx.props().public_id.clone(),
+            article: prefetched(ctx).and_then(|prefetched| prefetched.take_article(&public_id)),
+            public_id,
         }
     }
 
@@ -50,7 +51,7 @@ impl Component for ArticleViewer {
     }
 
     fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
-        if first_render {
+        if first_render && self.article.is_none() {
             let public_id = self.public_id.clone().to_string();
             ctx.link().send_future(async move {
                 match fetch_article(public_id.as_str()).await {
@@ -73,7 +74,7 @@ impl Component for ArticleViewer {
 }
 
 async fn fetch_article(public_id: &str) -> Result<interfacing::ArticleWithId, ()> {
-    let result = Request::get(&format!("/api/articles/{}", public_id))
+    let result = Request::static_get(routes().api.articles.by_public_id.with(public_id))
         .send()
         .await;
 
@@ -0,0 +1,29 @@
+use crate::components::imports::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    #[prop_or_default]
+    pub children: Children,
+    // rendered on the server and until hydrated
+    #[prop_or_default]
+    pub fallback: Html,
+}
+
+/// Renders children only in the browser, for components needing its APIs
+///
+/// Effects do not run on the server, so the first render of hydration matches
+/// the server one, and children are rendered right after.
+#[function_component(ClientOnly)]
+pub fn client_only(props: &Props) -> Html {
+    let mounted = use_state(|| false);
+
+    {
+        let mounted = mounted.clone();
+        use_effect_with_deps(move |_| mounted.set(true), ());
+    }
+
+    match *mounted {
+        true => html! { <>{ props.children.clone() }</> },
+        false => props.fallback.clone(),
+    }
+}
@@ -0,0 +1,96 @@
+use crate::components::imports::*;
+
+// None until loaded
+pub type FeaturesCtx = Rc<Option<interfacing::Features>>;
+
+pub struct WithFeatures {
+    features: Option<interfacing::Features>,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    #[prop_or_default]
+    pub children: Children,
+}
+
+pub enum Msg {
+    FeaturesLoaded(interfacing::Features),
+    Nothing,
+}
+
+impl Component for WithFeatures {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            features: prefetched(ctx).and_then(|prefetched| prefetched.take_features()),
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <ContextProvider<FeaturesCtx> context={Rc::new(self.features.clone())}>
+                { ctx.props().children.clone() }
+            </ContextProvider<FeaturesCtx>>
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Self::Message::FeaturesLoaded(features) => {
+                self.features = Some(features);
+                true
+            }
+            Self::Message::Nothing => false,
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render && self.features.is_none() {
+            ctx.link().send_future(async {
+                match fetch_features().await {
+                    Ok(features) => Msg::FeaturesLoaded(features),
+                    Err(()) => Msg::Nothing,
+                }
+            });
+        }
+    }
+}
+
+async fn fetch_features() -> Result<interfacing::Features, ()> {
+    let response = Request::static_get(routes().api.features)
+        .send()
+        .await
+        .map_err(|_| ())?;
+
+    match response.status() {
+        200 => response.json().await.map_err(|_| ()),
+        _ => Err(()),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct FeatureProps {
+    pub name: &'static str,
+    #[prop_or_default]
+    pub children: Children,
+    // rendered when the feature is disabled
+    #[prop_or_default]
+    pub fallback: Html,
+}
+
+/// Renders children only when the feature is enabled
+#[function_component(Feature)]
+pub fn feature(props: &FeatureProps) -> Html {
+    let features = use_context::<FeaturesCtx>().expect("Features context does not exist");
+
+    match features.as_ref() {
+        // not to flash disabled features or fallbacks
+        None => html! {},
+        Some(features) if features.get(props.name).copied().unwrap_or_default() => {
+            html! { <>{ props.children.clone() }</> }
+        }
+        Some(_) => props.fallback.clone(),
+    }
+}
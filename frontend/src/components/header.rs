@@ -1,6 +1,7 @@
 #![allow(non_upper_case_globals)]
 
 use crate::components::imports::*;
+use crate::components::Feature;
 
 pub struct Header {
     theme_ctx: ThemeCtxSub,
@@ -80,7 +81,9 @@ impl Component for Header {
 
         html! {
             <div class={ wrapper_style }>
-                <div class={ online_style }>{ self.online }{ " Online" }</div>
+                <Feature name={interfacing::USERS_ONLINE_FEATURE}>
+                    <div class={ online_style }>{ self.online }{ " Online" }</div>
+                </Feature>
             </div>
         }
     }
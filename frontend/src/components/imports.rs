@@ -5,6 +5,7 @@ pub use crate::components::theme::prelude::*;
 pub use crate::components::Colored;
 pub use crate::components::DefaultStyling;
 pub use crate::components::PageTitle;
+pub use crate::components::{prefetched, PrefetchedCtx};
 pub use crate::components::{OnlineCtx, OnlineCtxSub};
 pub use crate::router::Route;
 pub use static_routes::*;
@@ -12,7 +13,7 @@ pub use static_routes::*;
 pub use std::collections::HashMap;
 pub use std::rc::Rc;
 
-pub use gloo_console as console;
+pub use crate::console;
 pub use gloo_net::http::{Request, Response};
 pub use secrecy::{ExposeSecret, SecretString};
 pub use serde::{Deserialize, Serialize};
@@ -22,9 +23,21 @@ pub use web_sys::{HtmlElement, HtmlInputElement};
 pub use yew::prelude::*;
 pub use yew_router::prelude::*;
 
+// token of the loaded admin session, attached to mutating requests
+thread_local! {
+    static CSRF_TOKEN: std::cell::RefCell<Option<String>> = Default::default();
+}
+
+fn set_csrf_token(token: Option<String>) {
+    CSRF_TOKEN.with(|v| *v.borrow_mut() = token);
+}
+
 pub trait RequestExtend {
     fn static_get(static_path: impl Get) -> Self;
     fn static_post(static_path: impl Post) -> Self;
+    fn static_put(static_path: impl Put) -> Self;
+    fn static_delete(static_path: impl Delete) -> Self;
+    fn with_csrf_token(self) -> Self;
 }
 
 impl RequestExtend for Request {
@@ -33,7 +46,22 @@ impl RequestExtend for Request {
     }
 
     fn static_post(static_path: impl Post) -> Self {
-        Request::post(static_path.post().complete())
+        Request::post(static_path.post().complete()).with_csrf_token()
+    }
+
+    fn static_put(static_path: impl Put) -> Self {
+        Request::put(static_path.put().complete()).with_csrf_token()
+    }
+
+    fn static_delete(static_path: impl Delete) -> Self {
+        Request::delete(static_path.delete().complete()).with_csrf_token()
+    }
+
+    fn with_csrf_token(self) -> Self {
+        match CSRF_TOKEN.with(|v| v.borrow().clone()) {
+            None => self,
+            Some(token) => self.header(interfacing::CSRF_TOKEN_HEADER, &token),
+        }
     }
 }
 
@@ -82,14 +110,17 @@ pub async fn fetch_admin_session() -> Result<interfacing::AdminSession, SessionE
         .await
         .map_err(SessionError::RequestError)?;
 
-    match response.status() {
-        200 => Ok(response
+    let session = match response.status() {
+        200 => response
             .json::<interfacing::AdminSession>()
             .await
-            .map_err(SessionError::ParsingError)?),
+            .map_err(SessionError::ParsingError),
         401 => Err(SessionError::AuthError),
         status => Err(SessionError::BadStatus(status)),
-    }
+    };
+
+    set_csrf_token(session.as_ref().ok().map(|v| v.csrf_token.clone()));
+    session
 }
 
 pub fn internal_problems() -> Html {
@@ -1,6 +1,8 @@
 #![allow(non_upper_case_globals)]
 
 use crate::components::imports::*;
+use crate::components::passkeys::login_with_passkey;
+use crate::components::Feature;
 
 #[derive(Default, Clone)]
 pub struct Refs {
@@ -8,6 +10,13 @@ pub struct Refs {
     password_ref: NodeRef,
 }
 
+#[derive(Deserialize)]
+struct LoginQuery {
+    error: Option<String>,
+    // set by the OIDC callback when a passkey is required as the second factor
+    passkey: Option<String>,
+}
+
 pub struct Login {
     theme_ctx: ThemeCtxSub,
     refs: Refs,
@@ -16,6 +25,8 @@ pub struct Login {
 pub enum Msg {
     AuthSuccess,
     AuthFailure,
+    // passkey login of the user, alone or after password as the second factor
+    PasskeyLogin(String),
     AlreadyAuthed,
     Nothing,
     ThemeContextUpdate(ThemeCtx),
@@ -48,6 +59,15 @@ impl Component for Login {
                 window.alert_with_message("Unauthorized").unwrap();
                 true
             }
+            Self::Message::PasskeyLogin(username) => {
+                ctx.link().send_future(async move {
+                    match login_with_passkey(username).await {
+                        Ok(200) => Msg::AuthSuccess,
+                        _ => Msg::AuthFailure,
+                    }
+                });
+                false
+            }
             Self::Message::AlreadyAuthed => {
                 console::log!("already authed, redirect to dashboard");
                 navigator.push(&Route::AdminDashboard);
@@ -144,8 +164,9 @@ impl Component for Login {
                 let username_field = username_ref.cast::<HtmlInputElement>().unwrap();
                 let password_field = password_ref.cast::<HtmlInputElement>().unwrap();
 
+                let username = username_field.value();
                 let login_form = interfacing::LoginForm {
-                    username: username_field.value(),
+                    username: username.clone(),
                     password: SecretString::new(password_field.value()),
                 };
 
@@ -156,6 +177,7 @@ impl Component for Login {
 
                     match login_response.status() {
                         200 => Msg::AuthSuccess,
+                        202 => Msg::PasskeyLogin(username),
                         401 => Msg::AuthFailure,
                         _ => unimplemented!(),
                     }
@@ -163,10 +185,25 @@ impl Component for Login {
             })
         };
 
+        let on_passkey_click = {
+            let username_ref = self.refs.username_ref.clone();
+
+            ctx.link().batch_callback(move |event: MouseEvent| {
+                event.prevent_default();
+
+                let username = username_ref.cast::<HtmlInputElement>().unwrap().value();
+                (!username.is_empty()).then_some(Msg::PasskeyLogin(username))
+            })
+        };
+
+        // set by the OIDC callback when single sign-on fails
         let error_node = {
-            let location = ctx.link().location().unwrap();
-            let query_params = location.query::<HashMap<String, String>>().unwrap();
-            match query_params.get("error") {
+            let error = ctx
+                .link()
+                .location()
+                .and_then(|location| location.query::<LoginQuery>().ok())
+                .and_then(|query| query.error);
+            match error {
                 None => html! {},
                 Some(error) => html! {
                     <div class="alert alert-warning" role="alert">
@@ -202,8 +239,16 @@ impl Component for Login {
                             required={true}/>
                         </div>
 
-                        <button class={ button_style } type="submit">{ "login" }</button>
+                        <button class={ button_style.clone() } type="submit">{ "login" }</button>
+                        <button class={ classes!(button_style, css!("margin-left: 20px; width: auto;")) }
+                        type="button" onclick={ on_passkey_click }>{ "passkey" }</button>
                     </form>
+
+                    <Feature name={interfacing::OIDC_LOGIN_FEATURE}>
+                        <p class={ css!{"padding-top: 20px;"} }>
+                            <a href={ routes().api.login.oidc.get().complete().to_owned() }>{ "Sign in with SSO" }</a>
+                        </p>
+                    </Feature>
                 </div>
             </DefaultStyling>
         )
@@ -211,6 +256,16 @@ impl Component for Login {
 
     fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
         if first_render {
+            let passkey_username = ctx
+                .link()
+                .location()
+                .and_then(|location| location.query::<LoginQuery>().ok())
+                .and_then(|query| query.passkey);
+            if let Some(username) = passkey_username {
+                ctx.link().send_message(Msg::PasskeyLogin(username));
+                return;
+            }
+
             ctx.link().send_future(async {
                 match fetch_admin_session().await {
                     Ok(_session) => Self::Message::AlreadyAuthed,
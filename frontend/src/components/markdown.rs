@@ -101,10 +101,17 @@ impl Component for Markdown {
         );
 
         let code_style_css_link = {
-            let url = match self.theme_ctx.as_ref().id {
-                Themes::Dark | Themes::Pastel => "/api/static/code_styles/macchiato.css",
-                Themes::Light => "/api/static/code_styles/latte.css",
+            let style = match self.theme_ctx.as_ref().id {
+                Themes::Dark | Themes::Pastel => "code_styles/macchiato.css",
+                Themes::Light => "code_styles/latte.css",
             };
+            let url = routes()
+                .api
+                .static_files
+                .with(style)
+                .get()
+                .complete()
+                .to_owned();
 
             html! {
                 <link rel={"stylesheet"} href={url}/>
@@ -142,14 +149,7 @@ impl Component for Markdown {
 }
 
 pub fn parse_md(markdown_input: &str) -> yew::virtual_dom::VNode {
-    use pulldown_cmark::{html, Options, Parser};
-
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    let parser = Parser::new_ext(markdown_input, options);
-
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
+    let html_output = interfacing::markdown_to_html(markdown_input);
 
     yew::virtual_dom::VNode::from_html_unchecked(html_output.into())
 }
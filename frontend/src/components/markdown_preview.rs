@@ -116,6 +116,16 @@ impl Component for MarkdownPreview {
         }
     }
 
+    // markdown may also change from outside, like a link inserted by the article editor
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        if ctx.props().md != self.input_value {
+            self.input_value = ctx.props().md.clone();
+            true
+        } else {
+            false
+        }
+    }
+
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Self::Message::InputChanged(value) => {
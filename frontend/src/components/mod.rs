@@ -4,9 +4,11 @@
 pub mod imports;
 
 mod articles;
+mod client_only;
 mod colored;
 mod default_styling;
 mod error;
+mod features_ctx;
 mod header;
 mod login;
 mod markdown;
@@ -14,15 +16,19 @@ mod markdown_preview;
 mod markdown_preview_page;
 mod online;
 mod online_ctx;
+mod passkeys;
 mod post;
+mod prefetched;
 mod snake;
 mod title;
 
 pub mod admin;
 pub use articles::*;
+pub use client_only::ClientOnly;
 pub use colored::Colored;
 pub use default_styling::DefaultStyling;
 pub use error::Error;
+pub use features_ctx::{Feature, FeaturesCtx, WithFeatures};
 pub use header::Header;
 pub use login::Login;
 pub use markdown::Markdown;
@@ -31,6 +37,7 @@ pub use markdown_preview_page::MarkdownPreviewPage;
 pub use online::Online;
 pub use online_ctx::{OnlineCtx, OnlineCtxSub, WithOnline};
 pub use post::Post;
+pub use prefetched::{prefetched, Prefetched, PrefetchedCtx};
 pub use snake::comp::Snake;
 pub use title::PageTitle;
 
@@ -41,7 +41,9 @@ impl Component for Online {
 
     fn create(ctx: &Context<Self>) -> Self {
         let location = web_sys::window().unwrap().location();
-        let url = web_sys::Url::new("ws://127.0.0.1:8000/ws/users_online").unwrap();
+        let users_online = routes().ws.users_online.get();
+        let url =
+            web_sys::Url::new(users_online.with_base("ws://127.0.0.1:8000").complete()).unwrap();
 
         let hostname = location.hostname().unwrap();
 
@@ -89,7 +89,11 @@ impl Component for WithOnline {
 
         html! {
             <>
-                <crate::components::Online onchange={on_online_change}/>
+                <crate::components::Feature name={interfacing::USERS_ONLINE_FEATURE}>
+                    <crate::components::ClientOnly>
+                        <crate::components::Online onchange={on_online_change}/>
+                    </crate::components::ClientOnly>
+                </crate::components::Feature>
                 <ContextProvider<OnlineCtx> context={Rc::new(state)}>
                     { ctx.props().children.clone() }
                 </ContextProvider<OnlineCtx>>
@@ -0,0 +1,88 @@
+// Browser side of passkey ceremonies
+//
+
+use crate::components::imports::*;
+use wasm_bindgen_futures::JsFuture;
+use webauthn_rs_proto::{
+    CreationChallengeResponse, PublicKeyCredential, RegisterPublicKeyCredential,
+    RequestChallengeResponse,
+};
+
+pub async fn register_passkey(name: String) -> Result<(), ()> {
+    let challenge = Request::static_post(routes().api.admin.passkeys.register.start)
+        .json(&interfacing::PasskeyRegistrationStart { name })
+        .unwrap()
+        .send()
+        .await
+        .map_err(|_| ())?;
+
+    if challenge.status() != 200 {
+        return Err(());
+    }
+
+    let challenge = challenge
+        .json::<CreationChallengeResponse>()
+        .await
+        .map_err(|_| ())?;
+
+    let options: web_sys::CredentialCreationOptions = challenge.into();
+    let promise = gloo_utils::window()
+        .navigator()
+        .credentials()
+        .create_with_options(&options)
+        .map_err(|_| ())?;
+    // rejected when cancelled by user
+    let credential = JsFuture::from(promise).await.map_err(|_| ())?;
+    let credential =
+        RegisterPublicKeyCredential::from(web_sys::PublicKeyCredential::from(credential));
+
+    let response = Request::static_post(routes().api.admin.passkeys.register.finish)
+        .json(&credential)
+        .unwrap()
+        .send()
+        .await
+        .map_err(|_| ())?;
+
+    match response.status() {
+        200 => Ok(()),
+        _ => Err(()),
+    }
+}
+
+/// Returns status of the finishing request
+pub async fn login_with_passkey(username: String) -> Result<u16, ()> {
+    let challenge = Request::static_post(routes().api.login.passkey.start)
+        .json(&interfacing::PasskeyLoginStart { username })
+        .unwrap()
+        .send()
+        .await
+        .map_err(|_| ())?;
+
+    if challenge.status() != 200 {
+        return Ok(challenge.status());
+    }
+
+    let challenge = challenge
+        .json::<RequestChallengeResponse>()
+        .await
+        .map_err(|_| ())?;
+
+    let options: web_sys::CredentialRequestOptions = challenge.into();
+    let promise = gloo_utils::window()
+        .navigator()
+        .credentials()
+        .get_with_options(&options)
+        .map_err(|_| ())?;
+    // rejected when cancelled by user
+    let credential = JsFuture::from(promise).await.map_err(|_| ())?;
+    let credential = PublicKeyCredential::from(web_sys::PublicKeyCredential::from(credential));
+
+    let response = Request::static_post(routes().api.login.passkey.finish)
+        .json(&credential)
+        .unwrap()
+        .send()
+        .await
+        .map_err(|_| ())?;
+
+    Ok(response.status())
+}
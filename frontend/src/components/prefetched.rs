@@ -0,0 +1,48 @@
+use crate::components::imports::*;
+use std::cell::RefCell;
+
+/// Data the page was rendered with on the server
+///
+/// Components take their part once, so hydration renders what the server did,
+/// and later visits of the same route load fresh data.
+#[derive(Default)]
+pub struct Prefetched(RefCell<interfacing::Prefetched>);
+
+pub type PrefetchedCtx = Rc<Prefetched>;
+
+impl PartialEq for Prefetched {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl From<interfacing::Prefetched> for Prefetched {
+    fn from(value: interfacing::Prefetched) -> Self {
+        Self(RefCell::new(value))
+    }
+}
+
+impl Prefetched {
+    pub fn take_articles(&self) -> Option<Vec<interfacing::ArticleWithId>> {
+        self.0.borrow_mut().articles.take()
+    }
+
+    pub fn take_article(&self, public_id: &str) -> Option<interfacing::ArticleWithId> {
+        let mut prefetched = self.0.borrow_mut();
+        match &prefetched.article {
+            Some(article) if article.body().public_id == public_id => prefetched.article.take(),
+            _ => None,
+        }
+    }
+
+    pub fn take_features(&self) -> Option<interfacing::Features> {
+        self.0.borrow_mut().features.take()
+    }
+}
+
+/// Prefetched data from the context, if any was provided
+pub fn prefetched<COMP: Component>(ctx: &Context<COMP>) -> Option<PrefetchedCtx> {
+    ctx.link()
+        .context::<PrefetchedCtx>(Callback::noop())
+        .map(|(prefetched, _handle)| prefetched)
+}
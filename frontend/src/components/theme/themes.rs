@@ -97,6 +97,13 @@ impl<'a> From<RawTheme<'a>> for Theme {
 impl Themes {
     const SESSION_KEY: &str = "theme";
 
+    // the server renders the default, the remembered one is applied on hydration
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn derived() -> Self {
+        Self::default()
+    }
+
+    #[cfg(target_arch = "wasm32")]
     pub fn derived() -> Self {
         let remembered = || {
             use gloo_storage::{LocalStorage, Storage};
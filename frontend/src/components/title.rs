@@ -23,10 +23,14 @@ impl Component for PageTitle {
 
     #[allow(unused_variables)]
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let window = web_sys::window().unwrap();
-        let document = window.document().unwrap();
-        console::log!(format!("setting title: {:?}", &ctx.props().title));
-        document.set_title(&ctx.props().title);
+        // the server injects titles of pages into their head itself
+        #[cfg(target_arch = "wasm32")]
+        {
+            let window = web_sys::window().unwrap();
+            let document = window.document().unwrap();
+            console::log!(format!("setting title: {:?}", &ctx.props().title));
+            document.set_title(&ctx.props().title);
+        }
         html! {}
     }
 }
@@ -0,0 +1,18 @@
+// Browser console, silent while rendered on the server
+//
+// gloo_console calls into wasm-bindgen imports, which panic off wasm, and
+// components log from view and create.
+//
+
+#[cfg(target_arch = "wasm32")]
+pub use gloo_console::log;
+
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! log {
+    ($($arg:expr),* $(,)?) => {{
+        $( let _ = &$arg; )*
+    }};
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use log;
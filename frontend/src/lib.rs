@@ -0,0 +1,12 @@
+mod app;
+mod components;
+mod console;
+mod router;
+mod switch;
+
+#[cfg(feature = "ssr")]
+pub use app::{render, ServerApp, ServerAppProps, ServerRendered};
+pub use app::{App, AppProps};
+pub use router::Route;
+#[cfg(feature = "ssr")]
+pub use yew_router::Routable;
@@ -1,9 +1,14 @@
-mod app;
-mod components;
-mod router;
-mod static_articles;
-mod switch;
+use frontend::{App, AppProps};
 
 fn main() {
-    yew::Renderer::<app::App>::new().render();
+    // embedded by the server into pages it rendered
+    let prefetched = gloo_utils::document()
+        .get_element_by_id(interfacing::PREFETCHED_ELEMENT_ID)
+        .and_then(|element| element.text_content())
+        .and_then(|json| serde_json::from_str::<interfacing::Prefetched>(&json).ok());
+
+    match prefetched {
+        Some(prefetched) => yew::Renderer::<App>::with_props(AppProps { prefetched }).hydrate(),
+        None => yew::Renderer::<App>::new().render(),
+    };
 }
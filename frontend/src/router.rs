@@ -1,36 +1,7 @@
 use yew_router::prelude::*;
 
-// Router accepts only literals, so static_routes are used in tests
-#[derive(Clone, Routable, PartialEq)]
-pub enum Route {
-    #[at("/")]
-    Home,
-    #[at("/login")]
-    Login,
-    #[at("/md")]
-    MarkdownPreview,
-    #[at("/articles")]
-    ArticleList,
-    #[at("/articles/:public_id")]
-    ArticleViewer { public_id: String },
-    #[at("/admin/dashboard")]
-    AdminDashboard,
-    #[at("/admin/password")]
-    PasswordChange,
-    #[at("/admin/articles")]
-    CreateArticle,
-    #[at("/admin/articles/:public_id/edit")]
-    EditArticle { public_id: String },
-    #[at("/snake")]
-    Snake,
-    #[at("/i/")]
-    Ref,
-    #[not_found]
-    #[at("/404")]
-    NotFound,
-    #[at("/401")]
-    Unauthorized,
-}
+// Route, a variant per page declared in static_routes' root scope
+static_routes::frontend_route!();
 
 #[cfg(test)]
 mod tests {
@@ -39,21 +10,34 @@ mod tests {
 
     use super::Route;
 
-    fn map_to_one_another(frontend_defined_route: impl Routable, static_route: impl Get) {
-        assert_eq!(
-            frontend_defined_route.to_path(),
-            static_route.get().complete()
-        );
+    // the variant, as fields follow it
+    fn variant(route: &Route) -> String {
+        format!("{route:?}")
+            .split([' ', '{'])
+            .next()
+            .unwrap()
+            .to_owned()
     }
 
     #[test]
     fn test_local_routes_map_to_static_routes() {
-        let routes = routes().root;
+        let pages = routes()
+            .declared()
+            .into_iter()
+            .filter(|route| route.page.is_some())
+            .collect::<Vec<_>>();
 
-        map_to_one_another(Route::Home, routes.home);
-        map_to_one_another(Route::Login, routes.login);
-        map_to_one_another(Route::AdminDashboard, routes.admin.dashboard);
-        map_to_one_another(Route::PasswordChange, routes.admin.password);
-        map_to_one_another(Route::CreateArticle, routes.admin.articles);
+        for page in &pages {
+            let route = Route::recognize(&page.pattern)
+                .unwrap_or_else(|| panic!("{} is not routed", page.pattern));
+            assert_eq!(Some(variant(&route).as_str()), page.page);
+            assert_eq!(route.to_path(), page.pattern);
+        }
+        for pattern in Route::routes() {
+            assert!(
+                pages.iter().any(|page| page.pattern == pattern),
+                "{pattern} is not declared in static_routes"
+            );
+        }
     }
 }
@@ -1,19 +1,12 @@
 use crate::router::Route;
 
-use crate::static_articles::static_articles;
+use interfacing::static_articles;
 use yew::prelude::*;
 
 pub fn switch(routes: Route) -> Html {
     use crate::components::*;
     use admin::WithSession;
 
-    let path = yew_router::Routable::to_path(&routes);
-    let status: u16 = match routes.clone() {
-        Route::NotFound => 404,
-        Route::Unauthorized => 401,
-        _ => 200,
-    };
-
     let article_list = html! {
         <>
             <Header/>
@@ -23,8 +16,16 @@ pub fn switch(routes: Route) -> Html {
         </>
     };
 
+    // not a visit when rendered on the server
+    #[cfg(target_arch = "wasm32")]
     {
         use crate::components::imports::*;
+        let path = yew_router::Routable::to_path(&routes);
+        let status: u16 = match routes.clone() {
+            Route::NotFound => 404,
+            Route::Unauthorized => 401,
+            _ => 200,
+        };
         let params = web_sys::window().unwrap().location().search().unwrap();
         // TODO makes duplicate requests
         wasm_bindgen_futures::spawn_local(async move {
@@ -42,8 +43,10 @@ pub fn switch(routes: Route) -> Html {
         });
     }
 
+    let not_found = html! { <Error msg={"Not Found"} code=404 /> };
+
     match routes {
-        Route::NotFound => html! { <Error msg={"Not Found"} code=404 /> },
+        Route::NotFound => not_found,
         Route::Unauthorized => html! { <Error msg={"Unauthorized"} code=401 /> },
         Route::Home => article_list.clone(),
         Route::Ref => {
@@ -67,6 +70,18 @@ pub fn switch(routes: Route) -> Html {
         Route::CreateArticle => {
             html! {<WithSession><ArticleEditor mode={ ArticleEditorMode::Create }/></WithSession>}
         }
+        Route::AuditLog => {
+            html! {<WithSession><admin::AuditLog/></WithSession>}
+        }
+        Route::Sessions => {
+            html! {<WithSession><admin::Sessions/></WithSession>}
+        }
+        Route::Features => {
+            html! {<WithSession><admin::Features/></WithSession>}
+        }
+        Route::Media => {
+            html! {<WithSession><admin::Media/></WithSession>}
+        }
         Route::EditArticle { public_id } => {
             html! {<WithSession><EditArticle {public_id}/></WithSession>}
         }
@@ -75,7 +90,10 @@ pub fn switch(routes: Route) -> Html {
         }
         Route::Snake => {
             html! {
-                <Snake/>
+                <Feature name={interfacing::SNAKE_GAME_FEATURE} fallback={not_found.clone()}>
+                    // canvas, timers and key listeners are the browser's
+                    <ClientOnly><Snake/></ClientOnly>
+                </Feature>
             }
         }
         Route::ArticleViewer { public_id } => {
@@ -99,7 +117,9 @@ pub fn switch(routes: Route) -> Html {
                 }
                 _ if public_id == static_articles().snake.public_id => {
                     html! {
-                        <Snake/>
+                        <Feature name={interfacing::SNAKE_GAME_FEATURE} fallback={not_found.clone()}>
+                            <ClientOnly><Snake/></ClientOnly>
+                        </Feature>
                     }
                 }
                 _ => html! {